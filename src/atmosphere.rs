@@ -0,0 +1,216 @@
+use crate::rasterizer::BlendMode;
+use crate::shaders::{PlanetShader, ShaderColor, ShaderUniforms};
+use crate::vector::Vector3;
+
+// Muestras del rayo de vista dentro de la capa y del rayo hacia cada luz
+const VIEW_SAMPLES: usize = 10;
+const LIGHT_SAMPLES: usize = 4;
+
+// Altura de escala de Rayleigh y de Mie, como fracción de `height`: la
+// densidad cae a 1/e a esa altura sobre la superficie
+const RAYLEIGH_SCALE_HEIGHT: f32 = 0.25;
+const MIE_SCALE_HEIGHT: f32 = 0.1;
+
+// Mie también absorbe un poco, no solo dispersa
+const MIE_EXTINCTION: f32 = 1.1;
+
+// Capa de atmósfera de un cuerpo: una esfera algo mayor que el planeta
+#[derive(Debug, Clone, Copy)]
+pub struct Atmosphere {
+    // Espesor de la capa en unidades de mundo
+    pub height: f32,
+    // Multiplicador global de la densidad (0 = sin atmósfera)
+    pub density: f32,
+    // Coeficientes de Rayleigh por canal, por unidad de `height`; su
+    // proporción da el color del cielo (más azul = más dispersión en azul)
+    pub rayleigh: ShaderColor,
+    // Coeficiente de Mie (aerosoles, gris) y su anisotropía hacia adelante
+    pub mie: f32,
+    pub mie_g: f32,
+}
+
+impl Atmosphere {
+    // Cielo azul tipo Tierra: proporciones de Rayleigh 5.8 : 13.5 : 33.1
+    pub fn earth_like(height: f32) -> Self {
+        Atmosphere {
+            height,
+            density: 1.0,
+            rayleigh: ShaderColor::new(0.29, 0.68, 1.66, 1.0),
+            mie: 0.4,
+            mie_g: 0.76,
+        }
+    }
+
+    // Atmósfera fina y polvorienta: dispersa más en rojo que en azul
+    pub fn dusty(height: f32) -> Self {
+        Atmosphere {
+            height,
+            density: 0.6,
+            rayleigh: ShaderColor::new(1.6, 1.0, 0.6, 1.0),
+            mie: 0.8,
+            mie_g: 0.65,
+        }
+    }
+}
+
+// Dispersión simple (Rayleigh + Mie) de la luz de todas las luces a lo largo
+// del rayo de vista dentro de la capa. Se dibuja sobre la esfera exterior con
+// mezcla premultiplicada: alfa = opacidad de la capa, color = luz dispersada
+pub struct AtmosphereShader {
+    pub atmosphere: Atmosphere,
+    // Radio del planeta (mundo); la capa va de ahí a planet_radius + height
+    pub planet_radius: f32,
+    // Fracción del radio en la que la silueta real del mesh (irregular o
+    // facetada) puede quedar por dentro de la esfera analítica
+    pub limb_softness: f32,
+}
+
+// Intersecciones (t cercano, t lejano) del rayo origin + dir * t con una esfera
+fn ray_sphere(origin: Vector3, dir: Vector3, center: Vector3, radius: f32) -> Option<(f32, f32)> {
+    let oc = origin - center;
+    let b = oc.dot(&dir);
+    let c = oc.dot(&oc) - radius * radius;
+    let disc = b * b - c;
+    if disc < 0.0 {
+        return None;
+    }
+    let sqrt_disc = disc.sqrt();
+    Some((-b - sqrt_disc, -b + sqrt_disc))
+}
+
+// Fases normalizadas a media 1 sobre la esfera (sin el 1/4π)
+fn rayleigh_phase(cos_theta: f32) -> f32 {
+    0.75 * (1.0 + cos_theta * cos_theta)
+}
+
+// Henyey-Greenstein
+fn mie_phase(cos_theta: f32, g: f32) -> f32 {
+    let denom = (1.0 + g * g - 2.0 * g * cos_theta).max(1e-4);
+    (1.0 - g * g) / (denom * denom.sqrt())
+}
+
+impl AtmosphereShader {
+    // Densidades (Rayleigh, Mie) a la altura del punto `p` sobre la superficie
+    fn densities(&self, p: Vector3, center: Vector3) -> (f32, f32) {
+        let altitude = ((p - center).length() - self.planet_radius).max(0.0) / self.atmosphere.height;
+        ((-altitude / RAYLEIGH_SCALE_HEIGHT).exp(), (-altitude / MIE_SCALE_HEIGHT).exp())
+    }
+
+    // Profundidad óptica (Rayleigh, Mie) desde `p` hacia la luz hasta salir de
+    // la capa; None si el planeta tapa la luz
+    fn light_depth(&self, p: Vector3, to_light: Vector3, center: Vector3, outer: f32) -> Option<(f32, f32)> {
+        if let Some((near, _)) = ray_sphere(p, to_light, center, self.planet_radius) {
+            if near > 0.0 {
+                return None;
+            }
+        }
+        let (_, exit) = ray_sphere(p, to_light, center, outer)?;
+        let ds = exit.max(0.0) / LIGHT_SAMPLES as f32;
+        let (mut depth_r, mut depth_m) = (0.0, 0.0);
+        for i in 0..LIGHT_SAMPLES {
+            let (r, m) = self.densities(p + to_light * ((i as f32 + 0.5) * ds), center);
+            depth_r += r * ds;
+            depth_m += m * ds;
+        }
+        let scale = 1.0 / self.atmosphere.height;
+        Some((depth_r * scale, depth_m * scale))
+    }
+}
+
+impl PlanetShader for AtmosphereShader {
+    fn vertex_shader(&self, position: Vector3, normal: Vector3, _uv: (f32, f32), _uniforms: &ShaderUniforms) -> (Vector3, Vector3) {
+        (position, normal)
+    }
+
+    fn blend_mode(&self) -> BlendMode {
+        BlendMode::Premultiplied
+    }
+
+    fn fragment_shader(&self, position: Vector3, _normal: Vector3, _uv: (f32, f32), uniforms: &ShaderUniforms) -> ShaderColor {
+        let atmosphere = &self.atmosphere;
+        let transparent = ShaderColor::new(0.0, 0.0, 0.0, 0.0);
+        if atmosphere.density <= 0.0 || atmosphere.height <= 0.0 {
+            return transparent;
+        }
+
+        // Tramo del rayo de vista dentro de la capa (esferas analíticas, no el
+        // mesh facetado): desde la entrada hasta la salida o hasta el planeta
+        let center = uniforms.world_position(Vector3::zero());
+        let outer = self.planet_radius + atmosphere.height;
+        let eye = uniforms.camera_position;
+        let dir = (uniforms.world_position(position) - eye).normalize();
+        let Some((enter, exit)) = ray_sphere(eye, dir, center, outer) else {
+            return transparent;
+        };
+        let start = enter.max(0.0);
+        // Cerca del borde del planeta el tramo pasa gradualmente de terminar en
+        // la superficie a atravesar la capa entera; un corte brusco dejaría un
+        // anillo donde la silueta del mesh no coincide con la esfera
+        let offset = eye - center;
+        let closest = (offset.dot(&offset) - offset.dot(&dir).powi(2)).max(0.0).sqrt();
+        let inner = self.planet_radius * (1.0 - self.limb_softness);
+        let end = match ray_sphere(eye, dir, center, self.planet_radius) {
+            Some((hit, _)) if hit > 0.0 => {
+                let t = ((closest - inner) / (self.planet_radius - inner).max(1e-6)).clamp(0.0, 1.0);
+                exit.min(hit + (exit - hit) * t * t * (3.0 - 2.0 * t))
+            }
+            _ => exit,
+        };
+        if end <= start {
+            return transparent;
+        }
+
+        let beta_r = [atmosphere.rayleigh.r, atmosphere.rayleigh.g, atmosphere.rayleigh.b];
+        let beta_m = atmosphere.mie;
+        let ds = (end - start) / VIEW_SAMPLES as f32;
+        let ds_scaled = ds / atmosphere.height;
+
+        let (mut view_r, mut view_m) = (0.0, 0.0);
+        let mut sum_r = [0.0; 3];
+        let mut sum_m = [0.0; 3];
+        for i in 0..VIEW_SAMPLES {
+            let p = eye + dir * (start + (i as f32 + 0.5) * ds);
+            let (density_r, density_m) = self.densities(p, center);
+            view_r += density_r * ds_scaled * 0.5;
+            view_m += density_m * ds_scaled * 0.5;
+
+            for (light_index, light) in uniforms.lights.iter().enumerate() {
+                let Some((to_light, radiance)) = light.illuminate(p) else {
+                    continue;
+                };
+                let Some((light_r, light_m)) = self.light_depth(p, to_light, center, outer) else {
+                    continue;
+                };
+                // Sombra de otros cuerpos (una luna que eclipsa al planeta
+                // también oscurece su atmósfera)
+                let visibility = uniforms.visibility(light_index, p, (p - center).normalize());
+                if visibility <= 0.0 {
+                    continue;
+                }
+                let cos_theta = dir.dot(&to_light);
+                let phase_r = rayleigh_phase(cos_theta) * density_r * ds_scaled;
+                let phase_m = mie_phase(cos_theta, atmosphere.mie_g) * density_m * ds_scaled;
+                let radiance = [radiance.r * visibility, radiance.g * visibility, radiance.b * visibility];
+                for k in 0..3 {
+                    let optical = beta_r[k] * (view_r + light_r) + beta_m * MIE_EXTINCTION * (view_m + light_m);
+                    let transmittance = (-atmosphere.density * optical).exp();
+                    sum_r[k] += transmittance * phase_r * radiance[k];
+                    sum_m[k] += transmittance * phase_m * radiance[k];
+                }
+            }
+
+            view_r += density_r * ds_scaled * 0.5;
+            view_m += density_m * ds_scaled * 0.5;
+        }
+
+        // Opacidad = 1 - transmitancia media del tramo; el color ya es la luz
+        // dispersada completa (premultiplicada), independiente de la opacidad
+        let mut scattered = [0.0; 3];
+        let mut transmittance = 0.0;
+        for k in 0..3 {
+            scattered[k] = atmosphere.density * (beta_r[k] * sum_r[k] + beta_m * sum_m[k]);
+            transmittance += (-atmosphere.density * (beta_r[k] * view_r + beta_m * MIE_EXTINCTION * view_m)).exp() / 3.0;
+        }
+        ShaderColor::new(scattered[0], scattered[1], scattered[2], (1.0 - transmittance).clamp(0.0, 1.0))
+    }
+}
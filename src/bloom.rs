@@ -0,0 +1,176 @@
+use crate::post::PostEffect;
+use crate::rasterizer::Framebuffer;
+
+// Bloom sobre el buffer HDR: se extraen los píxeles que pasan el umbral, se
+// difuminan en una pirámide de resoluciones (cada nivel a la mitad, blur
+// gaussiano separable) y se suman de vuelta a la imagen antes del tone mapping
+#[derive(Debug, Clone, Copy)]
+pub struct Bloom {
+    // Luminancia a partir de la cual un píxel "brilla"
+    pub threshold: f32,
+    // Alcance aproximado del halo en píxeles de la imagen completa
+    pub radius: f32,
+    // Cantidad de halo sumada a la imagen (0 = sin bloom)
+    pub intensity: f32,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Bloom { threshold: 0.5, radius: 48.0, intensity: 1.0 }
+    }
+}
+
+// Sigma (en píxeles de cada nivel) del blur de la pirámide
+const LEVEL_SIGMA: f32 = 1.5;
+
+// Imagen RGB f32 auxiliar para los niveles de la pirámide
+struct Image {
+    width: usize,
+    height: usize,
+    data: Vec<[f32; 3]>,
+}
+
+impl Image {
+    fn new(width: usize, height: usize) -> Self {
+        Image { width, height, data: vec![[0.0; 3]; width * height] }
+    }
+
+    fn at(&self, x: isize, y: isize) -> [f32; 3] {
+        let x = x.clamp(0, self.width as isize - 1) as usize;
+        let y = y.clamp(0, self.height as isize - 1) as usize;
+        self.data[y * self.width + x]
+    }
+
+    // Muestreo bilineal en coordenadas de píxel (centros en .5)
+    fn sample(&self, x: f32, y: f32) -> [f32; 3] {
+        let (fx, fy) = (x - 0.5, y - 0.5);
+        let (x0, y0) = (fx.floor() as isize, fy.floor() as isize);
+        let (tx, ty) = (fx - fx.floor(), fy - fy.floor());
+        let mut out = [0.0; 3];
+        for (dx, dy, w) in [
+            (0, 0, (1.0 - tx) * (1.0 - ty)),
+            (1, 0, tx * (1.0 - ty)),
+            (0, 1, (1.0 - tx) * ty),
+            (1, 1, tx * ty),
+        ] {
+            let c = self.at(x0 + dx, y0 + dy);
+            for k in 0..3 {
+                out[k] += c[k] * w;
+            }
+        }
+        out
+    }
+
+    // Mitad de resolución promediando bloques 2x2
+    fn downsample(&self) -> Image {
+        let mut out = Image::new(self.width.div_ceil(2), self.height.div_ceil(2));
+        for y in 0..out.height {
+            for x in 0..out.width {
+                let mut sum = [0.0; 3];
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let c = self.at((x * 2 + dx) as isize, (y * 2 + dy) as isize);
+                    for k in 0..3 {
+                        sum[k] += c[k] * 0.25;
+                    }
+                }
+                out.data[y * out.width + x] = sum;
+            }
+        }
+        out
+    }
+
+    // Blur gaussiano separable (horizontal y luego vertical)
+    fn blur(&mut self, sigma: f32) {
+        let radius = (sigma * 3.0).ceil() as isize;
+        let weights: Vec<f32> = (-radius..=radius).map(|i| (-(i * i) as f32 / (2.0 * sigma * sigma)).exp()).collect();
+        let total: f32 = weights.iter().sum();
+        let weights: Vec<f32> = weights.iter().map(|w| w / total).collect();
+
+        for horizontal in [true, false] {
+            let mut out = Image::new(self.width, self.height);
+            for y in 0..self.height as isize {
+                for x in 0..self.width as isize {
+                    let mut sum = [0.0; 3];
+                    for (i, w) in weights.iter().enumerate() {
+                        let offset = i as isize - radius;
+                        let c = if horizontal { self.at(x + offset, y) } else { self.at(x, y + offset) };
+                        for k in 0..3 {
+                            sum[k] += c[k] * w;
+                        }
+                    }
+                    out.data[y as usize * self.width + x as usize] = sum;
+                }
+            }
+            *self = out;
+        }
+    }
+
+    // Sumar `src` (de menor resolución) reescalado bilinealmente
+    fn add_upsampled(&mut self, src: &Image) {
+        let sx = src.width as f32 / self.width as f32;
+        let sy = src.height as f32 / self.height as f32;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let c = src.sample((x as f32 + 0.5) * sx, (y as f32 + 0.5) * sy);
+                let d = &mut self.data[y * self.width + x];
+                for k in 0..3 {
+                    d[k] += c[k];
+                }
+            }
+        }
+    }
+}
+
+impl PostEffect for Bloom {
+    fn name(&self) -> &'static str {
+        "bloom"
+    }
+
+    fn apply(&self, fb: &mut Framebuffer, _time: f32) {
+        if self.intensity <= 0.0 || fb.hdr_buffer.is_empty() {
+            return;
+        }
+
+        // Paso de brillo con umbral suave: solo sobrevive lo que excede `threshold`
+        let mut bright = Image::new(fb.width, fb.height);
+        for (out, c) in bright.data.iter_mut().zip(&fb.hdr_buffer) {
+            let luminance = 0.2126 * c[0] + 0.7152 * c[1] + 0.0722 * c[2];
+            let excess = (luminance - self.threshold).max(0.0) / luminance.max(1e-4);
+            *out = [c[0] * excess, c[1] * excess, c[2] * excess];
+        }
+
+        // Pirámide: cada nivel duplica el alcance del blur; tantos niveles como
+        // hagan falta para cubrir `radius` (sin bajar de 1 píxel)
+        let levels = (self.radius.max(2.0).log2().ceil() as usize).clamp(1, 10);
+        let mut pyramid = vec![bright.downsample()];
+        while pyramid.len() < levels {
+            let last = pyramid.last().unwrap();
+            if last.width <= 1 || last.height <= 1 {
+                break;
+            }
+            let next = last.downsample();
+            pyramid.push(next);
+        }
+        for level in &mut pyramid {
+            level.blur(LEVEL_SIGMA);
+        }
+
+        // Recomponer de la más gruesa a la más fina
+        while pyramid.len() > 1 {
+            let coarse = pyramid.pop().unwrap();
+            pyramid.last_mut().unwrap().add_upsampled(&coarse);
+        }
+        let halo = &pyramid[0];
+        let sx = halo.width as f32 / fb.width as f32;
+        let sy = halo.height as f32 / fb.height as f32;
+        for y in 0..fb.height {
+            for x in 0..fb.width {
+                let c = halo.sample((x as f32 + 0.5) * sx, (y as f32 + 0.5) * sy);
+                let d = &mut fb.hdr_buffer[y * fb.width + x];
+                for k in 0..3 {
+                    d[k] += c[k] * self.intensity;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use crate::image_io::ImageFormat;
+use crate::tonemap::ToneMapper;
+use crate::post::EFFECT_NAMES;
+use crate::renderer::{HEIGHT, WIDTH};
+use crate::scene::FIXED_DT;
+use crate::sphere::SphereMesh;
+
+// Posición por defecto de los modelos de --model: entre las órbitas del
+// volcánico y el oceánico, algo por encima del plano orbital
+pub const DEFAULT_MODEL_POSITION: [f32; 3] = [0.0, 0.6, 2.4];
+
+// Modelo de --model y dónde ponerlo en la escena
+#[derive(Debug, Clone)]
+pub struct ModelSpec {
+    pub path: String,
+    pub position: [f32; 3],
+    pub scale: f32,
+}
+
+// Parametros de linea de comandos (sin dependencias externas)
+pub struct Config {
+    // Hilos del rasterizador; None = usar todos los nucleos disponibles
+    pub threads: Option<usize>,
+    // Render offline sin ventana
+    pub headless: bool,
+    pub width: usize,
+    pub height: usize,
+    pub frames: usize,
+    pub dt: f32,
+    // Carpeta para la secuencia de imágenes (headless); None = no escribirla
+    pub output_dir: Option<String>,
+    pub format: ImageFormat,
+    // Grabar animación GIF/APNG (por extensión del archivo)
+    pub record: Option<String>,
+    // Ajustar la duración para que la animación repita sin salto
+    pub seamless_loop: bool,
+    // Periodo (s) en que repite la animacion de los shaders; None = por defecto
+    pub loop_period: Option<f32>,
+    // Añadir las luces de relleno y contraluz
+    pub cinematic_lights: bool,
+    // Desactivar el shadow map de la estrella
+    pub no_shadows: bool,
+    // Tone mapping del buffer HDR; None = por defecto (ACES, exposición 1)
+    pub tone_mapper: Option<ToneMapper>,
+    pub exposure: Option<f32>,
+    // Parámetros del bloom; None = por defecto
+    pub bloom_threshold: Option<f32>,
+    pub bloom_radius: Option<f32>,
+    pub bloom_intensity: Option<f32>,
+    pub no_corona: bool,
+    pub no_atmospheres: bool,
+    // Parámetros de la corona; None = por defecto
+    pub corona_radius: Option<f32>,
+    pub corona_falloff: Option<f32>,
+    pub corona_frequency: Option<f32>,
+    // Efectos de post-proceso activos y su orden; None = bloom y tone mapping
+    pub post: Option<Vec<String>>,
+    // Archivo .cube para el efecto de color grading
+    pub lut: Option<String>,
+    // Stream YUV4MPEG2 a archivo o a stdout ("-")
+    pub y4m: Option<String>,
+    // Esfera procedimental en lugar de sphere.obj; None = cargar el OBJ
+    pub sphere: Option<SphereMesh>,
+    // Nivel de detalle de los cuerpos según su tamaño en pantalla
+    pub lod: bool,
+    // Teselado adaptativo de las superficies desplazadas (estrella, rocosos)
+    pub adaptive: bool,
+    // Modelos OBJ (con sus MTL) o glTF a dibujar junto a la estrella
+    pub models: Vec<ModelSpec>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            threads: None,
+            headless: false,
+            width: WIDTH,
+            height: HEIGHT,
+            frames: 120,
+            dt: FIXED_DT,
+            output_dir: None,
+            format: ImageFormat::Png,
+            record: None,
+            seamless_loop: false,
+            loop_period: None,
+            cinematic_lights: false,
+            no_shadows: false,
+            tone_mapper: None,
+            exposure: None,
+            bloom_threshold: None,
+            bloom_radius: None,
+            bloom_intensity: None,
+            no_corona: false,
+            no_atmospheres: false,
+            corona_radius: None,
+            corona_falloff: None,
+            corona_frequency: None,
+            post: None,
+            lut: None,
+            y4m: None,
+            sphere: None,
+            lod: false,
+            adaptive: false,
+            models: Vec::new(),
+        }
+    }
+}
+
+pub fn parse_args() -> Result<Config, String> {
+    parse_args_from(std::env::args().skip(1))
+}
+
+fn parse_args_from(mut args: impl Iterator<Item = String>) -> Result<Config, String> {
+    let mut config = Config::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threads" => {
+                let n = next_value::<usize>(&mut args, &arg)?;
+                config.threads = Some(n.max(1));
+            }
+            "--headless" => config.headless = true,
+            "--width" => config.width = next_value::<usize>(&mut args, &arg)?.max(1),
+            "--height" => config.height = next_value::<usize>(&mut args, &arg)?.max(1),
+            "--frames" => config.frames = next_value(&mut args, &arg)?,
+            "--dt" => {
+                // Con dt 0 el tiempo no avanza y --loop pediría infinitos frames
+                let dt: f32 = next_value(&mut args, &arg)?;
+                if !(dt > 0.0 && dt.is_finite()) {
+                    return Err(format!("--dt debe ser un número positivo: {}", dt));
+                }
+                config.dt = dt;
+            }
+            "--output" => config.output_dir = Some(next_value(&mut args, &arg)?),
+            "--record" => config.record = Some(next_value(&mut args, &arg)?),
+            "--loop" => config.seamless_loop = true,
+            "--loop-period" => {
+                // Sin --loop-period la animación no repite; 0 no es otra forma de decirlo
+                let period: f32 = next_value(&mut args, &arg)?;
+                if !(period > 0.0 && period.is_finite()) {
+                    return Err(format!("--loop-period debe ser un número positivo: {}", period));
+                }
+                config.loop_period = Some(period);
+            }
+            "--cinematic-lights" => config.cinematic_lights = true,
+            "--no-shadows" => config.no_shadows = true,
+            "--no-corona" => config.no_corona = true,
+            "--no-atmospheres" => config.no_atmospheres = true,
+            "--lod" => config.lod = true,
+            "--adaptive" => config.adaptive = true,
+            "--corona-radius" => config.corona_radius = Some(next_value(&mut args, &arg)?),
+            "--corona-falloff" => config.corona_falloff = Some(next_value(&mut args, &arg)?),
+            "--corona-frequency" => config.corona_frequency = Some(next_value(&mut args, &arg)?),
+            "--y4m" => config.y4m = Some(next_value(&mut args, &arg)?),
+            "--bloom-threshold" => config.bloom_threshold = Some(next_value(&mut args, &arg)?),
+            "--bloom-radius" => config.bloom_radius = Some(next_value(&mut args, &arg)?),
+            "--bloom-intensity" => config.bloom_intensity = Some(next_value(&mut args, &arg)?),
+            "--post" => {
+                let value: String = next_value(&mut args, &arg)?;
+                let names: Vec<String> = value
+                    .split(',')
+                    .map(|n| n.trim().to_string())
+                    .filter(|n| !n.is_empty())
+                    .collect();
+                if let Some(unknown) = names.iter().find(|n| !EFFECT_NAMES.contains(&n.as_str())) {
+                    return Err(format!("Efecto desconocido: {} ({})", unknown, EFFECT_NAMES.join("|")));
+                }
+                config.post = Some(names);
+            }
+            "--lut" => config.lut = Some(next_value(&mut args, &arg)?),
+            "--exposure" => config.exposure = Some(next_value(&mut args, &arg)?),
+            "--tonemap" => {
+                let value: String = next_value(&mut args, &arg)?;
+                config.tone_mapper = Some(match value.as_str() {
+                    "clamp" => ToneMapper::Clamp,
+                    "reinhard" => ToneMapper::Reinhard,
+                    "aces" => ToneMapper::Aces,
+                    other => return Err(format!("Tone mapper desconocido: {} (clamp|reinhard|aces)", other)),
+                });
+            }
+            "--sphere" => {
+                let value: String = next_value(&mut args, &arg)?;
+                let (kind, detail) = match value.split_once(':') {
+                    Some((kind, detail)) => {
+                        let detail = detail
+                            .parse::<u32>()
+                            .map_err(|_| format!("Resolucion invalida para --sphere: {}", detail))?;
+                        (kind, Some(detail))
+                    }
+                    None => (value.as_str(), None),
+                };
+                config.sphere = Some(match kind {
+                    "uv" => {
+                        let n = detail.unwrap_or(64);
+                        SphereMesh::Uv { segments: n, rings: n }
+                    }
+                    "ico" => SphereMesh::Icosphere { subdivisions: detail.unwrap_or(3) },
+                    "cube" => SphereMesh::CubeSphere { resolution: detail.unwrap_or(16) },
+                    other => return Err(format!("Esfera desconocida: {} (uv|ico|cube)", other)),
+                });
+            }
+            "--model" => {
+                let value: String = next_value(&mut args, &arg)?;
+                config.models.push(parse_model_spec(&value)?);
+            }
+            "--format" => {
+                let value: String = next_value(&mut args, &arg)?;
+                config.format = match value.as_str() {
+                    "png" => ImageFormat::Png,
+                    "ppm" => ImageFormat::Ppm,
+                    other => return Err(format!("Formato desconocido: {} (png|ppm)", other)),
+                };
+            }
+            "-h" | "--help" => {
+                print_usage();
+                std::process::exit(0);
+            }
+            other => return Err(format!("Argumento desconocido: {}", other)),
+        }
+    }
+
+    // Con --post el orden es el de la lista: --lut no puede agregar 'grade' en
+    // un lugar arbitrario (p. ej. después de gamma)
+    if config.lut.is_some() && config.post.as_ref().is_some_and(|names| !names.iter().any(|n| n == "grade")) {
+        return Err("--lut con --post requiere 'grade' en la lista (p. ej. bloom,tonemap,grade)".to_string());
+    }
+
+    // Sin otra salida, el modo headless escribe la secuencia en ./frames
+    if config.headless && config.record.is_none() && config.y4m.is_none() && config.output_dir.is_none() {
+        config.output_dir = Some("frames".to_string());
+    }
+
+    Ok(config)
+}
+
+// ARCHIVO[@X,Y,Z[@ESCALA]]
+fn parse_model_spec(value: &str) -> Result<ModelSpec, String> {
+    let mut parts = value.split('@');
+    let path = parts.next().unwrap_or("").to_string();
+    if path.is_empty() {
+        return Err("Falta el archivo en --model".to_string());
+    }
+    let position = match parts.next() {
+        Some(text) => {
+            let coords: Vec<f32> = text
+                .split(',')
+                .map(|c| c.trim().parse::<f32>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| format!("Posicion invalida para --model: {}", text))?;
+            match coords[..] {
+                [x, y, z] => [x, y, z],
+                _ => return Err(format!("La posicion de --model lleva 3 coordenadas: {}", text)),
+            }
+        }
+        None => DEFAULT_MODEL_POSITION,
+    };
+    let scale = match parts.next() {
+        Some(text) => text
+            .parse::<f32>()
+            .ok()
+            .filter(|s| *s > 0.0)
+            .ok_or_else(|| format!("Escala invalida para --model: {}", text))?,
+        None => 1.0,
+    };
+    if parts.next().is_some() {
+        return Err(format!("Valor invalido para --model: {}", value));
+    }
+    Ok(ModelSpec { path, position, scale })
+}
+
+fn next_value<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<T, String> {
+    let value = args
+        .next()
+        .ok_or_else(|| format!("Falta el valor para {}", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("Valor invalido para {}: {}", flag, value))
+}
+
+// A stderr: con --y4m - el stdout es el stream de video
+pub fn print_usage() {
+    eprintln!("Uso: reenderizar_nave [opciones]");
+    eprintln!("  --threads N     Hilos del rasterizador por tiles (1 = secuencial)");
+    eprintln!("  --headless      Renderizar sin ventana y guardar cada frame en disco");
+    eprintln!("  --width W       Ancho en pixeles (headless, por defecto {})", WIDTH);
+    eprintln!("  --height H      Alto en pixeles (headless, por defecto {})", HEIGHT);
+    eprintln!("  --frames N      Numero de frames a renderizar (headless, por defecto 120)");
+    eprintln!("  --dt S          Paso de tiempo fijo en segundos (por defecto {})", FIXED_DT);
+    eprintln!("  --output DIR    Carpeta para la secuencia de imagenes (por defecto ./frames)");
+    eprintln!("  --format F      Formato de imagen: png | ppm (por defecto png)");
+    eprintln!("  --record FILE   Grabar animacion: .gif (paleta cuantizada) o .png/.apng");
+    eprintln!("                  (la paleta del GIF sale del primer frame: los colores que aparecen");
+    eprintln!("                  despues se aproximan; para colores exactos usar .png)");
+    eprintln!("  --loop          Grabar exactamente un ciclo de la animacion (loop sin salto)");
+    eprintln!("  --loop-period S Repetir la animacion cada S segundos (por defecto no repite;");
+    eprintln!("                  con --loop, un ciclo del pulso de la estrella)");
+    eprintln!("  --cinematic-lights  Agregar luz de relleno y foco de contraluz");
+    eprintln!("  --no-shadows    Desactivar las sombras (eclipses) de la estrella");
+    eprintln!("  --no-corona     No dibujar la corona de la estrella");
+    eprintln!("  --no-atmospheres  No dibujar las atmosferas de los planetas");
+    eprintln!("  --corona-radius R     Alcance de la corona en radios de la estrella (por defecto 2.6)");
+    eprintln!("  --corona-falloff H    Altura en radios donde la corona cae a 1/e (por defecto 0.3)");
+    eprintln!("  --corona-frequency F  Cantidad de filamentos de la corona (por defecto 6)");
+    eprintln!("  --tonemap T     Curva HDR -> pantalla: clamp | reinhard | aces (por defecto aces)");
+    eprintln!("  --exposure E    Exposicion antes del tone mapping (por defecto 1.0)");
+    eprintln!("  --bloom-threshold L  Luminancia minima que genera halo (por defecto 0.5)");
+    eprintln!("  --bloom-radius PX    Alcance del halo en pixeles (por defecto 48)");
+    eprintln!("  --bloom-intensity I  Intensidad del halo, 0 = sin bloom (por defecto 1.0)");
+    eprintln!("  --post LISTA    Efectos de post-proceso en orden, separados por comas:");
+    eprintln!("                  {} (por defecto bloom,tonemap)", EFFECT_NAMES.join(","));
+    eprintln!("  --lut FILE      LUT 3D .cube para el color grading (activa 'grade'; con --post,");
+    eprintln!("                  la lista tiene que incluir 'grade')");
+    eprintln!("  --sphere T[:N]  Usar una esfera procedimental en vez de sphere.obj:");
+    eprintln!("                  uv[:segmentos] | ico[:subdivisiones] | cube[:quads por arista]");
+    eprintln!("                  (por defecto uv:64, ico:3, cube:16)");
+    eprintln!("  --lod           Nivel de detalle por cuerpo segun su tamano en pantalla (niveles");
+    eprintln!("                  de la esfera de --sphere, o de una icosfera si no se indica)");
+    eprintln!("  --adaptive      Teselado adaptativo (quadtree por cara) para la estrella y los");
+    eprintln!("                  planetas rocosos, mas denso cerca de la camara y en la silueta");
+    eprintln!("  --model F[@X,Y,Z[@S]]  Cargar un OBJ (con su MTL) o un .gltf/.glb junto a la");
+    eprintln!("                  estrella, en la posicion X,Y,Z (por defecto {:?}) y con", DEFAULT_MODEL_POSITION);
+    eprintln!("                  escala S; se puede repetir para varios modelos");
+    eprintln!("  --y4m FILE      Stream YUV4MPEG2 (4:2:0) a archivo, o a stdout con '-'");
+    eprintln!("  -h, --help      Mostrar esta ayuda");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Config, String> {
+        parse_args_from(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn dt_must_be_positive_and_finite() {
+        assert_eq!(parse(&["--dt", "0.25"]).unwrap().dt, 0.25);
+        for dt in ["0", "-0.5", "NaN", "inf", "-inf"] {
+            assert!(parse(&["--headless", "--loop", "--dt", dt]).is_err(), "--dt {}", dt);
+        }
+        assert!(parse(&["--dt", "rápido"]).is_err());
+    }
+
+    #[test]
+    fn loop_period_must_be_positive_even_without_loop() {
+        assert_eq!(parse(&["--loop-period", "4"]).unwrap().loop_period, Some(4.0));
+        for period in ["0", "-3", "NaN", "inf"] {
+            assert!(parse(&["--loop-period", period]).is_err(), "--loop-period {}", period);
+            assert!(parse(&["--loop", "--loop-period", period]).is_err(), "--loop --loop-period {}", period);
+        }
+    }
+}
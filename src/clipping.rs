@@ -0,0 +1,190 @@
+use crate::vector::Vector3;
+use nalgebra::Vector4;
+
+// Guard-band: los triangulos se recortan contra un frustum 4 veces mas ancho que
+// la pantalla en X/Y. Lo que cae entre la pantalla y el guard-band lo descarta
+// el bounding box del rasterizador, asi se evita recortar casi todo.
+pub const GUARD_BAND: f32 = 4.0;
+
+// Vertice en clip space con los atributos que se interpolan al recortar
+#[derive(Debug, Clone, Copy)]
+pub struct ClipVertex {
+    pub clip: Vector4<f32>,
+    pub position: Vector3,
+    pub normal: Vector3,
+    pub uv: (f32, f32),
+}
+
+impl ClipVertex {
+    fn lerp(&self, other: &ClipVertex, t: f32) -> ClipVertex {
+        ClipVertex {
+            clip: self.clip + (other.clip - self.clip) * t,
+            position: self.position + (other.position - self.position) * t,
+            normal: self.normal + (other.normal - self.normal) * t,
+            uv: (
+                self.uv.0 + (other.uv.0 - self.uv.0) * t,
+                self.uv.1 + (other.uv.1 - self.uv.1) * t,
+            ),
+        }
+    }
+}
+
+// Planos de recorte en coordenadas homogeneas (distancia >= 0 => dentro)
+#[derive(Debug, Clone, Copy)]
+enum ClipPlane {
+    Near,
+    Far,
+    Left,
+    Right,
+    Bottom,
+    Top,
+}
+
+const CLIP_PLANES: [ClipPlane; 6] = [
+    ClipPlane::Near,
+    ClipPlane::Far,
+    ClipPlane::Left,
+    ClipPlane::Right,
+    ClipPlane::Bottom,
+    ClipPlane::Top,
+];
+
+impl ClipPlane {
+    fn distance(&self, c: &Vector4<f32>) -> f32 {
+        match self {
+            ClipPlane::Near => c.z + c.w,
+            ClipPlane::Far => c.w - c.z,
+            ClipPlane::Left => GUARD_BAND * c.w + c.x,
+            ClipPlane::Right => GUARD_BAND * c.w - c.x,
+            ClipPlane::Bottom => GUARD_BAND * c.w + c.y,
+            ClipPlane::Top => GUARD_BAND * c.w - c.y,
+        }
+    }
+}
+
+// Recorta un triangulo (Sutherland-Hodgman) contra near/far y el guard-band.
+// Deja en `polygon` el poligono convexo resultante (vacio si queda fuera);
+// el llamador lo re-triangula como abanico desde polygon[0].
+pub fn clip_triangle(triangle: [ClipVertex; 3], polygon: &mut Vec<ClipVertex>) {
+    polygon.clear();
+
+    // Caso rapido: completamente dentro o completamente fuera de algun plano
+    let mut all_inside = true;
+    for plane in CLIP_PLANES {
+        let d = triangle.map(|v| plane.distance(&v.clip));
+        if d.iter().all(|&x| x < 0.0) {
+            return;
+        }
+        if d.iter().any(|&x| x < 0.0) {
+            all_inside = false;
+        }
+    }
+
+    polygon.extend_from_slice(&triangle);
+    if all_inside {
+        return;
+    }
+
+    let mut input: Vec<ClipVertex> = Vec::with_capacity(9);
+    for plane in CLIP_PLANES {
+        std::mem::swap(&mut input, polygon);
+        polygon.clear();
+
+        let count = input.len();
+        for i in 0..count {
+            let current = &input[i];
+            let next = &input[(i + 1) % count];
+            let d_current = plane.distance(&current.clip);
+            let d_next = plane.distance(&next.clip);
+
+            if d_current >= 0.0 {
+                polygon.push(*current);
+            }
+            // La arista cruza el plano: agregar el punto de interseccion
+            if (d_current >= 0.0) != (d_next >= 0.0) {
+                let t = d_current / (d_current - d_next);
+                polygon.push(current.lerp(next, t));
+            }
+        }
+
+        if polygon.len() < 3 {
+            polygon.clear();
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(x: f32, y: f32, z: f32, w: f32) -> ClipVertex {
+        ClipVertex { clip: Vector4::new(x, y, z, w), position: Vector3::new(x, y, z), normal: Vector3::zero(), uv: (x, y) }
+    }
+
+    fn clip(triangle: [ClipVertex; 3]) -> Vec<ClipVertex> {
+        let mut polygon = Vec::new();
+        clip_triangle(triangle, &mut polygon);
+        polygon
+    }
+
+    // Todos los vértices del resultado dentro de near/far y del guard-band
+    fn assert_inside(polygon: &[ClipVertex]) {
+        for v in polygon {
+            for plane in CLIP_PLANES {
+                assert!(plane.distance(&v.clip) >= -1e-4, "{:?} fuera de {:?}", v.clip, plane);
+            }
+        }
+    }
+
+    #[test]
+    fn triangle_inside_is_untouched() {
+        let polygon = clip([vertex(0.0, 0.0, 0.0, 1.0), vertex(0.5, 0.0, 0.0, 1.0), vertex(0.0, 0.5, 0.0, 1.0)]);
+        assert_eq!(polygon.len(), 3);
+        assert_eq!(polygon[1].clip, Vector4::new(0.5, 0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn one_vertex_behind_near_gives_a_quad() {
+        // z + w < 0 solo en el primer vértice
+        let polygon = clip([vertex(0.0, 0.0, -2.0, 1.0), vertex(0.5, 0.0, 0.0, 1.0), vertex(0.0, 0.5, 0.0, 1.0)]);
+        assert_eq!(polygon.len(), 4);
+        assert_inside(&polygon);
+        // Los dos puntos nuevos quedan sobre el plano near, con los atributos interpolados
+        let on_near: Vec<&ClipVertex> = polygon.iter().filter(|v| (v.clip.z + v.clip.w).abs() < 1e-5).collect();
+        assert_eq!(on_near.len(), 2);
+        assert!(on_near.iter().any(|v| (v.uv.0 - 0.25).abs() < 1e-5 && (v.position.z + 1.0).abs() < 1e-5));
+    }
+
+    #[test]
+    fn two_vertices_behind_near_give_a_triangle() {
+        let polygon = clip([vertex(0.0, 0.0, 0.0, 1.0), vertex(0.5, 0.0, -3.0, 1.0), vertex(0.0, 0.5, -3.0, 1.0)]);
+        assert_eq!(polygon.len(), 3);
+        assert_inside(&polygon);
+        assert_eq!(polygon[0].clip, Vector4::new(0.0, 0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn triangle_fully_behind_is_dropped() {
+        let polygon = clip([vertex(0.0, 0.0, -2.0, 1.0), vertex(0.5, 0.0, -3.0, 1.0), vertex(0.0, 0.5, -4.0, 1.0)]);
+        assert!(polygon.is_empty());
+    }
+
+    #[test]
+    fn guard_band_trims_only_past_the_band() {
+        // Fuera de la pantalla (|x| > w) pero dentro del guard-band: no se recorta
+        let polygon = clip([vertex(-3.0, 0.0, 0.0, 1.0), vertex(3.0, 0.0, 0.0, 1.0), vertex(0.0, 3.0, 0.0, 1.0)]);
+        assert_eq!(polygon.len(), 3);
+
+        // Un vértice más allá de la banda derecha: se corta en x = GUARD_BAND * w
+        let polygon = clip([vertex(0.0, 0.0, 0.0, 1.0), vertex(10.0, 0.0, 0.0, 1.0), vertex(0.0, 1.0, 0.0, 1.0)]);
+        assert_eq!(polygon.len(), 4);
+        assert_inside(&polygon);
+        let max_x = polygon.iter().map(|v| v.clip.x).fold(f32::MIN, f32::max);
+        assert!((max_x - GUARD_BAND).abs() < 1e-5);
+
+        // Completamente fuera de la banda
+        let polygon = clip([vertex(5.0, 0.0, 0.0, 1.0), vertex(6.0, 0.0, 0.0, 1.0), vertex(5.0, 1.0, 0.0, 1.0)]);
+        assert!(polygon.is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+use crate::rasterizer::{BlendMode, Framebuffer};
+use crate::renderer::{to_screen_coords, transform_vertex};
+use crate::shaders::{fbm_animated, ShaderColor, ShaderUniforms};
+use crate::vector::Vector3;
+use nalgebra::Matrix4;
+
+// Resolución de la tabla polar de filamentos (ángulo x altura sobre el limbo)
+const ANGLE_BINS: usize = 256;
+const HEIGHT_BINS: usize = 24;
+const OCTAVES: i32 = 4;
+
+// Corona alrededor de la estrella, en espacio de pantalla: brillo que cae con
+// la altura sobre el limbo, modulado por filamentos radiales de ruido FBM.
+// Se suma al buffer HDR (mezcla aditiva) solo donde no hay nada delante del
+// centro de la estrella, así queda detrás de la esfera y de los planetas que pasan por delante
+#[derive(Debug, Clone, Copy)]
+pub struct Corona {
+    // Alcance exterior, en radios de la estrella (1 = limbo)
+    pub radius: f32,
+    // Altura sobre el limbo (en radios) en la que el brillo cae a 1/e
+    pub falloff: f32,
+    // Cantidad aproximada de filamentos alrededor del disco
+    pub streamer_frequency: f32,
+    pub intensity: f32,
+    pub color: ShaderColor,
+    // Velocidad con la que los filamentos fluyen hacia afuera
+    pub speed: f32,
+    pub loop_period: f32,
+}
+
+impl Default for Corona {
+    fn default() -> Self {
+        Corona {
+            radius: 2.6,
+            falloff: 0.3,
+            streamer_frequency: 6.0,
+            intensity: 1.2,
+            color: ShaderColor::new(1.0, 0.72, 0.4, 1.0),
+            speed: 0.15,
+            loop_period: 0.0,
+        }
+    }
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0).max(1e-6)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+impl Corona {
+    // `center`/`star_radius` en mundo; `right` = eje X de la cámara en mundo,
+    // para medir el radio proyectado. El tiempo sale de los uniforms de la estrella
+    pub fn render(
+        &self,
+        fb: &mut Framebuffer,
+        view_projection: &Matrix4<f32>,
+        right: Vector3,
+        center: Vector3,
+        star_radius: f32,
+        uniforms: &ShaderUniforms,
+    ) {
+        if self.intensity <= 0.0 || self.radius <= 1.0 {
+            return;
+        }
+        let clip = transform_vertex(&center, view_projection);
+        if clip.3 <= 0.0 {
+            return;
+        }
+        let (width, height) = (fb.width as f32, fb.height as f32);
+        let (cx, cy, center_depth) = to_screen_coords(&clip, width, height);
+        let edge = transform_vertex(&(center + right * star_radius), view_projection);
+        if edge.3 <= 0.0 {
+            return;
+        }
+        let (ex, ey, _) = to_screen_coords(&edge, width, height);
+        let radius_px = ((ex - cx).powi(2) + (ey - cy).powi(2)).sqrt();
+        if radius_px < 0.5 {
+            return;
+        }
+
+        // Filamentos: ruido sobre un círculo (ángulo sin costura) estirado en
+        // altura, y animado en esa dirección para que fluyan hacia afuera
+        let max_height = self.radius - 1.0;
+        let mut streamers = vec![0.0; ANGLE_BINS * HEIGHT_BINS];
+        for a in 0..ANGLE_BINS {
+            let angle = a as f32 / ANGLE_BINS as f32 * std::f32::consts::TAU;
+            let (x, y) = (angle.cos() * self.streamer_frequency, angle.sin() * self.streamer_frequency);
+            for h in 0..HEIGHT_BINS {
+                let height = h as f32 / (HEIGHT_BINS - 1) as f32 * max_height;
+                let n = fbm_animated(x, y, -height, uniforms.time, self.speed, self.loop_period, OCTAVES);
+                streamers[a * HEIGHT_BINS + h] = smoothstep(0.4, 0.75, n);
+            }
+        }
+        let lookup = |angle: f32, height: f32| {
+            let fa = angle.rem_euclid(std::f32::consts::TAU) / std::f32::consts::TAU * ANGLE_BINS as f32;
+            let fh = (height / max_height).clamp(0.0, 1.0) * (HEIGHT_BINS - 1) as f32;
+            let (a0, h0) = (fa.floor() as usize % ANGLE_BINS, (fh.floor() as usize).min(HEIGHT_BINS - 2));
+            let a1 = (a0 + 1) % ANGLE_BINS;
+            let (ta, th) = (fa - fa.floor(), fh - h0 as f32);
+            let at = |a: usize, h: usize| streamers[a * HEIGHT_BINS + h];
+            let near = at(a0, h0) * (1.0 - ta) + at(a1, h0) * ta;
+            let far = at(a0, h0 + 1) * (1.0 - ta) + at(a1, h0 + 1) * ta;
+            near * (1.0 - th) + far * th
+        };
+
+        let outer_px = radius_px * self.radius;
+        let x0 = (cx - outer_px).floor().max(0.0) as usize;
+        let y0 = (cy - outer_px).floor().max(0.0) as usize;
+        let x1 = ((cx + outer_px).ceil().max(0.0) as usize).min(fb.width);
+        let y1 = ((cy + outer_px).ceil().max(0.0) as usize).min(fb.height);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+                let r = (dx * dx + dy * dy).sqrt() / radius_px;
+                if r >= self.radius {
+                    continue;
+                }
+
+                let height = (r - 1.0).max(0.0);
+                let streamer = lookup(dy.atan2(dx), height);
+                let fade = 1.0 - smoothstep(1.0 + max_height * 0.5, self.radius, r);
+                let density = (-height / self.falloff.max(1e-3)).exp() * (0.3 + 1.7 * streamer) * fade * self.intensity;
+
+                // A la profundidad del centro: el disco de la estrella o algo
+                // delante de él tapa la corona
+                let color = [self.color.r * density, self.color.g * density, self.color.b * density, 1.0];
+                fb.blend_pixel(x, y, center_depth, color, BlendMode::Additive, false);
+            }
+        }
+    }
+}
@@ -0,0 +1,614 @@
+use crate::image_io::decode_image;
+use crate::json::Json;
+use crate::material::{AlphaMode, Material, MaterialShader, Shading, Texture};
+use crate::model::SceneObject;
+use crate::shaders::ShaderColor;
+use crate::sphere::ensure_vertex_normals;
+use nalgebra::{Matrix4, Quaternion, UnitQuaternion};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+// Cabecera de .glb y tipos de sus chunks
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+// Elementos de un accessor sin bufferView (todo ceros): el count sale del
+// archivo y no hay bytes que lo acoten
+const MAX_ZERO_ACCESSOR_COUNT: usize = 1 << 24;
+
+// Primitivas con `mode` triángulos, tira o abanico; el resto (puntos, líneas) se ignora
+const MODE_TRIANGLES: usize = 4;
+const MODE_TRIANGLE_STRIP: usize = 5;
+const MODE_TRIANGLE_FAN: usize = 6;
+
+// Documento glTF ya leído: el JSON y el contenido de cada buffer
+struct Document {
+    json: Json,
+    buffers: Vec<Vec<u8>>,
+}
+
+// Cargar un .gltf (buffers embebidos como data URI o en archivos al lado) o un
+// .glb: un objeto por primitiva de cada nodo con mesh de la escena, con la
+// transformación acumulada de la jerarquía de nodos antes de `transform`
+pub fn load_gltf_objects(path: &Path, transform: Matrix4<f32>) -> Result<Vec<SceneObject>, String> {
+    let bytes = fs::read(path).map_err(|e| format!("No se pudo leer {}: {}", path.display(), e))?;
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    let document = parse_document(&bytes, base_dir).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    let mut textures: Vec<Option<Option<Arc<Texture>>>> = vec![None; document.json.get("images").map_or(0, |i| i.as_array().len())];
+    let materials: Vec<MaterialShader> = document
+        .json
+        .get("materials")
+        .map_or(&[][..], Json::as_array)
+        .iter()
+        .map(|material| MaterialShader::new(document.material(material, base_dir, &mut textures)))
+        .collect();
+    let fallback = MaterialShader::new(Material { shading: Shading::MetallicRoughness { metallic: 1.0, roughness: 1.0 }, ..Material::default() });
+
+    // Nodos raíz de la escena por defecto (o de la primera); sin escenas, los
+    // nodos que no son hijos de ningún otro
+    let nodes = document.json.get("nodes").map_or(&[][..], Json::as_array);
+    let scenes = document.json.get("scenes").map_or(&[][..], Json::as_array);
+    let roots: Vec<usize> = match scenes.get(document.json.get("scene").and_then(Json::as_usize).unwrap_or(0)) {
+        Some(scene) => scene.get("nodes").map_or(&[][..], Json::as_array).iter().filter_map(Json::as_usize).collect(),
+        None => {
+            let children: Vec<usize> = nodes
+                .iter()
+                .flat_map(|node| node.get("children").map_or(&[][..], Json::as_array))
+                .filter_map(Json::as_usize)
+                .collect();
+            (0..nodes.len()).filter(|i| !children.contains(i)).collect()
+        }
+    };
+
+    let mut objects = Vec::new();
+    // La jerarquía tiene que ser un árbol: un nodo alcanzado dos veces (ciclo o
+    // hijo compartido) multiplicaría el recorrido sin terminar nunca
+    let mut visited = vec![false; nodes.len()];
+    let mut pending: Vec<(usize, Matrix4<f32>)> = roots.into_iter().rev().map(|i| (i, transform)).collect();
+    while let Some((index, parent)) = pending.pop() {
+        let node = nodes.get(index).ok_or_else(|| format!("{}: nodo {} inexistente", path.display(), index))?;
+        if std::mem::replace(&mut visited[index], true) {
+            return Err(format!("{}: el nodo {} aparece dos veces en la jerarquía", path.display(), index));
+        }
+        let world = parent * node_matrix(node);
+
+        if let Some(mesh_index) = node.get("mesh").and_then(Json::as_usize) {
+            let name = node.get("name").and_then(Json::as_str).unwrap_or("").to_string();
+            for (k, (mesh, material)) in document.meshes(mesh_index, world).map_err(|e| format!("{}: {}", path.display(), e))?.into_iter().enumerate() {
+                let shader = material.and_then(|m| materials.get(m)).unwrap_or(&fallback).clone();
+                let name = if name.is_empty() { format!("mesh{}_{}", mesh_index, k) } else { format!("{}_{}", name, k) };
+                objects.push(SceneObject { name, mesh, shader, transform: world });
+            }
+        }
+        let children = node.get("children").map_or(&[][..], Json::as_array);
+        for child in children.iter().rev().filter_map(Json::as_usize) {
+            pending.push((child, world));
+        }
+    }
+    Ok(objects)
+}
+
+// Separar JSON y buffers; en .glb el buffer 0 sin `uri` es el chunk BIN
+fn parse_document(bytes: &[u8], base_dir: &Path) -> Result<Document, String> {
+    let (text, bin) = if bytes.starts_with(GLB_MAGIC) {
+        let word = |at: usize| -> Result<u32, String> {
+            let b = bytes.get(at..at + 4).ok_or("GLB truncado")?;
+            Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        };
+        if word(4)? != 2 {
+            return Err(format!("versión de GLB no soportada: {}", word(4)?));
+        }
+        let total = (word(8)? as usize).min(bytes.len());
+        let (mut text, mut bin) = (None, None);
+        let mut pos = 12;
+        while pos + 8 <= total {
+            let length = word(pos)? as usize;
+            let data = (pos + 8).checked_add(length).and_then(|end| bytes.get(pos + 8..end)).ok_or("chunk GLB truncado")?;
+            match word(pos + 4)? {
+                CHUNK_JSON if text.is_none() => text = Some(data),
+                CHUNK_BIN if bin.is_none() => bin = Some(data.to_vec()),
+                _ => {}
+            }
+            // Los chunks están alineados a 4 bytes
+            pos += 8 + length.div_ceil(4) * 4;
+        }
+        (text.ok_or("GLB sin chunk JSON")?, bin)
+    } else {
+        (bytes, None)
+    };
+
+    let text = std::str::from_utf8(text).map_err(|_| "el JSON no es UTF-8".to_string())?;
+    let json = Json::parse(text.trim_start_matches('\u{FEFF}'))?;
+    let version = json.get("asset").and_then(|a| a.get("version")).and_then(Json::as_str).unwrap_or("");
+    if !version.starts_with('2') {
+        return Err(format!("solo se soporta glTF 2.0 (asset.version = {:?})", version));
+    }
+
+    let mut bin = bin;
+    let mut buffers = Vec::new();
+    for (i, buffer) in json.get("buffers").map_or(&[][..], Json::as_array).iter().enumerate() {
+        let data = match buffer.get("uri").and_then(Json::as_str) {
+            Some(uri) => read_uri(uri, base_dir)?,
+            None if i == 0 => bin.take().ok_or("buffer 0 sin uri ni chunk BIN")?,
+            None => return Err(format!("buffer {} sin uri", i)),
+        };
+        let length = buffer.get("byteLength").and_then(Json::as_usize).unwrap_or(data.len());
+        if data.len() < length {
+            return Err(format!("buffer {} más corto que su byteLength", i));
+        }
+        buffers.push(data);
+    }
+    Ok(Document { json, buffers })
+}
+
+// data:...;base64,... o ruta relativa al archivo glTF (con %20 y demás escapados)
+fn read_uri(uri: &str, base_dir: &Path) -> Result<Vec<u8>, String> {
+    if let Some(rest) = uri.strip_prefix("data:") {
+        let (header, data) = rest.split_once(',').ok_or("data URI sin ','")?;
+        if !header.ends_with(";base64") {
+            return Err("data URI sin base64 no soportada".to_string());
+        }
+        return decode_base64(data);
+    }
+    let path = base_dir.join(percent_decode(uri));
+    fs::read(&path).map_err(|e| format!("No se pudo leer {}: {}", path.display(), e))
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok()).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for byte in text.bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            b if b.is_ascii_whitespace() => continue,
+            _ => return Err("base64 inválido".to_string()),
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
+
+// `matrix` (columna por columna) o traslación * rotación * escala
+fn node_matrix(node: &Json) -> Matrix4<f32> {
+    if let Some(m) = node.get("matrix").and_then(Json::as_f32_vec).filter(|m| m.len() == 16) {
+        return Matrix4::from_column_slice(&m);
+    }
+    let vector = |key: &str, default: [f32; 3]| match node.get(key).and_then(Json::as_f32_vec) {
+        Some(v) if v.len() == 3 => nalgebra::Vector3::new(v[0], v[1], v[2]),
+        _ => nalgebra::Vector3::from(default),
+    };
+    let translation = vector("translation", [0.0; 3]);
+    let scale = vector("scale", [1.0; 3]);
+    // Cuaternión en orden x, y, z, w
+    let rotation = match node.get("rotation").and_then(Json::as_f32_vec) {
+        Some(q) if q.len() == 4 => UnitQuaternion::from_quaternion(Quaternion::new(q[3], q[0], q[1], q[2])),
+        _ => UnitQuaternion::identity(),
+    };
+    Matrix4::new_translation(&translation) * rotation.to_homogeneous() * Matrix4::new_nonuniform_scaling(&scale)
+}
+
+impl Document {
+    fn item(&self, kind: &str, index: usize) -> Result<&Json, String> {
+        self.json
+            .get(kind)
+            .and_then(|items| items.as_array().get(index))
+            .ok_or_else(|| format!("{}[{}] inexistente", kind, index))
+    }
+
+    // Bytes de un bufferView y su stride declarado (0 = compacto)
+    fn buffer_view(&self, index: usize) -> Result<(&[u8], usize), String> {
+        let view = self.item("bufferViews", index)?;
+        let buffer = view.get("buffer").and_then(Json::as_usize).ok_or("bufferView sin buffer")?;
+        let data = self.buffers.get(buffer).ok_or_else(|| format!("buffer {} inexistente", buffer))?;
+        let offset = view.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+        let length = view.get("byteLength").and_then(Json::as_usize).ok_or("bufferView sin byteLength")?;
+        let bytes = offset.checked_add(length).and_then(|end| data.get(offset..end)).ok_or_else(|| format!("bufferView {} fuera del buffer", index))?;
+        Ok((bytes, view.get("byteStride").and_then(Json::as_usize).unwrap_or(0)))
+    }
+
+    // Accessor como f32 (los enteros normalizados se llevan a [0, 1] o [-1, 1]);
+    // devuelve los valores y la cantidad de componentes por elemento
+    fn accessor(&self, index: usize) -> Result<(Vec<f32>, usize), String> {
+        let accessor = self.item("accessors", index)?;
+        let count = accessor.get("count").and_then(Json::as_usize).ok_or("accessor sin count")?;
+        let components = match accessor.get("type").and_then(Json::as_str) {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") => 4,
+            Some("MAT4") => 16,
+            other => return Err(format!("tipo de accessor no soportado: {:?}", other)),
+        };
+        let component_type = accessor.get("componentType").and_then(Json::as_usize).unwrap_or(0);
+        let size = match component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            other => return Err(format!("componentType no soportado: {}", other)),
+        };
+        if accessor.get("sparse").is_some() {
+            return Err("accessors sparse no soportados".to_string());
+        }
+        let normalized = accessor.get("normalized").and_then(Json::as_bool).unwrap_or(false);
+
+        // Sin bufferView el accessor es todo ceros
+        let Some(view) = accessor.get("bufferView").and_then(Json::as_usize) else {
+            if count > MAX_ZERO_ACCESSOR_COUNT {
+                return Err(format!("accessor {} sin bufferView con count {}", index, count));
+            }
+            return Ok((vec![0.0; count * components], components));
+        };
+        let (bytes, stride) = self.buffer_view(view)?;
+        let offset = accessor.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+        let stride = if stride > 0 { stride } else { size * components };
+        if !fits(count, offset, stride, size * components, bytes.len()) {
+            return Err(format!("accessor {} fuera del bufferView", index));
+        }
+
+        let mut values = Vec::with_capacity(count * components);
+        for element in 0..count {
+            for c in 0..components {
+                let at = offset + element * stride + c * size;
+                let b = bytes.get(at..at + size).ok_or_else(|| format!("accessor {} fuera del bufferView", index))?;
+                let value = match component_type {
+                    5120 => {
+                        let v = b[0] as i8 as f32;
+                        if normalized { (v / 127.0).max(-1.0) } else { v }
+                    }
+                    5121 => {
+                        let v = b[0] as f32;
+                        if normalized { v / 255.0 } else { v }
+                    }
+                    5122 => {
+                        let v = i16::from_le_bytes([b[0], b[1]]) as f32;
+                        if normalized { (v / 32767.0).max(-1.0) } else { v }
+                    }
+                    5123 => {
+                        let v = u16::from_le_bytes([b[0], b[1]]) as f32;
+                        if normalized { v / 65535.0 } else { v }
+                    }
+                    5125 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32,
+                    _ => f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+                };
+                values.push(value);
+            }
+        }
+        Ok((values, components))
+    }
+
+    // Índices enteros (u8/u16/u32) leídos sin pasar por f32
+    fn indices(&self, index: usize) -> Result<Vec<u32>, String> {
+        let accessor = self.item("accessors", index)?;
+        let count = accessor.get("count").and_then(Json::as_usize).ok_or("accessor sin count")?;
+        let size = match accessor.get("componentType").and_then(Json::as_usize) {
+            Some(5121) => 1,
+            Some(5123) => 2,
+            Some(5125) => 4,
+            other => return Err(format!("índices con componentType {:?}", other)),
+        };
+        let view = accessor.get("bufferView").and_then(Json::as_usize).ok_or("índices sin bufferView")?;
+        let (bytes, stride) = self.buffer_view(view)?;
+        let offset = accessor.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+        let stride = if stride > 0 { stride } else { size };
+        if !fits(count, offset, stride, size, bytes.len()) {
+            return Err("índices fuera del bufferView".to_string());
+        }
+        (0..count)
+            .map(|i| {
+                let at = offset + i * stride;
+                let b = bytes.get(at..at + size).ok_or("índices fuera del bufferView")?;
+                Ok(match size {
+                    1 => b[0] as u32,
+                    2 => u16::from_le_bytes([b[0], b[1]]) as u32,
+                    _ => u32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+                })
+            })
+            .collect()
+    }
+
+    // Primitivas triangulares de un mesh como tobj::Mesh (el formato que
+    // consume render_planet) con el índice de su material. `world` decide el
+    // orden de los índices: una transformación espejada invierte el giro
+    fn meshes(&self, index: usize, world: Matrix4<f32>) -> Result<Vec<(tobj::Mesh, Option<usize>)>, String> {
+        let mirrored = world.fixed_view::<3, 3>(0, 0).determinant() < 0.0;
+        let mut meshes = Vec::new();
+        for primitive in self.item("meshes", index)?.get("primitives").map_or(&[][..], Json::as_array) {
+            let mode = primitive.get("mode").and_then(Json::as_usize).unwrap_or(MODE_TRIANGLES);
+            if !matches!(mode, MODE_TRIANGLES | MODE_TRIANGLE_STRIP | MODE_TRIANGLE_FAN) {
+                continue;
+            }
+            let attributes = primitive.get("attributes").ok_or("primitiva sin attributes")?;
+            let attribute = |name: &str, components: usize| -> Result<Option<Vec<f32>>, String> {
+                match attributes.get(name).and_then(Json::as_usize) {
+                    Some(accessor) => {
+                        let (values, found) = self.accessor(accessor)?;
+                        if found != components {
+                            return Err(format!("{} con {} componentes", name, found));
+                        }
+                        Ok(Some(values))
+                    }
+                    None => Ok(None),
+                }
+            };
+            let positions = attribute("POSITION", 3)?.ok_or("primitiva sin POSITION")?;
+            let vertex_count = positions.len() / 3;
+            let normals = attribute("NORMAL", 3)?.unwrap_or_default();
+            // glTF tiene el origen de los UV arriba a la izquierda; OBJ (y Texture::sample) abajo
+            let texcoords: Vec<f32> = attribute("TEXCOORD_0", 2)?
+                .unwrap_or_default()
+                .chunks_exact(2)
+                .flat_map(|uv| [uv[0], 1.0 - uv[1]])
+                .collect();
+
+            let order = match primitive.get("indices").and_then(Json::as_usize) {
+                Some(accessor) => self.indices(accessor)?,
+                None => (0..vertex_count as u32).collect(),
+            };
+            if order.iter().any(|&i| i as usize >= vertex_count) {
+                return Err(format!("índice fuera de rango en el mesh {}", index));
+            }
+            let mut indices = triangle_list(&order, mode);
+            if mirrored {
+                for tri in indices.chunks_exact_mut(3) {
+                    tri.swap(1, 2);
+                }
+            }
+            if indices.is_empty() {
+                continue;
+            }
+
+            let mut mesh = tobj::Mesh { positions, normals, texcoords, indices, ..Default::default() };
+            ensure_vertex_normals(&mut mesh);
+            meshes.push((mesh, primitive.get("material").and_then(Json::as_usize)));
+        }
+        Ok(meshes)
+    }
+
+    // Material metálico-rugoso: factores y textura de color base
+    fn material(&self, json: &Json, base_dir: &Path, textures: &mut [Option<Option<Arc<Texture>>>]) -> Material {
+        let pbr = json.get("pbrMetallicRoughness");
+        let factor = |key: &str| pbr.and_then(|p| p.get(key)).and_then(Json::as_f32);
+        let base = pbr
+            .and_then(|p| p.get("baseColorFactor"))
+            .and_then(Json::as_f32_vec)
+            .filter(|c| c.len() == 4)
+            .unwrap_or(vec![1.0; 4]);
+        let emissive = json.get("emissiveFactor").and_then(Json::as_f32_vec).filter(|c| c.len() == 3).unwrap_or(vec![0.0; 3]);
+        let alpha_mode = match json.get("alphaMode").and_then(Json::as_str) {
+            Some("BLEND") => AlphaMode::Blend,
+            Some("MASK") => AlphaMode::Mask(json.get("alphaCutoff").and_then(Json::as_f32).unwrap_or(0.5)),
+            _ => AlphaMode::Opaque,
+        };
+        let texture = pbr
+            .and_then(|p| p.get("baseColorTexture"))
+            .and_then(|t| t.get("index"))
+            .and_then(Json::as_usize)
+            .and_then(|t| self.texture(t, base_dir, textures));
+
+        Material {
+            name: json.get("name").and_then(Json::as_str).unwrap_or("").to_string(),
+            // El color base de glTF ya es lineal
+            diffuse: ShaderColor::new(base[0], base[1], base[2], 1.0),
+            emissive: ShaderColor::new(emissive[0], emissive[1], emissive[2], 1.0),
+            opacity: base[3],
+            shading: Shading::MetallicRoughness {
+                metallic: factor("metallicFactor").unwrap_or(1.0),
+                roughness: factor("roughnessFactor").unwrap_or(1.0),
+            },
+            alpha_mode,
+            double_sided: json.get("doubleSided").and_then(Json::as_bool).unwrap_or(false),
+            diffuse_texture: texture,
+            ..Material::default()
+        }
+    }
+
+    // Textura -> imagen (PNG o PPM, por URI o bufferView), cargada una sola vez
+    // por imagen; si no se puede decodificar se avisa y el material sigue sin ella
+    fn texture(&self, index: usize, base_dir: &Path, textures: &mut [Option<Option<Arc<Texture>>>]) -> Option<Arc<Texture>> {
+        let image = self.item("textures", index).ok()?.get("source").and_then(Json::as_usize)?;
+        if let Some(cached) = textures.get(image)? {
+            return cached.clone();
+        }
+        let loaded = self.image_bytes(image, base_dir).and_then(|bytes| decode_image(&bytes)).and_then(|image| Texture::from_image(&image)).map(Arc::new);
+        let texture = loaded.map_err(|e| eprintln!("Aviso: imagen {} del glTF: {}", image, e)).ok();
+        textures[image] = Some(texture.clone());
+        texture
+    }
+
+    fn image_bytes(&self, index: usize, base_dir: &Path) -> Result<Vec<u8>, String> {
+        let image = self.item("images", index)?;
+        if let Some(uri) = image.get("uri").and_then(Json::as_str) {
+            return read_uri(uri, base_dir);
+        }
+        let view = image.get("bufferView").and_then(Json::as_usize).ok_or("imagen sin uri ni bufferView")?;
+        Ok(self.buffer_view(view)?.0.to_vec())
+    }
+}
+
+// Si `count` elementos de `size` bytes cada `stride` a partir de `offset` caben
+// en `length` bytes; se comprueba antes de reservar memoria según el count
+fn fits(count: usize, offset: usize, stride: usize, size: usize, length: usize) -> bool {
+    match count.checked_sub(1) {
+        Some(last) => last.checked_mul(stride).and_then(|n| n.checked_add(offset)).and_then(|n| n.checked_add(size)).is_some_and(|end| end <= length),
+        None => offset <= length,
+    }
+}
+
+// Índices de tira o abanico -> lista de triángulos (manteniendo el giro)
+fn triangle_list(order: &[u32], mode: usize) -> Vec<u32> {
+    match mode {
+        MODE_TRIANGLE_STRIP => (0..order.len().saturating_sub(2))
+            .flat_map(|i| {
+                if i % 2 == 0 {
+                    [order[i], order[i + 1], order[i + 2]]
+                } else {
+                    [order[i + 1], order[i], order[i + 2]]
+                }
+            })
+            .collect(),
+        MODE_TRIANGLE_FAN => (1..order.len().saturating_sub(1)).flat_map(|i| [order[0], order[i], order[i + 1]]).collect(),
+        _ => order[..order.len() - order.len() % 3].to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::Point3;
+    use std::path::PathBuf;
+
+    // Fixtures generados por testdata/generar_gltf.py
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata").join(name)
+    }
+
+    fn object<'a>(objects: &'a [SceneObject], name: &str) -> &'a SceneObject {
+        objects.iter().find(|o| o.name == name).unwrap_or_else(|| panic!("falta {}", name))
+    }
+
+    fn assert_point(transform: &Matrix4<f32>, local: [f32; 3], expected: [f32; 3]) {
+        let p = transform.transform_point(&Point3::from(local));
+        assert!((p - Point3::from(expected)).norm() < 1e-5, "{:?} != {:?}", p, expected);
+    }
+
+    // Documento mínimo con un buffer de 12 bytes en data URI y un accessor
+    fn document(accessor: &str, view: &str) -> Result<Document, String> {
+        let text = format!(
+            r#"{{"asset": {{"version": "2.0"}},
+                "buffers": [{{"byteLength": 12, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAA"}}],
+                "bufferViews": [{}], "accessors": [{}]}}"#,
+            view, accessor
+        );
+        parse_document(text.as_bytes(), Path::new("."))
+    }
+
+    #[test]
+    fn gltf_and_glb_fixtures_load_the_same_scene() {
+        for name in ["nodos.gltf", "nodos.glb"] {
+            let objects = load_gltf_objects(&fixture(name), Matrix4::identity()).unwrap();
+            let names: Vec<&str> = objects.iter().map(|o| o.name.as_str()).collect();
+            assert_eq!(names, ["trs_0", "matriz_0", "espejo_0", "tira_0", "abanico_0"], "{}", name);
+
+            // Traslación * rotación (90° en y) * escala 2, y el hijo con `matrix` debajo
+            assert_point(&object(&objects, "trs_0").transform, [1.0, 0.0, 0.0], [1.0, 2.0, 1.0]);
+            assert_point(&object(&objects, "matriz_0").transform, [0.0, 0.0, 0.0], [1.0, 2.0, 1.0]);
+            assert_eq!(object(&objects, "trs_0").mesh.positions, [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+            assert_eq!(object(&objects, "trs_0").mesh.indices, [0, 1, 2]);
+
+            // El espejo invierte el giro para que la cara siga mirando afuera
+            assert_point(&object(&objects, "espejo_0").transform, [1.0, 0.0, 0.0], [-1.0, 0.0, 0.0]);
+            assert_eq!(object(&objects, "espejo_0").mesh.indices, [0, 2, 1]);
+
+            assert_eq!(object(&objects, "tira_0").mesh.indices, [0, 1, 2, 2, 1, 3]);
+            assert_eq!(object(&objects, "abanico_0").mesh.indices, [0, 1, 2, 0, 2, 3, 0, 3, 4]);
+        }
+    }
+
+    #[test]
+    fn parent_transform_applies_to_every_root() {
+        let transform = Matrix4::new_translation(&nalgebra::Vector3::new(0.0, 0.0, 10.0));
+        let objects = load_gltf_objects(&fixture("nodos.gltf"), transform).unwrap();
+        assert_point(&object(&objects, "tira_0").transform, [0.0, 0.0, 0.0], [0.0, 0.0, 10.0]);
+        assert_point(&object(&objects, "trs_0").transform, [1.0, 0.0, 0.0], [1.0, 2.0, 11.0]);
+    }
+
+    #[test]
+    fn accessor_reads_the_data_uri_buffer() {
+        let doc = document(r#"{"bufferView": 0, "componentType": 5126, "count": 1, "type": "VEC3"}"#, r#"{"buffer": 0, "byteLength": 12}"#).unwrap();
+        assert_eq!(doc.accessor(0).unwrap(), (vec![0.0; 3], 3));
+    }
+
+    #[test]
+    fn oversized_counts_and_offsets_are_errors() {
+        let view = r#"{"buffer": 0, "byteLength": 12}"#;
+        // count * stride mucho más grande que el bufferView (y que la memoria)
+        let doc = document(r#"{"bufferView": 0, "componentType": 5126, "count": 1e15, "type": "MAT4"}"#, view).unwrap();
+        assert!(doc.accessor(0).is_err());
+        // count * stride desborda usize
+        let doc = document(r#"{"bufferView": 0, "componentType": 5126, "count": 1e19, "type": "VEC3"}"#, view).unwrap();
+        assert!(doc.accessor(0).is_err());
+        let doc = document(r#"{"bufferView": 0, "componentType": 5125, "count": 1e15, "type": "SCALAR"}"#, view).unwrap();
+        assert!(doc.indices(0).is_err());
+        // Un elemento que empieza dentro y termina fuera
+        let doc = document(r#"{"bufferView": 0, "byteOffset": 4, "componentType": 5126, "count": 1, "type": "VEC3"}"#, view).unwrap();
+        assert!(doc.accessor(0).is_err());
+        // Sin bufferView no hay bytes que acoten el count
+        let doc = document(r#"{"componentType": 5126, "count": 1e15, "type": "VEC3"}"#, view).unwrap();
+        assert!(doc.accessor(0).is_err());
+        // byteOffset + byteLength desborda
+        let doc = document(r#"{"bufferView": 0, "componentType": 5126, "count": 1, "type": "VEC3"}"#, r#"{"buffer": 0, "byteOffset": 1.8e19, "byteLength": 1e18}"#).unwrap();
+        assert!(doc.accessor(0).is_err());
+    }
+
+    #[test]
+    fn strips_and_fans_keep_winding() {
+        assert_eq!(triangle_list(&[0, 1, 2, 3, 4], MODE_TRIANGLE_STRIP), [0, 1, 2, 2, 1, 3, 2, 3, 4]);
+        assert_eq!(triangle_list(&[5, 6, 7, 8], MODE_TRIANGLE_FAN), [5, 6, 7, 5, 7, 8]);
+        assert_eq!(triangle_list(&[0, 1, 2, 3], MODE_TRIANGLES), [0, 1, 2]);
+        assert!(triangle_list(&[0, 1], MODE_TRIANGLE_STRIP).is_empty());
+    }
+
+    // Cargar un .gltf escrito en el test (load_gltf_objects lee de un archivo)
+    fn load_text(name: &str, text: &str) -> Result<Vec<SceneObject>, String> {
+        let path = std::env::temp_dir().join(format!("reenderizar_nave_{}_{}.gltf", std::process::id(), name));
+        fs::write(&path, text).unwrap();
+        let result = load_gltf_objects(&path, Matrix4::identity());
+        fs::remove_file(&path).ok();
+        result
+    }
+
+    #[test]
+    fn nodes_reached_twice_are_errors() {
+        // Cada nodo lista dos veces al siguiente: 2^60 caminos sin ciclo alguno
+        let nodes: Vec<String> = (0..60).map(|i| if i < 59 { format!(r#"{{"children": [{0}, {0}]}}"#, i + 1) } else { "{}".to_string() }).collect();
+        let text = format!(r#"{{"asset": {{"version": "2.0"}}, "scenes": [{{"nodes": [0]}}], "nodes": [{}]}}"#, nodes.join(","));
+        assert!(load_text("dag", &text).is_err());
+
+        let cycle = r#"{"asset": {"version": "2.0"}, "scenes": [{"nodes": [0]}], "nodes": [{"children": [1]}, {"children": [0]}]}"#;
+        assert!(load_text("ciclo", cycle).is_err());
+
+        let tree = r#"{"asset": {"version": "2.0"}, "scenes": [{"nodes": [0]}], "nodes": [{"children": [1, 2]}, {}, {}]}"#;
+        assert!(load_text("arbol", tree).unwrap().is_empty());
+    }
+
+    #[test]
+    fn empty_texture_image_is_dropped() {
+        // PPM de 0x4: decodifica a una imagen vacía que sample() no puede muestrear
+        let text = r#"{"asset": {"version": "2.0"}, "images": [{"uri": "data:image/x-portable-pixmap;base64,UDYKMCA0CjI1NQo="}], "textures": [{"source": 0}]}"#;
+        let document = parse_document(text.as_bytes(), Path::new(".")).unwrap();
+        assert!(document.texture(0, Path::new("."), &mut [None]).is_none());
+    }
+}
@@ -0,0 +1,82 @@
+use crate::cli::Config;
+use crate::image_io::save_framebuffer;
+use crate::post::PostChain;
+use crate::rasterizer::Framebuffer;
+use crate::recorder::{seamless_loop, Recorder};
+use crate::renderer::RenderOptions;
+use crate::scene::Scene;
+use crate::y4m::Y4mWriter;
+use std::fs;
+use std::path::Path;
+
+// Renderizar N frames con paso de tiempo fijo y guardarlos en disco, sin ventana
+pub fn run_headless(
+    config: &Config,
+    scene: &mut Scene,
+    options: &RenderOptions,
+    post: &PostChain,
+) -> Result<(), String> {
+    let output_dir = config.output_dir.as_deref().map(Path::new);
+    if let Some(dir) = output_dir {
+        fs::create_dir_all(dir).map_err(|e| format!("No se pudo crear {}: {}", dir.display(), e))?;
+    }
+
+    let mut recorder = match &config.record {
+        Some(path) => Some(Recorder::new(Path::new(path), config.width, config.height)?),
+        None => None,
+    };
+
+    // Loop sin salto: un ciclo exacto de la animación
+    let (frames, dt) = if config.seamless_loop {
+        let period = scene.loop_length(scene.loop_period());
+        scene.snap_rotation_to_loop(period);
+        let (frames, dt) = seamless_loop(period, config.dt);
+        eprintln!("Loop de {:.3} s -> {} frames (dt = {:.5})", period, frames, dt);
+        (frames, dt)
+    } else {
+        (config.frames, config.dt)
+    };
+
+    let mut y4m = match &config.y4m {
+        Some(path) => Some(
+            Y4mWriter::create(path, config.width, config.height, dt)
+                .map_err(|e| format!("No se pudo abrir {}: {}", path, e))?,
+        ),
+        None => None,
+    };
+
+    let mut framebuffer = Framebuffer::new(config.width, config.height);
+
+    eprintln!("Headless: {} frames de {}x{} (dt = {})", frames, config.width, config.height, dt);
+
+    for frame in 0..frames {
+        scene.render(&mut framebuffer, options);
+        post.run(&mut framebuffer, scene.time);
+
+        if let Some(dir) = output_dir {
+            let path = dir.join(format!("frame_{:05}.{}", frame, config.format.extension()));
+            save_framebuffer(&framebuffer, &path, config.format)
+                .map_err(|e| format!("No se pudo escribir {}: {}", path.display(), e))?;
+        }
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.push_frame(&framebuffer, dt);
+        }
+        if let Some(y4m) = y4m.as_mut() {
+            y4m.write_frame(&framebuffer)
+                .map_err(|e| format!("Error escribiendo Y4M: {}", e))?;
+        }
+        eprintln!("  [{}/{}]", frame + 1, frames);
+
+        scene.update(dt);
+    }
+
+    if let Some(y4m) = y4m.as_mut() {
+        y4m.finish().map_err(|e| format!("Error escribiendo Y4M: {}", e))?;
+    }
+    if let Some(recorder) = &recorder {
+        recorder.finish()?;
+        eprintln!("Animacion guardada en {}", recorder.path().display());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,908 @@
+use crate::rasterizer::Framebuffer;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Formatos de imagen soportados para exportar frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Ppm,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Ppm => "ppm",
+        }
+    }
+}
+
+// Guardar el color_buffer (0x00RRGGBB) del framebuffer en disco
+pub fn save_framebuffer(fb: &Framebuffer, path: &Path, format: ImageFormat) -> io::Result<()> {
+    let bytes = match format {
+        ImageFormat::Png => encode_png(fb.width, fb.height, &fb.color_buffer),
+        ImageFormat::Ppm => encode_ppm(fb.width, fb.height, &fb.color_buffer),
+    };
+    fs::write(path, bytes)
+}
+
+// Pasar píxeles 0x00RRGGBB a bytes RGB
+pub fn to_rgb_bytes(pixels: &[u32]) -> Vec<u8> {
+    let mut rgb = Vec::with_capacity(pixels.len() * 3);
+    for &p in pixels {
+        rgb.extend_from_slice(&[(p >> 16) as u8, (p >> 8) as u8, p as u8]);
+    }
+    rgb
+}
+
+// ============================================================================
+// PPM (P6 binario)
+// ============================================================================
+pub fn encode_ppm(width: usize, height: usize, pixels: &[u32]) -> Vec<u8> {
+    let mut out = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    out.extend_from_slice(&to_rgb_bytes(pixels));
+    out
+}
+
+// ============================================================================
+// PNG (RGB 8 bits, sin filtros, zlib con Huffman fijo)
+// ============================================================================
+pub fn encode_png(width: usize, height: usize, pixels: &[u32]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&PNG_SIGNATURE);
+    write_png_chunk(&mut out, b"IHDR", &png_ihdr(width, height));
+    write_png_chunk(&mut out, b"IDAT", &zlib_compress(&png_scanlines(width, height, pixels)));
+    write_png_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+pub const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+pub fn png_ihdr(width: usize, height: usize) -> Vec<u8> {
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&(width as u32).to_be_bytes());
+    ihdr.extend_from_slice(&(height as u32).to_be_bytes());
+    // bit depth 8, color type 2 (RGB), compresión 0, filtro 0, sin entrelazado
+    ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+    ihdr
+}
+
+// Filas RGB precedidas por el byte de filtro (0 = None)
+pub fn png_scanlines(width: usize, height: usize, pixels: &[u32]) -> Vec<u8> {
+    let mut raw = Vec::with_capacity(height * (width * 3 + 1));
+    for row in pixels.chunks_exact(width).take(height) {
+        raw.push(0);
+        raw.extend_from_slice(&to_rgb_bytes(row));
+    }
+    raw
+}
+
+pub fn write_png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+// ============================================================================
+// DEFLATE: LZ77 con tabla hash + un solo bloque de Huffman fijo
+// Suficiente para frames con mucho fondo negro sin depender de crates externos
+// ============================================================================
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const HASH_BITS: usize = 15;
+const MAX_CHAIN: usize = 32;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+// Escritor de bits LSB primero (DEFLATE y LZW de GIF usan el mismo orden)
+pub struct BitWriter {
+    out: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        BitWriter { out: Vec::new(), buffer: 0, count: 0 }
+    }
+
+    // Bits en orden LSB primero (datos y bits extra)
+    pub fn write_bits(&mut self, value: u32, bits: u32) {
+        self.buffer |= value << self.count;
+        self.count += bits;
+        while self.count >= 8 {
+            self.out.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    // Los códigos Huffman se escriben empezando por el bit más significativo
+    fn write_code(&mut self, code: u32, bits: u32) {
+        let reversed = code.reverse_bits() >> (32 - bits);
+        self.write_bits(reversed, bits);
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.buffer as u8);
+        }
+        self.out
+    }
+}
+
+fn write_fixed_literal(w: &mut BitWriter, symbol: u32) {
+    match symbol {
+        0..=143 => w.write_code(0x30 + symbol, 8),
+        144..=255 => w.write_code(0x190 + symbol - 144, 9),
+        256..=279 => w.write_code(symbol - 256, 7),
+        _ => w.write_code(0xC0 + symbol - 280, 8),
+    }
+}
+
+fn write_match(w: &mut BitWriter, length: usize, distance: usize) {
+    let li = LENGTH_BASE.iter().rposition(|&b| b as usize <= length).unwrap();
+    write_fixed_literal(w, 257 + li as u32);
+    w.write_bits((length - LENGTH_BASE[li] as usize) as u32, LENGTH_EXTRA[li] as u32);
+
+    let di = DIST_BASE.iter().rposition(|&b| b as usize <= distance).unwrap();
+    w.write_code(di as u32, 5);
+    w.write_bits((distance - DIST_BASE[di] as usize) as u32, DIST_EXTRA[di] as u32);
+}
+
+fn hash3(data: &[u8], i: usize) -> usize {
+    let v = (data[i] as u32) << 16 | (data[i + 1] as u32) << 8 | data[i + 2] as u32;
+    (v.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+}
+
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut w = BitWriter::new();
+    // BFINAL = 1, BTYPE = 01 (Huffman fijo)
+    w.write_bits(1, 1);
+    w.write_bits(1, 2);
+
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; WINDOW_SIZE];
+    let insert = |head: &mut Vec<usize>, prev: &mut Vec<usize>, i: usize| {
+        if i + MIN_MATCH <= data.len() {
+            let h = hash3(data, i);
+            prev[i % WINDOW_SIZE] = head[h];
+            head[h] = i;
+        }
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        let mut best_len = 0;
+        let mut best_dist = 0;
+
+        if i + MIN_MATCH <= data.len() {
+            let max_len = MAX_MATCH.min(data.len() - i);
+            let mut candidate = head[hash3(data, i)];
+            let mut chain = 0;
+            while candidate != usize::MAX && i - candidate <= WINDOW_SIZE && chain < MAX_CHAIN {
+                let len = data[candidate..]
+                    .iter()
+                    .zip(&data[i..i + max_len])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    best_len = len;
+                    best_dist = i - candidate;
+                    if len == max_len {
+                        break;
+                    }
+                }
+                let next = prev[candidate % WINDOW_SIZE];
+                if next == usize::MAX || next >= candidate {
+                    break;
+                }
+                candidate = next;
+                chain += 1;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            write_match(&mut w, best_len, best_dist);
+            for j in i..i + best_len {
+                insert(&mut head, &mut prev, j);
+            }
+            i += best_len;
+        } else {
+            write_fixed_literal(&mut w, data[i] as u32);
+            insert(&mut head, &mut prev, i);
+            i += 1;
+        }
+    }
+
+    // Fin de bloque
+    write_fixed_literal(&mut w, 256);
+    w.finish()
+}
+
+pub fn zlib_compress(data: &[u8]) -> Vec<u8> {
+    // CMF = 0x78 (deflate, ventana 32K), FLG = 0x01 (check de cabecera)
+    let mut out = vec![0x78, 0x01];
+    out.extend_from_slice(&deflate(data));
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+// ============================================================================
+// LECTURA: PPM (P3/P6) y PNG (sin entrelazado) a RGBA 8 bits
+// Para las texturas de los materiales, sin crates de imágenes
+// ============================================================================
+
+// Imagen decodificada: RGBA 8 bits por canal, filas de arriba hacia abajo
+pub struct RgbaImage {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+// Decodificar PNG o PPM según la firma del archivo
+pub fn decode_image(bytes: &[u8]) -> Result<RgbaImage, String> {
+    if bytes.starts_with(&PNG_SIGNATURE) {
+        decode_png(bytes)
+    } else if bytes.starts_with(b"P6") || bytes.starts_with(b"P3") {
+        decode_ppm(bytes)
+    } else {
+        Err("formato de imagen no soportado (solo PNG y PPM)".to_string())
+    }
+}
+
+pub fn load_image(path: &Path) -> Result<RgbaImage, String> {
+    let bytes = fs::read(path).map_err(|e| format!("No se pudo leer {}: {}", path.display(), e))?;
+    decode_image(&bytes).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+pub fn decode_ppm(bytes: &[u8]) -> Result<RgbaImage, String> {
+    // Cabecera: magic, ancho, alto y maxval separados por espacios (con comentarios #)
+    let mut pos = 0;
+    let mut fields = Vec::with_capacity(4);
+    while fields.len() < 4 {
+        while pos < bytes.len() && (bytes[pos].is_ascii_whitespace() || bytes[pos] == b'#') {
+            if bytes[pos] == b'#' {
+                while pos < bytes.len() && bytes[pos] != b'\n' {
+                    pos += 1;
+                }
+            } else {
+                pos += 1;
+            }
+        }
+        let start = pos;
+        while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if start == pos {
+            return Err("cabecera PPM incompleta".to_string());
+        }
+        fields.push(std::str::from_utf8(&bytes[start..pos]).unwrap_or(""));
+    }
+    let number = |s: &str| s.parse::<usize>().map_err(|_| format!("número inválido en cabecera PPM: {}", s));
+    let (width, height, max) = (number(fields[1])?, number(fields[2])?, number(fields[3])?);
+    if max == 0 || max > 65535 {
+        return Err(format!("maxval PPM inválido: {}", max));
+    }
+    let count = width * height * 3;
+
+    let samples: Vec<usize> = if fields[0] == "P6" {
+        // Un solo espacio separa la cabecera de los datos binarios
+        let data = bytes.get(pos + 1..).unwrap_or(&[]);
+        let size = if max > 255 { 2 } else { 1 };
+        if data.len() < count * size {
+            return Err("datos PPM truncados".to_string());
+        }
+        (0..count)
+            .map(|i| if size == 2 { (data[i * 2] as usize) << 8 | data[i * 2 + 1] as usize } else { data[i] as usize })
+            .collect()
+    } else {
+        let text = std::str::from_utf8(&bytes[pos..]).map_err(|_| "datos P3 no son texto".to_string())?;
+        let samples: Vec<usize> = text
+            .split(|c: char| c.is_ascii_whitespace())
+            .filter(|s| !s.is_empty())
+            .take(count)
+            .map(number)
+            .collect::<Result<_, _>>()?;
+        if samples.len() < count {
+            return Err("datos PPM truncados".to_string());
+        }
+        samples
+    };
+
+    let mut pixels = Vec::with_capacity(width * height * 4);
+    for rgb in samples.chunks_exact(3) {
+        for &s in rgb {
+            pixels.push((s.min(max) * 255 / max) as u8);
+        }
+        pixels.push(255);
+    }
+    Ok(RgbaImage { width, height, pixels })
+}
+
+pub fn decode_png(bytes: &[u8]) -> Result<RgbaImage, String> {
+    if !bytes.starts_with(&PNG_SIGNATURE) {
+        return Err("no es un PNG".to_string());
+    }
+    let mut pos = PNG_SIGNATURE.len();
+    let mut header = None;
+    let mut palette: Vec<[u8; 4]> = Vec::new();
+    let mut transparent: Option<[u16; 3]> = None;
+    let mut idat = Vec::new();
+    while pos + 12 <= bytes.len() {
+        let length = u32::from_be_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]]) as usize;
+        let kind = &bytes[pos + 4..pos + 8];
+        let data = bytes.get(pos + 8..pos + 8 + length).ok_or("chunk PNG truncado")?;
+        match kind {
+            b"IHDR" if length >= 13 => {
+                let width = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+                let height = u32::from_be_bytes([data[4], data[5], data[6], data[7]]) as usize;
+                if width == 0 || height == 0 {
+                    return Err(format!("PNG de {}x{} píxeles", width, height));
+                }
+                if data[12] != 0 {
+                    return Err("PNG entrelazado no soportado".to_string());
+                }
+                header = Some((width, height, data[8], data[9]));
+            }
+            b"PLTE" => palette = data.chunks_exact(3).map(|c| [c[0], c[1], c[2], 255]).collect(),
+            b"tRNS" => match header.map(|h| h.3) {
+                Some(3) => {
+                    for (entry, &alpha) in palette.iter_mut().zip(data) {
+                        entry[3] = alpha;
+                    }
+                }
+                Some(0) if length >= 2 => transparent = Some([u16::from_be_bytes([data[0], data[1]]); 3]),
+                Some(2) if length >= 6 => {
+                    let sample = |i: usize| u16::from_be_bytes([data[i], data[i + 1]]);
+                    transparent = Some([sample(0), sample(2), sample(4)]);
+                }
+                _ => {}
+            },
+            b"IDAT" => idat.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+        pos += length + 12;
+    }
+
+    let (width, height, depth, color_type) = header.ok_or("PNG sin IHDR")?;
+    let channels = match (color_type, depth) {
+        (0, 1 | 2 | 4 | 8 | 16) => 1,
+        (3, 1 | 2 | 4 | 8) => 1,
+        (4, 8 | 16) => 2,
+        (2, 8 | 16) => 3,
+        (6, 8 | 16) => 4,
+        _ => return Err(format!("PNG con tipo de color {} y {} bits no soportado", color_type, depth)),
+    };
+    let bits = channels * depth as usize;
+    // Las dimensiones salen del archivo: un tamaño imposible es un error, no un pánico
+    let too_big = || format!("PNG demasiado grande: {}x{}", width, height);
+    let stride = width.checked_mul(bits).ok_or_else(too_big)?.div_ceil(8);
+    let expected = (stride + 1).checked_mul(height).ok_or_else(too_big)?;
+    let pixel_bytes = width.checked_mul(height).and_then(|n| n.checked_mul(4)).ok_or_else(too_big)?;
+    // Distancia en bytes al píxel de la izquierda para los filtros (mínimo 1)
+    let bpp = bits.div_ceil(8);
+
+    // Un PNG válido no descomprime a más de las filas filtradas
+    let raw = zlib_decompress(&idat, expected)?;
+    if raw.len() < expected {
+        return Err("datos PNG truncados".to_string());
+    }
+
+    // Deshacer los filtros fila por fila
+    let mut rows = vec![0u8; height * stride];
+    for y in 0..height {
+        let filter = raw[y * (stride + 1)];
+        let line = &raw[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        let (previous, current) = rows.split_at_mut(y * stride);
+        let up = if y > 0 { &previous[(y - 1) * stride..] } else { &[][..] };
+        let current = &mut current[..stride];
+        for i in 0..stride {
+            let a = if i >= bpp { current[i - bpp] } else { 0 };
+            let b = up.get(i).copied().unwrap_or(0);
+            let c = if i >= bpp { up.get(i - bpp).copied().unwrap_or(0) } else { 0 };
+            let predicted = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return Err(format!("filtro PNG inválido: {}", filter)),
+            };
+            current[i] = line[i].wrapping_add(predicted);
+        }
+    }
+
+    // Muestra `index` (en orden de canal) de una fila, escalada a 16 bits
+    let sample = |row: &[u8], index: usize| -> u16 {
+        match depth {
+            16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+            8 => row[index] as u16,
+            _ => {
+                let bit = index * depth as usize;
+                let shift = 8 - depth as usize - bit % 8;
+                ((row[bit / 8] >> shift) & ((1u8 << depth) - 1)) as u16
+            }
+        }
+    };
+    // Muestra -> 8 bits (las de menos de 8 bits se expanden al rango completo)
+    let to_byte = |s: u16| -> u8 {
+        match depth {
+            16 => (s >> 8) as u8,
+            8 => s as u8,
+            _ => (s as u32 * 255 / ((1u32 << depth) - 1)) as u8,
+        }
+    };
+
+    let mut pixels = Vec::with_capacity(pixel_bytes);
+    for row in rows.chunks_exact(stride) {
+        for x in 0..width {
+            let s = |c: usize| sample(row, x * channels + c);
+            let rgba = match color_type {
+                0 => {
+                    let g = s(0);
+                    let alpha = if transparent.is_some_and(|t| t[0] == g) { 0 } else { 255 };
+                    [to_byte(g), to_byte(g), to_byte(g), alpha]
+                }
+                3 => *palette.get(s(0) as usize).ok_or("índice de paleta PNG fuera de rango")?,
+                4 => [to_byte(s(0)), to_byte(s(0)), to_byte(s(0)), to_byte(s(1))],
+                2 => {
+                    let rgb = [s(0), s(1), s(2)];
+                    let alpha = if transparent == Some(rgb) { 0 } else { 255 };
+                    [to_byte(rgb[0]), to_byte(rgb[1]), to_byte(rgb[2]), alpha]
+                }
+                _ => [to_byte(s(0)), to_byte(s(1)), to_byte(s(2)), to_byte(s(3))],
+            };
+            pixels.extend_from_slice(&rgba);
+        }
+    }
+    Ok(RgbaImage { width, height, pixels })
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+// `limit`: bytes máximos de salida (más es un error, no memoria sin tope)
+pub fn zlib_decompress(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    if data.len() < 6 || data[0] & 0x0F != 8 || !u16::from_be_bytes([data[0], data[1]]).is_multiple_of(31) {
+        return Err("cabecera zlib inválida".to_string());
+    }
+    if data[1] & 0x20 != 0 {
+        return Err("zlib con diccionario preestablecido no soportado".to_string());
+    }
+    let (out, used) = inflate(&data[2..], limit)?;
+    let checksum = data.get(2 + used..2 + used + 4).ok_or("zlib sin adler32")?;
+    if adler32(&out).to_be_bytes() != checksum {
+        return Err("adler32 de zlib no coincide".to_string());
+    }
+    Ok(out)
+}
+
+// ============================================================================
+// INFLATE: bloques sin comprimir, Huffman fijo y dinámico (RFC 1951)
+// ============================================================================
+
+// Lector de bits LSB primero, el inverso de BitWriter
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader { data, pos: 0, bit: 0 }
+    }
+
+    fn read_bits(&mut self, bits: u32) -> Result<u32, String> {
+        let mut value = 0;
+        for i in 0..bits {
+            let byte = *self.data.get(self.pos).ok_or("deflate truncado")?;
+            value |= (((byte >> self.bit) & 1) as u32) << i;
+            self.bit += 1;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.pos += 1;
+            }
+        }
+        Ok(value)
+    }
+
+    // Saltar al próximo byte entero (bloques sin comprimir)
+    fn align(&mut self) {
+        if self.bit > 0 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+    }
+
+    // Bytes consumidos contando el último aunque esté a medias
+    fn consumed(&self) -> usize {
+        self.pos + (self.bit > 0) as usize
+    }
+}
+
+// Código Huffman canónico: cantidad de códigos por largo y símbolos en orden
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for len in 1..16 {
+            offsets[len] = offsets[len - 1] + counts[len - 1];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len > 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    // Leer bit a bit: los códigos de cada largo son consecutivos
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= reader.read_bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("código Huffman inválido".to_string())
+    }
+}
+
+// Orden en que llegan los largos del código de largos de un bloque dinámico
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+// Descomprimir un stream DEFLATE; devuelve los datos y los bytes consumidos
+pub fn inflate(data: &[u8], limit: usize) -> Result<(Vec<u8>, usize), String> {
+    let mut reader = BitReader::new(data);
+    let mut out = Vec::new();
+    loop {
+        let last = reader.read_bits(1)? == 1;
+        match reader.read_bits(2)? {
+            0 => {
+                reader.align();
+                let header = data.get(reader.pos..reader.pos + 4).ok_or("deflate truncado")?;
+                let len = u16::from_le_bytes([header[0], header[1]]) as usize;
+                if len != !u16::from_le_bytes([header[2], header[3]]) as usize {
+                    return Err("largo de bloque sin comprimir inválido".to_string());
+                }
+                let start = reader.pos + 4;
+                if out.len() + len > limit {
+                    return Err(too_long());
+                }
+                out.extend_from_slice(data.get(start..start + len).ok_or("deflate truncado")?);
+                reader.pos = start + len;
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                inflate_block(&mut reader, &mut out, limit, &Huffman::new(&lengths), &Huffman::new(&[5; 30]))?;
+            }
+            2 => {
+                let (literals, distances) = read_dynamic_tables(&mut reader)?;
+                inflate_block(&mut reader, &mut out, limit, &literals, &distances)?;
+            }
+            _ => return Err("tipo de bloque deflate inválido".to_string()),
+        }
+        if last {
+            break;
+        }
+    }
+    Ok((out, reader.consumed()))
+}
+
+fn read_dynamic_tables(reader: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+    let literal_count = reader.read_bits(5)? as usize + 257;
+    let distance_count = reader.read_bits(5)? as usize + 1;
+    let code_count = reader.read_bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for &index in &CODE_LENGTH_ORDER[..code_count] {
+        code_lengths[index] = reader.read_bits(3)? as u8;
+    }
+    let code = Huffman::new(&code_lengths);
+
+    // Largos de literales y distancias juntos: 16-18 repiten o ponen ceros
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let (value, repeat) = match code.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => (*lengths.last().ok_or("repetición sin largo previo")?, 3 + reader.read_bits(2)?),
+            17 => (0, 3 + reader.read_bits(3)?),
+            _ => (0, 11 + reader.read_bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() > literal_count + distance_count {
+        return Err("largos de código de más".to_string());
+    }
+    Ok((Huffman::new(&lengths[..literal_count]), Huffman::new(&lengths[literal_count..])))
+}
+
+fn too_long() -> String {
+    "deflate descomprime a más de lo esperado".to_string()
+}
+
+fn inflate_block(reader: &mut BitReader, out: &mut Vec<u8>, limit: usize, literals: &Huffman, distances: &Huffman) -> Result<(), String> {
+    loop {
+        let symbol = literals.decode(reader)? as usize;
+        match symbol {
+            0..=255 if out.len() >= limit => return Err(too_long()),
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let li = symbol - 257;
+                if li >= LENGTH_BASE.len() {
+                    return Err("largo deflate inválido".to_string());
+                }
+                let length = LENGTH_BASE[li] as usize + reader.read_bits(LENGTH_EXTRA[li] as u32)? as usize;
+                let di = distances.decode(reader)? as usize;
+                if di >= DIST_BASE.len() {
+                    return Err("distancia deflate inválida".to_string());
+                }
+                let distance = DIST_BASE[di] as usize + reader.read_bits(DIST_EXTRA[di] as u32)? as usize;
+                if distance > out.len() {
+                    return Err("distancia deflate fuera de la ventana".to_string());
+                }
+                if out.len() + length > limit {
+                    return Err(too_long());
+                }
+                // Byte a byte: la copia puede solaparse con lo que escribe
+                let start = out.len() - distance;
+                for i in 0..length {
+                    out.push(out[start + i]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Generador xorshift: datos "incompresibles" reproducibles
+    fn noise(len: usize, mut state: u32) -> Vec<u32> {
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state & 0x00FF_FFFF
+            })
+            .collect()
+    }
+
+    fn roundtrip(width: usize, height: usize, pixels: &[u32]) {
+        let image = decode_png(&encode_png(width, height, pixels)).expect("PNG propio ilegible");
+        assert_eq!((image.width, image.height), (width, height));
+        let decoded: Vec<u32> = image
+            .pixels
+            .chunks_exact(4)
+            .map(|p| {
+                assert_eq!(p[3], 255);
+                (p[0] as u32) << 16 | (p[1] as u32) << 8 | p[2] as u32
+            })
+            .collect();
+        assert!(decoded == pixels, "píxeles distintos en {}x{}", width, height);
+    }
+
+    #[test]
+    fn crc32_and_adler32_match_known_vectors() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+        // CRC del chunk IEND vacío, el mismo en todo PNG
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        assert_eq!(adler32(b""), 1);
+        // Más de 5552 bytes: el módulo se aplica por bloques sin desbordar
+        assert_eq!(adler32(&[0xFF; 100_000]), 0x149A_302C);
+    }
+
+    #[test]
+    fn png_chunks_carry_length_and_crc() {
+        let png = encode_png(2, 1, &[0xFF0000, 0x00FF00]);
+        assert!(png.starts_with(&PNG_SIGNATURE));
+        assert_eq!(&png[8..16], &[0, 0, 0, 13, b'I', b'H', b'D', b'R']);
+        assert_eq!(&png[png.len() - 12..], &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]);
+    }
+
+    #[test]
+    fn png_roundtrip_long_runs() {
+        // Fondo negro con una franja: matches de largo máximo y distancias
+        // hasta el borde de la ventana de 32K
+        let (width, height) = (320, 240);
+        let pixels: Vec<u32> = (0..width * height)
+            .map(|i| if (i / width) % 97 == 5 { 0x20_40_80 } else { 0 })
+            .collect();
+        roundtrip(width, height, &pixels);
+        roundtrip(64, 64, &[0xFFFFFF; 64 * 64]);
+    }
+
+    #[test]
+    fn png_roundtrip_incompressible() {
+        roundtrip(128, 96, &noise(128 * 96, 0x1234_5678));
+    }
+
+    #[test]
+    fn png_roundtrip_odd_widths() {
+        for (width, height) in [(1, 1), (1, 9), (3, 5), (7, 3), (13, 11), (65, 2)] {
+            roundtrip(width, height, &noise(width * height, width as u32 * 31 + height as u32));
+        }
+    }
+
+    #[test]
+    fn deflate_roundtrip_repeats_at_window_edge() {
+        // Bloque aleatorio repetido a exactamente WINDOW_SIZE de distancia
+        let block: Vec<u8> = noise(WINDOW_SIZE / 4, 99).iter().flat_map(|p| p.to_le_bytes()).collect();
+        let mut data = block.clone();
+        data.extend_from_slice(&block);
+        data.extend_from_slice(&[7; 1000]);
+        let compressed = zlib_compress(&data);
+        assert!(compressed.len() < data.len() * 3 / 4);
+        assert!(zlib_decompress(&compressed, usize::MAX).unwrap() == data);
+        assert_eq!(zlib_decompress(&zlib_compress(&[]), usize::MAX).unwrap(), Vec::<u8>::new());
+    }
+
+    // Fixtures generados con el zlib de Python: testdata/generar_fixtures.py
+    macro_rules! fixture {
+        ($name:expr) => {
+            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/", $name))
+        };
+    }
+
+    #[test]
+    fn decode_png_fixtures() {
+        let check = |name: &str, png: &[u8], expected: &[u8], width: usize, height: usize| {
+            let image = decode_png(png).unwrap_or_else(|e| panic!("{}: {}", name, e));
+            assert_eq!((image.width, image.height), (width, height), "{}", name);
+            assert!(image.pixels == expected, "{}: píxeles distintos", name);
+        };
+        // Filtros 0-4 en RGB y RGBA de 8 bits; IDAT partido en dos chunks
+        check("rgb8_filtros", fixture!("rgb8_filtros.png"), fixture!("rgb8_filtros.rgba"), 9, 10);
+        check("rgba8_filtros", fixture!("rgba8_filtros.png"), fixture!("rgba8_filtros.rgba"), 6, 5);
+        // 16 bits: gris con tRNS y RGBA
+        check("gris16_trns", fixture!("gris16_trns.png"), fixture!("gris16_trns.rgba"), 7, 5);
+        check("rgba16", fixture!("rgba16.png"), fixture!("rgba16.rgba"), 5, 4);
+        // Paleta de 4 bits con tRNS más corto que la paleta
+        check("paleta4_trns", fixture!("paleta4_trns.png"), fixture!("paleta4_trns.rgba"), 5, 4);
+        // Gris de menos de 8 bits con filas que no llenan el último byte
+        check("gris1", fixture!("gris1.png"), fixture!("gris1.rgba"), 11, 3);
+        check("gris2", fixture!("gris2.png"), fixture!("gris2.rgba"), 7, 3);
+    }
+
+    #[test]
+    fn inflate_fixture_block_types() {
+        let phrase = b"el sol sale por el este y se pone por el oeste; ";
+        let text: Vec<u8> = (0..6000).map(|i| phrase[(i * 7 / 5) % phrase.len()] ^ ((i / 997) & 3) as u8).collect();
+        let streams: [(&[u8], u8); 3] = [
+            (fixture!("texto_stored.zlib"), 0),
+            (fixture!("texto_fixed.zlib"), 1),
+            (fixture!("texto_dynamic.zlib"), 2),
+        ];
+        for (stream, block_type) in streams {
+            // BTYPE del primer bloque, justo después de la cabecera zlib
+            assert_eq!((stream[2] >> 1) & 3, block_type);
+            assert!(zlib_decompress(stream, usize::MAX).unwrap() == text, "bloques tipo {}", block_type);
+        }
+    }
+
+    #[test]
+    fn corrupt_streams_are_errors() {
+        let stream = fixture!("texto_dynamic.zlib");
+        assert!(zlib_decompress(&stream[..stream.len() / 2], usize::MAX).is_err());
+        let mut wrong_checksum = stream.to_vec();
+        *wrong_checksum.last_mut().unwrap() ^= 1;
+        assert!(zlib_decompress(&wrong_checksum, usize::MAX).is_err());
+
+        let png = fixture!("rgb8_filtros.png");
+        assert!(decode_png(&png[..png.len() - 40]).is_err());
+    }
+
+    // El mismo PNG con otras dimensiones en el IHDR (y su CRC corregido)
+    fn with_size(png: &[u8], width: u32, height: u32) -> Vec<u8> {
+        let mut png = png.to_vec();
+        png[16..20].copy_from_slice(&width.to_be_bytes());
+        png[20..24].copy_from_slice(&height.to_be_bytes());
+        let crc = crc32(&png[12..29]);
+        png[29..33].copy_from_slice(&crc.to_be_bytes());
+        png
+    }
+
+    #[test]
+    fn malformed_dimensions_are_errors() {
+        let png = encode_png(2, 2, &[0x102030; 4]);
+        assert!(decode_png(&with_size(&png, 2, 2)).is_ok());
+        assert!(decode_png(&with_size(&png, 0, 2)).is_err());
+        assert!(decode_png(&with_size(&png, 2, 0)).is_err());
+        // height * stride desborda usize
+        assert!(decode_png(&with_size(&png, u32::MAX, u32::MAX)).is_err());
+        // Dimensiones enormes con datos para 2x2: error sin reservar las filas
+        assert!(decode_png(&with_size(&png, 60_000, 60_000)).is_err());
+        // Menos píxeles que los datos: la salida de inflate supera lo esperado
+        assert!(decode_png(&with_size(&png, 1, 1)).is_err());
+    }
+
+    #[test]
+    fn inflate_output_is_capped() {
+        let stream = zlib_compress(&[7; 1000]);
+        assert_eq!(zlib_decompress(&stream, 1000).unwrap().len(), 1000);
+        assert!(zlib_decompress(&stream, 999).is_err());
+        let stored = fixture!("texto_stored.zlib");
+        assert!(zlib_decompress(stored, 5999).is_err());
+    }
+}
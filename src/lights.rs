@@ -0,0 +1,172 @@
+use crate::shaders::ShaderColor;
+use crate::vector::Vector3;
+
+// Tipos de luz soportados por los shaders
+#[derive(Debug, Clone, Copy)]
+pub enum LightKind {
+    // Rayos paralelos; `direction` es hacia dónde viaja la luz
+    Directional { direction: Vector3 },
+    // Emite en todas direcciones desde `position` (p. ej. una estrella)
+    Point { position: Vector3 },
+    // Cono desde `position` apuntando a `direction`; cosenos de los ángulos
+    // interior (intensidad completa) y exterior (se apaga)
+    Spot { position: Vector3, direction: Vector3, cos_inner: f32, cos_outer: f32 },
+}
+
+// Atenuación con la distancia: 1 / (constant + linear*d + quadratic*d²)
+#[derive(Debug, Clone, Copy)]
+pub struct Attenuation {
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+}
+
+impl Attenuation {
+    // Sin caída con la distancia
+    pub const NONE: Attenuation = Attenuation { constant: 1.0, linear: 0.0, quadratic: 0.0 };
+
+    fn factor(&self, distance: f32) -> f32 {
+        1.0 / (self.constant + self.linear * distance + self.quadratic * distance * distance).max(1e-4)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Light {
+    pub kind: LightKind,
+    pub color: ShaderColor,
+    pub intensity: f32,
+    // Ignorada por las luces direccionales
+    pub attenuation: Attenuation,
+}
+
+impl Light {
+    pub fn directional(direction: Vector3, color: ShaderColor, intensity: f32) -> Self {
+        Light {
+            kind: LightKind::Directional { direction: direction.normalize() },
+            color,
+            intensity,
+            attenuation: Attenuation::NONE,
+        }
+    }
+
+    pub fn point(position: Vector3, color: ShaderColor, intensity: f32) -> Self {
+        Light { kind: LightKind::Point { position }, color, intensity, attenuation: Attenuation::NONE }
+    }
+
+    // `inner_angle`/`outer_angle` en radianes desde el eje del cono
+    pub fn spot(
+        position: Vector3,
+        direction: Vector3,
+        inner_angle: f32,
+        outer_angle: f32,
+        color: ShaderColor,
+        intensity: f32,
+    ) -> Self {
+        Light {
+            kind: LightKind::Spot {
+                position,
+                direction: direction.normalize(),
+                cos_inner: inner_angle.cos(),
+                cos_outer: outer_angle.cos(),
+            },
+            color,
+            intensity,
+            attenuation: Attenuation::NONE,
+        }
+    }
+
+    pub fn with_attenuation(mut self, constant: f32, linear: f32, quadratic: f32) -> Self {
+        self.attenuation = Attenuation { constant, linear, quadratic };
+        self
+    }
+
+    // Luz que llega al punto `p` (mundo): dirección normalizada hacia la luz y
+    // color ya escalado por intensidad, atenuación y cono. None si no llega
+    pub fn illuminate(&self, p: Vector3) -> Option<(Vector3, ShaderColor)> {
+        let (to_light, scale) = match self.kind {
+            LightKind::Directional { direction } => (direction * -1.0, 1.0),
+            LightKind::Point { position } => {
+                let offset = position - p;
+                let distance = offset.length();
+                (offset / distance.max(1e-6), self.attenuation.factor(distance))
+            }
+            LightKind::Spot { position, direction, cos_inner, cos_outer } => {
+                let offset = position - p;
+                let distance = offset.length();
+                let to_light = offset / distance.max(1e-6);
+                // Borde suave entre el cono exterior y el interior
+                let cos_angle = (to_light * -1.0).dot(&direction);
+                let t = ((cos_angle - cos_outer) / (cos_inner - cos_outer).max(1e-6)).clamp(0.0, 1.0);
+                let cone = t * t * (3.0 - 2.0 * t);
+                (to_light, self.attenuation.factor(distance) * cone)
+            }
+        };
+
+        let scale = scale * self.intensity;
+        if scale <= 0.0 {
+            return None;
+        }
+        Some((
+            to_light,
+            ShaderColor::new(self.color.r * scale, self.color.g * scale, self.color.b * scale, 1.0),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: ShaderColor = ShaderColor { r: 1.0, g: 1.0, b: 1.0, a: 1.0 };
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    // Punto a `distance` del origen a `degrees` del eje +z (en el plano xz)
+    fn at_angle(degrees: f32, distance: f32) -> Vector3 {
+        let angle = degrees.to_radians();
+        Vector3::new(angle.sin(), 0.0, angle.cos()) * distance
+    }
+
+    #[test]
+    fn spot_cone_has_a_smooth_edge_between_inner_and_outer() {
+        let spot = Light::spot(Vector3::zero(), Vector3::new(0.0, 0.0, 2.0), 20f32.to_radians(), 30f32.to_radians(), WHITE, 2.0);
+        let red = |degrees: f32| spot.illuminate(at_angle(degrees, 3.0)).map_or(0.0, |(_, c)| c.r);
+
+        // Dentro del cono interior: intensidad completa
+        assert!(close(red(0.0), 2.0));
+        assert!(close(red(19.0), 2.0));
+        // Entre los conos cae de forma monótona y sin saltos
+        let edge: Vec<f32> = (20..=30).map(|d| red(d as f32)).collect();
+        assert!(edge.windows(2).all(|w| w[1] <= w[0]), "{:?}", edge);
+        assert!(red(25.0) > 0.2 && red(25.0) < 1.8);
+        // Fuera del cono exterior no llega
+        assert!(spot.illuminate(at_angle(31.0, 3.0)).is_none());
+        assert!(spot.illuminate(Vector3::new(0.0, 0.0, -3.0)).is_none());
+
+        // La dirección devuelta apunta del punto hacia la luz
+        let (to_light, _) = spot.illuminate(at_angle(10.0, 3.0)).unwrap();
+        assert!((to_light - at_angle(10.0, 1.0) * -1.0).length() < 1e-5);
+    }
+
+    #[test]
+    fn attenuation_follows_distance() {
+        let point = Light::point(Vector3::new(0.0, 4.0, 0.0), WHITE, 3.0).with_attenuation(1.0, 0.5, 0.25);
+        // d = 4: 1 / (1 + 0.5*4 + 0.25*16) = 1/7
+        let (to_light, color) = point.illuminate(Vector3::zero()).unwrap();
+        assert!(close(color.g, 3.0 / 7.0));
+        assert!((to_light - Vector3::new(0.0, 1.0, 0.0)).length() < 1e-6);
+        // d = 0: solo el término constante
+        assert!(close(point.illuminate(Vector3::new(0.0, 4.0, 0.0)).unwrap().1.g, 3.0));
+        // A más distancia, siempre menos luz
+        let falloff: Vec<f32> = (1..20).map(|d| point.illuminate(Vector3::new(0.0, 4.0 - d as f32, 0.0)).unwrap().1.g).collect();
+        assert!(falloff.windows(2).all(|w| w[1] < w[0]));
+
+        // El foco también se atenúa; la direccional ignora la atenuación
+        let spot = Light::spot(Vector3::zero(), Vector3::new(1.0, 0.0, 0.0), 0.3, 0.5, WHITE, 1.0).with_attenuation(0.0, 0.0, 1.0);
+        assert!(close(spot.illuminate(Vector3::new(2.0, 0.0, 0.0)).unwrap().1.b, 0.25));
+        let sun = Light::directional(Vector3::new(0.0, -1.0, 0.0), WHITE, 1.5).with_attenuation(0.0, 0.0, 1.0);
+        assert!(close(sun.illuminate(Vector3::new(0.0, -100.0, 0.0)).unwrap().1.r, 1.5));
+    }
+}
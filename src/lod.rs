@@ -0,0 +1,155 @@
+use crate::sphere::{generate_sphere, SphereMesh};
+use crate::vector::Vector3;
+
+// Largo (px) que puede alcanzar una arista en pantalla antes de pasar al
+// nivel más denso
+const MAX_EDGE_PIXELS: f32 = 8.0;
+
+// Un nivel de detalle y el radio proyectado (px) desde el que se usa
+pub struct LodLevel {
+    pub mesh: tobj::Mesh,
+    pub min_screen_radius: f32,
+    // Distancia mínima de una cara al centro: las caras planas quedan por
+    // dentro de la esfera, más cuanto más simple es el nivel
+    pub inner_radius: f32,
+}
+
+// Niveles de detalle de la esfera, del más denso al más simple, elegidos por
+// cuerpo según su radio proyectado en pantalla
+pub struct MeshLod {
+    pub levels: Vec<LodLevel>,
+    // Margen relativo alrededor de cada umbral: hay que pasarlo por esta
+    // fracción para cambiar de nivel, así un cuerpo justo en el borde no
+    // alterna de nivel en cada frame
+    pub hysteresis: f32,
+}
+
+// Ángulo (rad) de la arista de un triángulo equilátero si `triangles` de ellos
+// cubren la esfera unitaria: 4π / T = (√3 / 4) a²
+fn edge_angle(triangles: usize) -> f32 {
+    (16.0 * std::f32::consts::PI / (3.0f32.sqrt() * triangles.max(1) as f32)).sqrt()
+}
+
+impl MeshLod {
+    // Hasta `count` niveles desde `finest`, cada uno más simple que el anterior
+    pub fn from_sphere(finest: SphereMesh, radius: f32, count: usize) -> Self {
+        let mut spheres = vec![finest];
+        while spheres.len() < count.max(1) {
+            match spheres[spheres.len() - 1].coarser() {
+                Some(next) => spheres.push(next),
+                None => break,
+            }
+        }
+
+        let meshes: Vec<tobj::Mesh> = spheres.iter().map(|&sphere| generate_sphere(sphere, radius)).collect();
+        // Se usa un nivel mientras las aristas del siguiente (más simple)
+        // quedarían más largas que MAX_EDGE_PIXELS en pantalla
+        let thresholds: Vec<f32> = (0..meshes.len())
+            .map(|i| {
+                meshes
+                    .get(i + 1)
+                    .map_or(0.0, |coarser| MAX_EDGE_PIXELS / edge_angle(coarser.indices.len() / 3))
+            })
+            .collect();
+        let levels = meshes
+            .into_iter()
+            .zip(thresholds)
+            .map(|(mesh, min_screen_radius)| LodLevel {
+                inner_radius: inner_radius(&mesh),
+                mesh,
+                min_screen_radius,
+            })
+            .collect();
+
+        MeshLod { levels, hysteresis: 0.15 }
+    }
+
+    // Nivel para un cuerpo de `screen_radius` px que venía usando `current`
+    pub fn select(&self, current: usize, screen_radius: f32) -> usize {
+        let mut level = current.min(self.levels.len() - 1);
+        while level > 0 && screen_radius >= self.levels[level - 1].min_screen_radius * (1.0 + self.hysteresis) {
+            level -= 1;
+        }
+        while level + 1 < self.levels.len() && screen_radius < self.levels[level].min_screen_radius * (1.0 - self.hysteresis) {
+            level += 1;
+        }
+        level
+    }
+}
+
+// Menor distancia del centro de una cara al origen
+fn inner_radius(mesh: &tobj::Mesh) -> f32 {
+    let position = |i: u32| {
+        let i = i as usize * 3;
+        Vector3::new(mesh.positions[i], mesh.positions[i + 1], mesh.positions[i + 2])
+    };
+    mesh.indices
+        .chunks_exact(3)
+        .map(|tri| ((position(tri[0]) + position(tri[1]) + position(tri[2])) / 3.0).length())
+        .fold(f32::INFINITY, f32::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Umbrales 200 / 80 / 30 px con el margen por defecto (15 %)
+    fn lod() -> MeshLod {
+        let level = |min_screen_radius| LodLevel { mesh: tobj::Mesh::default(), min_screen_radius, inner_radius: 1.0 };
+        MeshLod { levels: vec![level(200.0), level(80.0), level(30.0), level(0.0)], hysteresis: 0.15 }
+    }
+
+    #[test]
+    fn radius_near_a_threshold_does_not_flip() {
+        let lod = lod();
+        // Oscilando alrededor de 200 px: el nivel 1 se mantiene hasta pasar 230
+        let mut level = 1;
+        for radius in [199.0, 201.0, 198.0, 205.0, 229.0, 199.0] {
+            level = lod.select(level, radius);
+            assert_eq!(level, 1, "{} px", radius);
+        }
+        level = lod.select(level, 231.0);
+        assert_eq!(level, 0);
+        // ... y el nivel 0 hasta bajar de 170
+        for radius in [201.0, 199.0, 175.0, 171.0, 199.0] {
+            level = lod.select(level, radius);
+            assert_eq!(level, 0, "{} px", radius);
+        }
+        assert_eq!(lod.select(level, 169.0), 1);
+    }
+
+    #[test]
+    fn levels_step_monotonically_with_radius() {
+        let lod = lod();
+        let radii: Vec<f32> = (0..=500).map(|r| r as f32).collect();
+        // Creciendo el nivel solo baja (más denso), de a un paso o más
+        let mut level = lod.levels.len() - 1;
+        for &radius in &radii {
+            let next = lod.select(level, radius);
+            assert!(next <= level, "{} px: {} -> {}", radius, level, next);
+            level = next;
+        }
+        assert_eq!(level, 0);
+        // Achicándose solo sube
+        for &radius in radii.iter().rev() {
+            let next = lod.select(level, radius);
+            assert!(next >= level, "{} px: {} -> {}", radius, level, next);
+            level = next;
+        }
+        assert_eq!(level, lod.levels.len() - 1);
+        // Lejos de los umbrales el nivel no depende del anterior
+        for (radius, expected) in [(400.0, 0), (120.0, 1), (50.0, 2), (10.0, 3)] {
+            for current in 0..lod.levels.len() {
+                assert_eq!(lod.select(current, radius), expected, "{} px desde {}", radius, current);
+            }
+        }
+    }
+
+    #[test]
+    fn sphere_thresholds_decrease_with_detail() {
+        let lod = MeshLod::from_sphere(SphereMesh::Icosphere { subdivisions: 4 }, 1.0, 4);
+        assert_eq!(lod.levels.len(), 4);
+        assert!(lod.levels.windows(2).all(|w| w[0].min_screen_radius > w[1].min_screen_radius));
+        assert_eq!(lod.levels[3].min_screen_radius, 0.0);
+    }
+}
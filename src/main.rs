@@ -5,30 +5,143 @@ mod shaders;
 mod vector;
 mod text;
 mod renderer;
+mod clipping;
+mod cli;
+mod scene;
+mod image_io;
+mod headless;
+mod recorder;
+mod y4m;
+mod lights;
+mod shadows;
+mod tonemap;
+mod bloom;
+mod post;
+mod corona;
+mod atmosphere;
+mod rings;
+mod lod;
+mod tessellation;
+mod material;
+mod model;
+mod json;
+mod gltf;
 
 use minifb::{Key, Window, WindowOptions};
 use rasterizer::Framebuffer;
-use vector::Vector3;
-use shaders::{PlanetShader, ShaderUniforms, StarShader};
-use nalgebra::{Matrix4, Point3};
-use renderer::{WIDTH, HEIGHT, generate_stars, render_stars, render_planet};
+use shaders::{StarShader, DEFAULT_LOOP_PERIOD, STAR_PULSE_SPEED};
+use renderer::{WIDTH, HEIGHT, Interpolation, RenderOptions};
+use scene::{cinematic_lights, Scene, FIXED_DT, LOD_LEVELS};
+use lod::MeshLod;
+use recorder::{seamless_loop, Recorder};
+use y4m::Y4mWriter;
 use text::draw_text;
+use bloom::Bloom;
+use post::{ColorGrading, ColorLut, PostChain};
+use tonemap::ToneMapping;
+use nalgebra::Matrix4;
+use std::path::Path;
 
 fn main() {
-    println!("\nIniciando Software Renderer...");
+    let config = match cli::parse_args() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            cli::print_usage();
+            std::process::exit(1);
+        }
+    };
+
+    eprintln!("\nIniciando Software Renderer...");
     
     // Cargar o generar esfera
-    let (models, _materials, used_fallback) = sphere::load_sphere_or_generate()
+    let (models, materials, source) = sphere::load_sphere_or_generate(config.sphere)
         .expect("No se pudo cargar ni generar la esfera");
 
-    if used_fallback {
+    if let Some(sphere) = config.sphere {
+        eprintln!("Usando esfera procedimental: {:?}", sphere);
+    } else if source.is_none() {
         eprintln!("No se encontró sphere.obj, usando ESFERA PROCEDIMENTAL.");
     } else {
-        println!("sphere.obj cargada desde archivo.");
+        eprintln!("sphere.obj cargada desde archivo.");
+    }
+
+    // El primer modelo es la esfera de todos los cuerpos; si el OBJ trae más,
+    // se dibujan como objetos de la escena con sus materiales
+    let mut models = models.into_iter();
+    let mesh = models.next().expect("El modelo no tiene meshes").mesh;
+    let base_dir = source.as_deref().and_then(Path::parent).unwrap_or(Path::new("."));
+    let mut objects = model::objects_from_obj(models.collect(), &materials, base_dir, Matrix4::identity());
+
+    // Modelos de --model (naves, estaciones), cada uno en su posición
+    for spec in &config.models {
+        let [x, y, z] = spec.position;
+        let transform = Matrix4::new_translation(&nalgebra::Vector3::new(x, y, z)) * Matrix4::new_scaling(spec.scale);
+        match model::load_objects(Path::new(&spec.path), transform) {
+            Ok(loaded) => objects.extend(loaded),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let mut render_options = RenderOptions::default();
+    if let Some(threads) = config.threads {
+        render_options.threads = threads;
+    }
+    // Cadena de post-proceso: bloom y tone mapping por defecto, o la de `--post`
+    let mut post = match &config.post {
+        Some(names) => PostChain::from_names(names).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }),
+        None => PostChain::default(),
+    };
+    if let Some(tone_mapping) = post.get_mut::<ToneMapping>() {
+        if let Some(mapper) = config.tone_mapper {
+            tone_mapping.mapper = mapper;
+        }
+        if let Some(exposure) = config.exposure {
+            tone_mapping.exposure = exposure;
+        }
+    }
+    if let Some(bloom) = post.get_mut::<Bloom>() {
+        if let Some(threshold) = config.bloom_threshold {
+            bloom.threshold = threshold;
+        }
+        if let Some(radius) = config.bloom_radius {
+            bloom.radius = radius;
+        }
+        if let Some(intensity) = config.bloom_intensity {
+            bloom.intensity = intensity;
+        }
+    }
+    if let Some(path) = &config.lut {
+        let lut = ColorLut::load_cube(std::path::Path::new(path)).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+        if let Some(grading) = post.get_mut::<ColorGrading>() {
+            grading.lut = lut;
+        }
+        post.set_enabled("grade", true);
+    }
+    eprintln!("Rasterizador: {} hilo(s)", render_options.threads);
+    eprintln!("Post-proceso: {}", post.enabled_names().join(" -> "));
+
+    // Modo offline: sin ventana, frames a disco
+    if config.headless {
+        let mut scene = Scene::new(mesh, config.width, config.height);
+        configure_scene(&mut scene, &config);
+        scene.objects = objects;
+        if let Err(e) = headless::run_headless(&config, &mut scene, &render_options, &post) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
     }
 
-    let mesh = &models[0].mesh;
-    
     // Crear ventana con minifb
     let mut window = Window::new(
         "Software Renderer - Planetas",
@@ -42,129 +155,262 @@ fn main() {
     window.set_target_fps(60);
 
     let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
-    let mut time = 0.0f32;
-    let mut rotation_angle: f32 = 0.0;
-    let mut camera_distance: f32 = 3.5;
-    let mut camera_angle: f32 = 0.0;
+    let mut scene = Scene::new(mesh, WIDTH, HEIGHT);
+    configure_scene(&mut scene, &config);
+    scene.objects = objects;
 
-    // Generar campo de estrellas
-    let stars = generate_stars(500);
+    // Grabación opcional de lo que se ve en la ventana (sin el HUD)
+    let mut recorder = config.record.as_ref().map(|path| {
+        Recorder::new(std::path::Path::new(path), WIDTH, HEIGHT).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        })
+    });
+    let mut frame_dt = FIXED_DT;
+    let mut loop_frames = None;
+    if recorder.is_some() && config.seamless_loop {
+        let period = scene.loop_length(scene.loop_period());
+        scene.snap_rotation_to_loop(period);
+        let (frames, dt) = seamless_loop(period, FIXED_DT);
+        frame_dt = dt;
+        loop_frames = Some(frames);
+        eprintln!("Grabando loop de {:.3} s ({} frames)", period, frames);
+    }
 
-    // Instanciar SOLO el shader de la estrella
-    let mut star_shader = StarShader::default();
+    // Stream Y4M opcional (p. ej. `--y4m - | ffmpeg -i - captura.mp4`)
+    let mut y4m = config.y4m.as_ref().map(|path| {
+        Y4mWriter::create(path, WIDTH, HEIGHT, frame_dt).unwrap_or_else(|e| {
+            eprintln!("No se pudo abrir {}: {}", path, e);
+            std::process::exit(1);
+        })
+    });
 
-    println!("Todo listo! Presiona ESC para salir.\n");
-    println!("Controles: A/Z=freq, S/X=speed, D/C=octaves, F/V=disp, G/B=flare, R=reset\n");
+    eprintln!("Todo listo! Presiona ESC para salir.\n");
+    eprintln!("Controles: A/Z=freq, S/X=speed, D/C=octaves, F/V=disp, G/B=flare, R=reset, P=interp, L=luces, K=sombras, T=tonemap, Y/H=exposicion,\n           1/Q 2/W 3/E=bloom (umbral, radio, intensidad), 4-8=efectos (vineta, aberracion, grano, LUT, gamma),\n           9=corona, U/J I/O M/N=corona (alcance, caida, filamentos), 0=atmosferas, Tab=LOD, Espacio=teselado adaptativo\n");
 
     while window.is_open() && !window.is_key_down(Key::Escape) {
         // --- Controles de parametros del StarShader ---
         // Frecuencia: A / Z
         if window.is_key_pressed(Key::A, minifb::KeyRepeat::No) {
-            star_shader.freq += 0.2;
-            println!("Star freq -> {:.2}", star_shader.freq);
+            scene.star_shader.freq += 0.2;
+            eprintln!("Star freq -> {:.2}", scene.star_shader.freq);
         }
         if window.is_key_pressed(Key::Z, minifb::KeyRepeat::No) {
-            star_shader.freq = (star_shader.freq - 0.2).max(0.1);
-            println!("Star freq -> {:.2}", star_shader.freq);
+            scene.star_shader.freq = (scene.star_shader.freq - 0.2).max(0.1);
+            eprintln!("Star freq -> {:.2}", scene.star_shader.freq);
         }
 
         // Speed: S / X
         if window.is_key_pressed(Key::S, minifb::KeyRepeat::No) {
-            star_shader.speed += 0.05;
-            println!("Star speed -> {:.3}", star_shader.speed);
+            scene.star_shader.speed += 0.05;
+            eprintln!("Star speed -> {:.3}", scene.star_shader.speed);
         }
         if window.is_key_pressed(Key::X, minifb::KeyRepeat::No) {
-            star_shader.speed = (star_shader.speed - 0.05).max(0.0);
-            println!("Star speed -> {:.3}", star_shader.speed);
+            scene.star_shader.speed = (scene.star_shader.speed - 0.05).max(0.0);
+            eprintln!("Star speed -> {:.3}", scene.star_shader.speed);
         }
 
         // Octaves: D / C
         if window.is_key_pressed(Key::D, minifb::KeyRepeat::No) {
-            star_shader.octaves = (star_shader.octaves + 1).min(10);
-            println!("Star octaves -> {}", star_shader.octaves);
+            scene.star_shader.octaves = (scene.star_shader.octaves + 1).min(10);
+            eprintln!("Star octaves -> {}", scene.star_shader.octaves);
         }
         if window.is_key_pressed(Key::C, minifb::KeyRepeat::No) {
-            star_shader.octaves = (star_shader.octaves - 1).max(1);
-            println!("Star octaves -> {}", star_shader.octaves);
+            scene.star_shader.octaves = (scene.star_shader.octaves - 1).max(1);
+            eprintln!("Star octaves -> {}", scene.star_shader.octaves);
         }
 
         // Displacement scale: F / V
         if window.is_key_pressed(Key::F, minifb::KeyRepeat::No) {
-            star_shader.displacement_scale += 0.01;
-            println!("Star disp -> {:.3}", star_shader.displacement_scale);
+            scene.star_shader.displacement_scale += 0.01;
+            eprintln!("Star disp -> {:.3}", scene.star_shader.displacement_scale);
         }
         if window.is_key_pressed(Key::V, minifb::KeyRepeat::No) {
-            star_shader.displacement_scale = (star_shader.displacement_scale - 0.01).max(0.0);
-            println!("Star disp -> {:.3}", star_shader.displacement_scale);
+            scene.star_shader.displacement_scale = (scene.star_shader.displacement_scale - 0.01).max(0.0);
+            eprintln!("Star disp -> {:.3}", scene.star_shader.displacement_scale);
         }
 
         // Flare strength: G / B
         if window.is_key_pressed(Key::G, minifb::KeyRepeat::No) {
-            star_shader.flare_strength += 0.05;
-            println!("Star flare -> {:.3}", star_shader.flare_strength);
+            scene.star_shader.flare_strength += 0.05;
+            eprintln!("Star flare -> {:.3}", scene.star_shader.flare_strength);
         }
         if window.is_key_pressed(Key::B, minifb::KeyRepeat::No) {
-            star_shader.flare_strength = (star_shader.flare_strength - 0.05).max(0.0);
-            println!("Star flare -> {:.3}", star_shader.flare_strength);
+            scene.star_shader.flare_strength = (scene.star_shader.flare_strength - 0.05).max(0.0);
+            eprintln!("Star flare -> {:.3}", scene.star_shader.flare_strength);
         }
 
         // Reset parametros: R
         if window.is_key_pressed(Key::R, minifb::KeyRepeat::No) {
-            star_shader = StarShader::default();
-            println!("Star params RESET to defaults");
+            // Conservar el periodo de loop elegido por linea de comandos
+            let loop_period = scene.star_shader.loop_period;
+            scene.star_shader = StarShader { loop_period, ..StarShader::default() };
+            eprintln!("Star params RESET to defaults");
+        }
+
+        // Interpolacion perspectiva / afin: P
+        if window.is_key_pressed(Key::P, minifb::KeyRepeat::No) {
+            render_options.interpolation = match render_options.interpolation {
+                Interpolation::PerspectiveCorrect => Interpolation::Affine,
+                Interpolation::Affine => Interpolation::PerspectiveCorrect,
+            };
+            eprintln!("Interpolacion -> {:?}", render_options.interpolation);
+        }
+
+        // Luces de relleno/contraluz: L
+        if window.is_key_pressed(Key::L, minifb::KeyRepeat::No) {
+            scene.extra_lights = if scene.extra_lights.is_empty() { cinematic_lights() } else { Vec::new() };
+            eprintln!("Luces extra -> {}", scene.extra_lights.len());
+        }
+
+        // Bloom: 1/Q umbral, 2/W radio, 3/E intensidad
+        if let Some(bloom) = post.get_mut::<Bloom>() {
+            if window.is_key_pressed(Key::Key1, minifb::KeyRepeat::No) {
+                bloom.threshold += 0.05;
+                eprintln!("Bloom threshold -> {:.2}", bloom.threshold);
+            }
+            if window.is_key_pressed(Key::Q, minifb::KeyRepeat::No) {
+                bloom.threshold = (bloom.threshold - 0.05).max(0.0);
+                eprintln!("Bloom threshold -> {:.2}", bloom.threshold);
+            }
+            if window.is_key_pressed(Key::Key2, minifb::KeyRepeat::No) {
+                bloom.radius = (bloom.radius * 1.25).min(512.0);
+                eprintln!("Bloom radius -> {:.1}", bloom.radius);
+            }
+            if window.is_key_pressed(Key::W, minifb::KeyRepeat::No) {
+                bloom.radius = (bloom.radius / 1.25).max(2.0);
+                eprintln!("Bloom radius -> {:.1}", bloom.radius);
+            }
+            if window.is_key_pressed(Key::Key3, minifb::KeyRepeat::No) {
+                bloom.intensity += 0.1;
+                eprintln!("Bloom intensity -> {:.2}", bloom.intensity);
+            }
+            if window.is_key_pressed(Key::E, minifb::KeyRepeat::No) {
+                bloom.intensity = (bloom.intensity - 0.1).max(0.0);
+                eprintln!("Bloom intensity -> {:.2}", bloom.intensity);
+            }
+        }
+
+        // Tone mapping: T alterna la curva, Y/H suben/bajan la exposicion
+        if let Some(tone_mapping) = post.get_mut::<ToneMapping>() {
+            if window.is_key_pressed(Key::T, minifb::KeyRepeat::No) {
+                tone_mapping.mapper = tone_mapping.mapper.next();
+                eprintln!("Tone mapping -> {}", tone_mapping.mapper.name());
+            }
+            if window.is_key_pressed(Key::Y, minifb::KeyRepeat::No) {
+                tone_mapping.exposure *= 1.25;
+                eprintln!("Exposicion -> {:.3}", tone_mapping.exposure);
+            }
+            if window.is_key_pressed(Key::H, minifb::KeyRepeat::No) {
+                tone_mapping.exposure /= 1.25;
+                eprintln!("Exposicion -> {:.3}", tone_mapping.exposure);
+            }
+        }
+
+        // Efectos de post-proceso: 4 vineta, 5 aberracion, 6 grano, 7 LUT, 8 gamma
+        for (key, name) in [
+            (Key::Key4, "vignette"),
+            (Key::Key5, "aberration"),
+            (Key::Key6, "grain"),
+            (Key::Key7, "grade"),
+            (Key::Key8, "gamma"),
+        ] {
+            if window.is_key_pressed(key, minifb::KeyRepeat::No) {
+                let enabled = post.toggle(name);
+                eprintln!("Post {} -> {}", name, enabled);
+            }
+        }
+
+        // Sombras: K
+        if window.is_key_pressed(Key::K, minifb::KeyRepeat::No) {
+            scene.shadows = !scene.shadows;
+            eprintln!("Sombras -> {}", scene.shadows);
+        }
+
+        // Corona: 9 la activa, U/J alcance, I/O caida, M/N filamentos
+        if window.is_key_pressed(Key::Key9, minifb::KeyRepeat::No) {
+            scene.show_corona = !scene.show_corona;
+            eprintln!("Corona -> {}", scene.show_corona);
+        }
+        if window.is_key_pressed(Key::U, minifb::KeyRepeat::No) {
+            scene.corona.radius = (scene.corona.radius + 0.2).min(6.0);
+            eprintln!("Corona radius -> {:.2}", scene.corona.radius);
+        }
+        if window.is_key_pressed(Key::J, minifb::KeyRepeat::No) {
+            scene.corona.radius = (scene.corona.radius - 0.2).max(1.2);
+            eprintln!("Corona radius -> {:.2}", scene.corona.radius);
+        }
+        if window.is_key_pressed(Key::I, minifb::KeyRepeat::No) {
+            scene.corona.falloff += 0.05;
+            eprintln!("Corona falloff -> {:.2}", scene.corona.falloff);
+        }
+        if window.is_key_pressed(Key::O, minifb::KeyRepeat::No) {
+            scene.corona.falloff = (scene.corona.falloff - 0.05).max(0.05);
+            eprintln!("Corona falloff -> {:.2}", scene.corona.falloff);
+        }
+        if window.is_key_pressed(Key::M, minifb::KeyRepeat::No) {
+            scene.corona.streamer_frequency += 0.5;
+            eprintln!("Corona frequency -> {:.1}", scene.corona.streamer_frequency);
+        }
+        if window.is_key_pressed(Key::N, minifb::KeyRepeat::No) {
+            scene.corona.streamer_frequency = (scene.corona.streamer_frequency - 0.5).max(0.5);
+            eprintln!("Corona frequency -> {:.1}", scene.corona.streamer_frequency);
+        }
+
+        // Atmosferas: 0
+        if window.is_key_pressed(Key::Key0, minifb::KeyRepeat::No) {
+            scene.atmospheres = !scene.atmospheres;
+            eprintln!("Atmosferas -> {}", scene.atmospheres);
+        }
+
+        // Nivel de detalle de los cuerpos: Tab
+        if window.is_key_pressed(Key::Tab, minifb::KeyRepeat::No) {
+            scene.use_lod = !scene.use_lod;
+            eprintln!("LOD -> {}", scene.use_lod);
+        }
+
+        // Teselado adaptativo de las superficies desplazadas: Espacio
+        if window.is_key_pressed(Key::Space, minifb::KeyRepeat::No) {
+            scene.use_adaptive = !scene.use_adaptive;
+            eprintln!("Teselado adaptativo -> {}", scene.use_adaptive);
         }
 
         // Controles de camara con flechas
         if window.is_key_down(Key::Left) {
-            camera_angle -= 0.02;
+            scene.camera_angle -= 0.02;
         }
         if window.is_key_down(Key::Right) {
-            camera_angle += 0.02;
+            scene.camera_angle += 0.02;
         }
         if window.is_key_down(Key::Up) {
-            camera_distance = (camera_distance - 0.05).max(1.5);
+            scene.camera_distance = (scene.camera_distance - 0.1).max(1.5);
         }
         if window.is_key_down(Key::Down) {
-            camera_distance = (camera_distance + 0.05).min(10.0);
+            scene.camera_distance = (scene.camera_distance + 0.1).min(20.0);
         }
 
         // Update
-        time += 0.016; // ~60 FPS
-        rotation_angle += 0.005;
-
-        // Clear framebuffer
-        framebuffer.clear(0x000000);
-
-        // Renderizar fondo de estrellas
-        render_stars(&mut framebuffer, &stars);
-
-        // Matrices de transformación para cámara
-        let camera_x = camera_distance * camera_angle.sin();
-        let camera_z = camera_distance * camera_angle.cos();
-        let eye = Point3::new(camera_x, 1.0, camera_z);
-        let target = Point3::new(0.0, 0.0, 0.0);
-        let up = nalgebra::Vector3::new(0.0, 1.0, 0.0);
-        let view = Matrix4::look_at_rh(&eye, &target, &up);
-        
-        let aspect = WIDTH as f32 / HEIGHT as f32;
-        let fov = std::f32::consts::PI / 3.0;
-        let projection = Matrix4::new_perspective(aspect, fov, 0.1, 100.0);
-
-        // Configurar uniforms para shaders
-        let camera_pos = Vector3::new(camera_x, 1.0, camera_z);
-        let uniforms = ShaderUniforms {
-            time,
-            light_direction: Vector3::new(1.0, 1.0, 0.5).normalize(),
-            camera_position: camera_pos,
-        };
+        scene.update(frame_dt);
 
-        // Usar SOLO el shader de la estrella
-        let shader: &dyn PlanetShader = &star_shader;
+        // Renderizar fondo y estrella
+        scene.render(&mut framebuffer, &render_options);
+        post.run(&mut framebuffer, scene.time);
 
-        // Renderizar la estrella
-        let model = Matrix4::from_axis_angle(&nalgebra::Vector3::y_axis(), rotation_angle);
-        let mvp = projection * view * model;
-        render_planet(&mut framebuffer, mesh, &mvp, shader, &uniforms);
+        if let Some(stream) = y4m.as_mut() {
+            if let Err(e) = stream.write_frame(&framebuffer) {
+                eprintln!("Error escribiendo Y4M: {}", e);
+                y4m = None;
+            }
+        }
+
+        // Grabar el frame; en modo loop se cierra el archivo al completar el ciclo
+        if let Some(rec) = recorder.as_mut() {
+            rec.push_frame(&framebuffer, frame_dt);
+            if loop_frames == Some(rec.frame_count()) {
+                finish_recording(rec);
+                recorder = None;
+            }
+        }
 
         // Dibujar texto de instrucciones en pantalla
         let text_color = 0xFFFFFF; // Blanco
@@ -179,22 +425,89 @@ fn main() {
 
         // Mostrar parametros del StarShader en pantalla
         let mut y = 40;
-        let s1 = format!("Freq (A/Z): {:.2}", star_shader.freq);
+        let s1 = format!("Freq (A/Z): {:.2}", scene.star_shader.freq);
         draw_text(&mut framebuffer, 740, y, s1.as_str(), text_color, 1);
         y += 16;
-        let s2 = format!("Speed (S/X): {:.3}", star_shader.speed);
+        let s2 = format!("Speed (S/X): {:.3}", scene.star_shader.speed);
         draw_text(&mut framebuffer, 740, y, s2.as_str(), text_color, 1);
         y += 16;
-        let s3 = format!("Octaves (D/C): {}", star_shader.octaves);
+        let s3 = format!("Octaves (D/C): {}", scene.star_shader.octaves);
         draw_text(&mut framebuffer, 740, y, s3.as_str(), text_color, 1);
         y += 16;
-        let s4 = format!("Disp (F/V): {:.3}", star_shader.displacement_scale);
+        let s4 = format!("Disp (F/V): {:.3}", scene.star_shader.displacement_scale);
         draw_text(&mut framebuffer, 740, y, s4.as_str(), text_color, 1);
         y += 16;
-        let s5 = format!("Flare (G/B): {:.3}", star_shader.flare_strength);
+        let s5 = format!("Flare (G/B): {:.3}", scene.star_shader.flare_strength);
         draw_text(&mut framebuffer, 740, y, s5.as_str(), text_color, 1);
         y += 16;
         draw_text(&mut framebuffer, 740, y, "R: reset", text_color, 1);
+        y += 16;
+        let interp = match render_options.interpolation {
+            Interpolation::PerspectiveCorrect => "PERSPECTIVA",
+            Interpolation::Affine => "AFIN",
+        };
+        let s6 = format!("Interp (P): {}", interp);
+        draw_text(&mut framebuffer, 740, y, s6.as_str(), text_color, 1);
+        y += 16;
+        let s7 = format!("Threads: {}", render_options.threads);
+        draw_text(&mut framebuffer, 740, y, s7.as_str(), text_color, 1);
+        y += 16;
+        let s8 = format!("Luces (L): {}", 1 + scene.extra_lights.len());
+        draw_text(&mut framebuffer, 740, y, s8.as_str(), text_color, 1);
+        y += 16;
+        let s9 = format!("Sombras (K): {}", if scene.shadows { "ON" } else { "OFF" });
+        draw_text(&mut framebuffer, 740, y, s9.as_str(), text_color, 1);
+        y += 16;
+        let corona = if scene.show_corona {
+            format!(
+                "Corona (9): {:.1} {:.2} {:.1}",
+                scene.corona.radius, scene.corona.falloff, scene.corona.streamer_frequency
+            )
+        } else {
+            "Corona (9): OFF".to_string()
+        };
+        draw_text(&mut framebuffer, 740, y, corona.as_str(), text_color, 1);
+        y += 16;
+        let atmospheres = format!("Atmosferas (0): {}", if scene.atmospheres { "ON" } else { "OFF" });
+        draw_text(&mut framebuffer, 740, y, atmospheres.as_str(), text_color, 1);
+        y += 16;
+        // Nivel de cada cuerpo (0 = el más denso)
+        let lod = if scene.use_lod {
+            let levels: Vec<String> = scene.bodies.iter().map(|body| body.lod_level.to_string()).collect();
+            format!("LOD (Tab): {}", levels.join(" "))
+        } else {
+            "LOD (Tab): OFF".to_string()
+        };
+        draw_text(&mut framebuffer, 740, y, lod.as_str(), text_color, 1);
+        y += 16;
+        let adaptive = format!("Teselado (Espacio): {}", if scene.use_adaptive { "ON" } else { "OFF" });
+        draw_text(&mut framebuffer, 740, y, adaptive.as_str(), text_color, 1);
+        y += 16;
+        if let Some(tone_mapping) = post.get::<ToneMapping>() {
+            let s10 = format!("Tonemap (T): {}", tone_mapping.mapper.name());
+            draw_text(&mut framebuffer, 740, y, s10.as_str(), text_color, 1);
+            y += 16;
+            let s11 = format!("Exposicion (Y/H): {:.2}", tone_mapping.exposure);
+            draw_text(&mut framebuffer, 740, y, s11.as_str(), text_color, 1);
+            y += 16;
+        }
+        if let Some(bloom) = post.get::<Bloom>() {
+            let s12 = format!("Bloom thr (1/Q): {:.2}", bloom.threshold);
+            draw_text(&mut framebuffer, 740, y, s12.as_str(), text_color, 1);
+            y += 16;
+            let s13 = format!("Bloom rad (2/W): {:.1}", bloom.radius);
+            draw_text(&mut framebuffer, 740, y, s13.as_str(), text_color, 1);
+            y += 16;
+            let s14 = format!("Bloom int (3/E): {:.2}", bloom.intensity);
+            draw_text(&mut framebuffer, 740, y, s14.as_str(), text_color, 1);
+            y += 16;
+        }
+        // Efectos activos en orden de aplicacion
+        draw_text(&mut framebuffer, 740, y, "Post (4-8):", text_color, 1);
+        for name in post.enabled_names() {
+            y += 16;
+            draw_text(&mut framebuffer, 756, y, name, text_color, 1);
+        }
 
         // Mostrar en ventana
         window
@@ -202,5 +515,63 @@ fn main() {
             .expect("Error al actualizar ventana");
     }
 
-    println!("\n¡Adiós!");
+    if let Some(rec) = &recorder {
+        finish_recording(rec);
+    }
+    if let Some(stream) = y4m.as_mut() {
+        if let Err(e) = stream.finish() {
+            eprintln!("Error escribiendo Y4M: {}", e);
+        }
+    }
+
+    eprintln!("\n¡Adiós!");
+}
+
+// Opciones de linea de comandos que afectan a la escena (ventana y headless)
+fn configure_scene(scene: &mut Scene, config: &cli::Config) {
+    // Los shaders repiten solo si se pide: --loop-period, o --loop con el
+    // periodo por defecto
+    let loop_period = config.loop_period.or(config.seamless_loop.then_some(DEFAULT_LOOP_PERIOD));
+    if let Some(period) = loop_period {
+        scene.set_loop_period(period);
+        // El pulso da ciclos enteros por periodo: avisar si cambia de ritmo
+        let pulse = scene.star_shader.pulse_speed();
+        if (pulse / STAR_PULSE_SPEED - 1.0).abs() > 0.01 {
+            eprintln!(
+                "Aviso: con un loop de {} s el pulso de la estrella va a {:.3} rad/s en lugar de {} ({:.2}x)",
+                period,
+                pulse,
+                STAR_PULSE_SPEED,
+                pulse / STAR_PULSE_SPEED
+            );
+        }
+    }
+    if config.cinematic_lights {
+        scene.extra_lights = cinematic_lights();
+    }
+    scene.shadows = !config.no_shadows;
+    scene.show_corona = !config.no_corona;
+    scene.atmospheres = !config.no_atmospheres;
+    if let Some(radius) = config.corona_radius {
+        scene.corona.radius = radius;
+    }
+    if let Some(falloff) = config.corona_falloff {
+        scene.corona.falloff = falloff;
+    }
+    if let Some(frequency) = config.corona_frequency {
+        scene.corona.streamer_frequency = frequency;
+    }
+    // Los niveles de detalle siguen la topología elegida con --sphere
+    if let Some(sphere) = config.sphere {
+        scene.lod = MeshLod::from_sphere(sphere, scene.mesh_radius, LOD_LEVELS);
+    }
+    scene.use_lod = config.lod;
+    scene.use_adaptive = config.adaptive;
+}
+
+fn finish_recording(recorder: &Recorder) {
+    match recorder.finish() {
+        Ok(()) => eprintln!("Animacion guardada en {} ({} frames)", recorder.path().display(), recorder.frame_count()),
+        Err(e) => eprintln!("{}", e),
+    }
 }
\ No newline at end of file
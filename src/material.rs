@@ -0,0 +1,344 @@
+use crate::image_io::{load_image, RgbaImage};
+use crate::rasterizer::BlendMode;
+use crate::shaders::{blinn_phong, PlanetShader, ShaderColor, ShaderUniforms};
+use crate::vector::Vector3;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// Luz ambiente de los materiales (los planetas usan valores parecidos)
+const AMBIENT: f32 = 0.08;
+
+// Brillo por defecto si el MTL no trae Ns
+const DEFAULT_SHININESS: f32 = 32.0;
+
+// Textura en espacio lineal (los PNG/PPM vienen en sRGB), muestreo bilineal
+// con repetición. v = 0 es la fila de abajo, como en los UV de OBJ
+pub struct Texture {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<ShaderColor>,
+}
+
+impl Texture {
+    // Una imagen vacía es un error: sample() usa el ancho y el alto como módulo
+    pub fn from_image(image: &RgbaImage) -> Result<Self, String> {
+        if image.width == 0 || image.height == 0 {
+            return Err(format!("textura vacía ({}x{})", image.width, image.height));
+        }
+        let pixels = image
+            .pixels
+            .chunks_exact(4)
+            .map(|p| {
+                ShaderColor::new(
+                    srgb_to_linear(p[0]),
+                    srgb_to_linear(p[1]),
+                    srgb_to_linear(p[2]),
+                    p[3] as f32 / 255.0,
+                )
+            })
+            .collect();
+        Ok(Texture { width: image.width, height: image.height, pixels })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let image = load_image(path)?;
+        Texture::from_image(&image).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn sample(&self, uv: (f32, f32)) -> ShaderColor {
+        let x = uv.0.rem_euclid(1.0) * self.width as f32 - 0.5;
+        let y = (1.0 - uv.1).rem_euclid(1.0) * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let texel = |x: f32, y: f32| {
+            let x = (x as i64).rem_euclid(self.width as i64) as usize;
+            let y = (y as i64).rem_euclid(self.height as i64) as usize;
+            self.pixels[y * self.width + x]
+        };
+        let lerp = |a: ShaderColor, b: ShaderColor, t: f32| {
+            ShaderColor::new(
+                a.r + (b.r - a.r) * t,
+                a.g + (b.g - a.g) * t,
+                a.b + (b.b - a.b) * t,
+                a.a + (b.a - a.a) * t,
+            )
+        };
+        let top = lerp(texel(x0, y0), texel(x0 + 1.0, y0), fx);
+        let bottom = lerp(texel(x0, y0 + 1.0), texel(x0 + 1.0, y0 + 1.0), fx);
+        lerp(top, bottom, fy)
+    }
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    (value as f32 / 255.0).powf(2.2)
+}
+
+// Modelo de iluminación del material
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shading {
+    // Difusa + especular Blinn-Phong con `specular` y `shininess` (MTL)
+    BlinnPhong,
+    // PBR metálico-rugoso de glTF: GGX + Smith + Fresnel de Schlick
+    MetallicRoughness { metallic: f32, roughness: f32 },
+}
+
+// Cómo se usa el alfa del color base (opacidad * textura)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlphaMode {
+    Opaque,
+    // Se descartan los fragmentos con alfa menor que el corte (hojas, rejas)
+    Mask(f32),
+    // Translúcido con mezcla alfa
+    Blend,
+}
+
+// Material de un modelo de archivo: del MTL (Kd, Ks, Ke, Ns, d, map_Kd,
+// map_Ks) o de glTF (color base, metálico, rugosidad, emisión)
+#[derive(Clone)]
+pub struct Material {
+    pub name: String,
+    // Color difuso / color base
+    pub diffuse: ShaderColor,
+    pub specular: ShaderColor,
+    pub emissive: ShaderColor,
+    pub shininess: f32,
+    pub opacity: f32,
+    pub shading: Shading,
+    pub alpha_mode: AlphaMode,
+    // Sin backface culling; la normal se da vuelta hacia la cámara
+    pub double_sided: bool,
+    pub diffuse_texture: Option<Arc<Texture>>,
+    pub specular_texture: Option<Arc<Texture>>,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material {
+            name: String::new(),
+            diffuse: ShaderColor::new(0.8, 0.8, 0.8, 1.0),
+            specular: ShaderColor::new(0.2, 0.2, 0.2, 1.0),
+            emissive: ShaderColor::new(0.0, 0.0, 0.0, 1.0),
+            shininess: DEFAULT_SHININESS,
+            opacity: 1.0,
+            shading: Shading::BlinnPhong,
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false,
+            diffuse_texture: None,
+            specular_texture: None,
+        }
+    }
+}
+
+impl Material {
+    // Las rutas de las texturas son relativas a `base_dir` (la carpeta del
+    // MTL); `textures` evita cargar dos veces la misma imagen. Una textura que
+    // no se puede leer se avisa y el material sigue sin ella
+    pub fn from_mtl(mtl: &tobj::Material, base_dir: &Path, textures: &mut TextureCache) -> Self {
+        let color = |c: Option<[f32; 3]>, default: ShaderColor| c.map_or(default, |c| ShaderColor::new(c[0], c[1], c[2], 1.0));
+        let defaults = Material::default();
+        let opacity = mtl.dissolve.unwrap_or(1.0).clamp(0.0, 1.0);
+        Material {
+            name: mtl.name.clone(),
+            diffuse: color(mtl.diffuse, defaults.diffuse),
+            specular: color(mtl.specular, defaults.specular),
+            // Ke: tobj 4.0.3 no lo interpreta, queda entre los parámetros desconocidos
+            emissive: color(mtl.unknown_param.get("Ke").and_then(|ke| parse_rgb(ke)), defaults.emissive),
+            // Ns va de 0 a 1000; 0 dejaría el especular encendido en todo el hemisferio
+            shininess: mtl.shininess.map_or(DEFAULT_SHININESS, |ns| ns.max(1.0)),
+            opacity,
+            alpha_mode: if opacity < 1.0 { AlphaMode::Blend } else { AlphaMode::Opaque },
+            diffuse_texture: mtl.diffuse_texture.as_deref().and_then(|name| textures.get(base_dir, name)),
+            specular_texture: mtl.specular_texture.as_deref().and_then(|name| textures.get(base_dir, name)),
+            ..defaults
+        }
+    }
+}
+
+fn parse_rgb(text: &str) -> Option<[f32; 3]> {
+    let values: Vec<f32> = text.split_whitespace().map(|v| v.parse().ok()).collect::<Option<_>>()?;
+    match values[..] {
+        [r, g, b] => Some([r, g, b]),
+        // Un solo valor vale para los tres canales
+        [v] => Some([v, v, v]),
+        _ => None,
+    }
+}
+
+// Texturas ya cargadas por ruta (los materiales de un modelo suelen compartirlas)
+#[derive(Default)]
+pub struct TextureCache {
+    textures: HashMap<PathBuf, Option<Arc<Texture>>>,
+}
+
+impl TextureCache {
+    pub fn get(&mut self, base_dir: &Path, name: &str) -> Option<Arc<Texture>> {
+        // Los MTL pueden traer opciones antes del archivo (-bm 1 foo.png):
+        // el nombre es el último campo
+        let file = name.split_whitespace().last()?;
+        let path = base_dir.join(file.replace('\\', "/"));
+        self.textures
+            .entry(path.clone())
+            .or_insert_with(|| match Texture::load(&path) {
+                Ok(texture) => Some(Arc::new(texture)),
+                Err(e) => {
+                    eprintln!("Aviso: {}", e);
+                    None
+                }
+            })
+            .clone()
+    }
+}
+
+// Shader de los modelos cargados de archivo: Blinn-Phong con todas las luces
+// y sombras de la escena, colores del material y UV del propio mesh
+#[derive(Clone)]
+pub struct MaterialShader {
+    pub material: Arc<Material>,
+}
+
+impl MaterialShader {
+    pub fn new(material: Material) -> Self {
+        MaterialShader { material: Arc::new(material) }
+    }
+
+    fn blinn_phong(&self, base: ShaderColor, uv: (f32, f32), position: Vector3, normal: Vector3, view_dir: Vector3, uniforms: &ShaderUniforms) -> ShaderColor {
+        let material = &self.material;
+        let spec_texel = material.specular_texture.as_ref().map_or(ShaderColor::new(1.0, 1.0, 1.0, 1.0), |t| t.sample(uv));
+        let specular = ShaderColor::new(
+            material.specular.r * spec_texel.r,
+            material.specular.g * spec_texel.g,
+            material.specular.b * spec_texel.b,
+            1.0,
+        );
+
+        let mut color = ShaderColor::new(base.r * AMBIENT, base.g * AMBIENT, base.b * AMBIENT, 1.0);
+        for (i, light) in uniforms.lights.iter().enumerate() {
+            if let Some((to_light, radiance)) = light.illuminate(position) {
+                let diffuse = normal.dot(&to_light).max(0.0);
+                if diffuse <= 0.0 {
+                    continue;
+                }
+                let highlight = blinn_phong(normal, to_light, view_dir, material.shininess);
+                let visibility = uniforms.visibility(i, position, normal);
+                color.r += radiance.r * (base.r * diffuse + specular.r * highlight) * visibility;
+                color.g += radiance.g * (base.g * diffuse + specular.g * highlight) * visibility;
+                color.b += radiance.b * (base.b * diffuse + specular.b * highlight) * visibility;
+            }
+        }
+        color
+    }
+}
+
+// Cook-Torrance con distribución GGX, sombreado de Smith-Schlick y Fresnel de
+// Schlick. Como en los demás shaders la difusa no se divide por PI (la
+// intensidad de las luces ya está pensada así), por eso el especular se multiplica por PI
+fn metallic_roughness(
+    base: ShaderColor,
+    metallic: f32,
+    roughness: f32,
+    position: Vector3,
+    normal: Vector3,
+    view_dir: Vector3,
+    uniforms: &ShaderUniforms,
+) -> ShaderColor {
+    let metallic = metallic.clamp(0.0, 1.0);
+    let alpha = (roughness.clamp(0.0, 1.0) * roughness.clamp(0.0, 1.0)).max(1e-3);
+    let alpha2 = alpha * alpha;
+    let k = alpha * 0.5;
+    // Reflectancia a incidencia normal: 4% en dieléctricos, el color base en metales
+    let base_rgb = [base.r, base.g, base.b];
+    let f0 = base_rgb.map(|c| 0.04 + (c - 0.04) * metallic);
+    let n_dot_v = normal.dot(&view_dir).max(1e-4);
+
+    let mut rgb = [0.0; 3];
+    for c in 0..3 {
+        rgb[c] = AMBIENT * (base_rgb[c] * (1.0 - metallic) + f0[c] * metallic);
+    }
+    for (i, light) in uniforms.lights.iter().enumerate() {
+        let Some((to_light, radiance)) = light.illuminate(position) else {
+            continue;
+        };
+        let n_dot_l = normal.dot(&to_light);
+        if n_dot_l <= 0.0 {
+            continue;
+        }
+        let half = (to_light + view_dir).normalize();
+        let n_dot_h = normal.dot(&half).max(0.0);
+        let v_dot_h = view_dir.dot(&half).max(0.0);
+
+        let d = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+        let distribution = alpha2 / (std::f32::consts::PI * d * d);
+        let geometry = (n_dot_l / (n_dot_l * (1.0 - k) + k)) * (n_dot_v / (n_dot_v * (1.0 - k) + k));
+        let schlick = (1.0 - v_dot_h).powi(5);
+
+        let visibility = uniforms.visibility(i, position, normal);
+        let radiance = [radiance.r, radiance.g, radiance.b];
+        for c in 0..3 {
+            let fresnel = f0[c] + (1.0 - f0[c]) * schlick;
+            let specular = distribution * geometry * fresnel / (4.0 * n_dot_l * n_dot_v);
+            let diffuse = (1.0 - fresnel) * (1.0 - metallic) * base_rgb[c];
+            rgb[c] += radiance[c] * (diffuse + specular * std::f32::consts::PI) * n_dot_l * visibility;
+        }
+    }
+    ShaderColor::new(rgb[0], rgb[1], rgb[2], 1.0)
+}
+
+impl PlanetShader for MaterialShader {
+    fn vertex_shader(&self, position: Vector3, normal: Vector3, _uv: (f32, f32), _uniforms: &ShaderUniforms) -> (Vector3, Vector3) {
+        (position, normal)
+    }
+
+    fn fragment_shader(&self, position: Vector3, normal: Vector3, uv: (f32, f32), uniforms: &ShaderUniforms) -> ShaderColor {
+        let material = &self.material;
+        let position = uniforms.world_position(position);
+        let mut normal = uniforms.world_normal(normal);
+        let view_dir = (uniforms.camera_position - position).normalize();
+        if material.double_sided && normal.dot(&view_dir) < 0.0 {
+            normal = normal * -1.0;
+        }
+
+        let texel = material.diffuse_texture.as_ref().map_or(ShaderColor::new(1.0, 1.0, 1.0, 1.0), |t| t.sample(uv));
+        let base = ShaderColor::new(
+            material.diffuse.r * texel.r,
+            material.diffuse.g * texel.g,
+            material.diffuse.b * texel.b,
+            material.opacity * texel.a,
+        );
+
+        let mut color = match material.shading {
+            Shading::BlinnPhong => self.blinn_phong(base, uv, position, normal, view_dir, uniforms),
+            Shading::MetallicRoughness { metallic, roughness } => {
+                metallic_roughness(base, metallic, roughness, position, normal, view_dir, uniforms)
+            }
+        };
+        color.r += material.emissive.r;
+        color.g += material.emissive.g;
+        color.b += material.emissive.b;
+        color.a = base.a;
+        color
+    }
+
+    fn blend_mode(&self) -> BlendMode {
+        if self.material.alpha_mode == AlphaMode::Blend {
+            BlendMode::Alpha
+        } else {
+            BlendMode::Opaque
+        }
+    }
+
+    fn alpha_cutoff(&self) -> Option<f32> {
+        match self.material.alpha_mode {
+            AlphaMode::Mask(cutoff) => Some(cutoff),
+            _ => None,
+        }
+    }
+
+    fn double_sided(&self) -> bool {
+        self.material.double_sided
+    }
+
+    fn uses_texcoords(&self) -> bool {
+        true
+    }
+}
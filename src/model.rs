@@ -0,0 +1,105 @@
+use crate::gltf::load_gltf_objects;
+use crate::material::{Material, MaterialShader, TextureCache};
+use crate::sphere::load_obj;
+use nalgebra::Matrix4;
+use std::path::Path;
+
+// Modelo cargado de archivo (nave, estación...) que se dibuja junto a la
+// estrella: un mesh con su material y su propia transformación al mundo
+pub struct SceneObject {
+    pub name: String,
+    pub mesh: tobj::Mesh,
+    pub shader: MaterialShader,
+    pub transform: Matrix4<f32>,
+}
+
+// Un objeto por modelo del OBJ, cada uno con su material del MTL y la
+// transformación `transform` (los OBJ no traen transformaciones propias: los
+// vértices ya están en el espacio del archivo)
+pub fn objects_from_obj(
+    models: Vec<tobj::Model>,
+    materials: &[tobj::Material],
+    base_dir: &Path,
+    transform: Matrix4<f32>,
+) -> Vec<SceneObject> {
+    let mut textures = TextureCache::default();
+    let materials: Vec<MaterialShader> = materials
+        .iter()
+        .map(|mtl| MaterialShader::new(Material::from_mtl(mtl, base_dir, &mut textures)))
+        .collect();
+    let fallback = MaterialShader::new(Material::default());
+
+    models
+        .into_iter()
+        .filter(|model| !model.mesh.indices.is_empty())
+        .map(|model| {
+            let shader = model.mesh.material_id.and_then(|id| materials.get(id)).unwrap_or(&fallback).clone();
+            SceneObject { name: model.name, mesh: model.mesh, shader, transform }
+        })
+        .collect()
+}
+
+// Cargar los objetos de un modelo según la extensión: .gltf/.glb o OBJ
+pub fn load_objects(path: &Path, transform: Matrix4<f32>) -> Result<Vec<SceneObject>, String> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+    if extension == "gltf" || extension == "glb" {
+        let objects = load_gltf_objects(path, transform)?;
+        log_objects(path, &objects);
+        Ok(objects)
+    } else {
+        load_obj_objects(path, transform)
+    }
+}
+
+fn load_obj_objects(path: &Path, transform: Matrix4<f32>) -> Result<Vec<SceneObject>, String> {
+    let (models, materials) = load_obj(path)?;
+    let materials = materials.unwrap_or_else(|e| {
+        eprintln!("Aviso: materiales de {}: {}", path.display(), e);
+        Vec::new()
+    });
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    let objects = objects_from_obj(models, &materials, base_dir, transform);
+    log_objects(path, &objects);
+    Ok(objects)
+}
+
+fn log_objects(path: &Path, objects: &[SceneObject]) {
+    eprintln!("{}: {} objeto(s)", path.display(), objects.len());
+    for object in objects {
+        let material = &object.shader.material.name;
+        let material = if material.is_empty() { "(por defecto)" } else { material.as_str() };
+        eprintln!("  {}: {} triángulos, material {}", object.name, object.mesh.indices.len() / 3, material);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::Vector3;
+
+    #[test]
+    fn obj_fixture_loads_one_object_per_model() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/estacion.obj");
+        let transform = Matrix4::new_translation(&Vector3::new(1.0, 2.0, 3.0));
+        let objects = load_objects(&path, transform).unwrap();
+
+        let summary: Vec<(&str, usize, &str)> =
+            objects.iter().map(|o| (o.name.as_str(), o.mesh.indices.len() / 3, o.shader.material.name.as_str())).collect();
+        assert_eq!(summary, [("modulo", 12, "metal"), ("panel", 2, "panel"), ("baliza", 1, "baliza")]);
+        assert!(objects.iter().all(|o| o.transform == transform));
+
+        // Normales calculadas donde el OBJ no las trae y UV donde sí
+        let modulo = &objects[0];
+        assert_eq!(modulo.mesh.normals.len(), modulo.mesh.positions.len());
+        assert_eq!(objects[1].mesh.texcoords.len(), 8);
+
+        // Transparencia, textura (relativa al MTL) y emisión del MTL
+        let panel = &objects[1].shader.material;
+        assert_eq!(panel.opacity, 0.5);
+        let texture = panel.diffuse_texture.as_ref().expect("map_Kd sin cargar");
+        assert_eq!((texture.width, texture.height), (9, 10));
+        let baliza = &objects[2].shader.material;
+        assert_eq!((baliza.emissive.r, baliza.emissive.g, baliza.emissive.b), (4.0, 0.6, 0.3));
+        assert!(baliza.diffuse_texture.is_none());
+    }
+}
@@ -0,0 +1,438 @@
+use crate::bloom::Bloom;
+use crate::rasterizer::{Framebuffer, Rgba};
+use crate::tonemap::ToneMapping;
+use std::any::Any;
+use std::path::Path;
+
+// Un paso de post-proceso sobre el Framebuffer ya rasterizado. Trabaja en
+// `hdr_buffer` (y puede leer `depth_buffer`); la cuantización a 8 bits la hace
+// la cadena al final. Antes del tone mapping el color es HDR lineal, después
+// queda en el rango de pantalla [0, 1]
+pub trait PostEffect: Any + Send {
+    // Nombre corto, el mismo que acepta `--post`
+    fn name(&self) -> &'static str;
+    // `time` = tiempo de la escena (para efectos animados como el grano)
+    fn apply(&self, fb: &mut Framebuffer, time: f32);
+}
+
+// Efectos incluidos, en el orden de la cadena por defecto
+pub const EFFECT_NAMES: [&str; 7] = ["bloom", "tonemap", "grade", "aberration", "vignette", "grain", "gamma"];
+
+fn builtin(name: &str) -> Option<Box<dyn PostEffect>> {
+    Some(match name {
+        "bloom" => Box::new(Bloom::default()),
+        "tonemap" => Box::new(ToneMapping::default()),
+        "grade" => Box::new(ColorGrading::default()),
+        "aberration" => Box::new(ChromaticAberration::default()),
+        "vignette" => Box::new(Vignette::default()),
+        "grain" => Box::new(FilmGrain::default()),
+        "gamma" => Box::new(Gamma::default()),
+        _ => return None,
+    })
+}
+
+// Lista ordenada de efectos, cada uno activable por separado
+pub struct PostChain {
+    effects: Vec<(Box<dyn PostEffect>, bool)>,
+}
+
+impl Default for PostChain {
+    // Todos los efectos incluidos; solo bloom y tone mapping activos
+    fn default() -> Self {
+        let mut chain = PostChain { effects: Vec::new() };
+        for name in EFFECT_NAMES {
+            chain = chain.with_effect(builtin(name).unwrap(), matches!(name, "bloom" | "tonemap"));
+        }
+        chain
+    }
+}
+
+impl PostChain {
+    // Cadena con los efectos nombrados activos y en ese orden; el resto de
+    // los incluidos se agrega al final, desactivado
+    pub fn from_names(names: &[String]) -> Result<Self, String> {
+        let mut chain = PostChain { effects: Vec::new() };
+        for name in names {
+            if chain.position(name).is_some() {
+                return Err(format!("Efecto repetido en --post: {}", name));
+            }
+            let effect = builtin(name)
+                .ok_or_else(|| format!("Efecto desconocido: {} ({})", name, EFFECT_NAMES.join("|")))?;
+            chain = chain.with_effect(effect, true);
+        }
+        for name in EFFECT_NAMES {
+            if chain.position(name).is_none() {
+                chain = chain.with_effect(builtin(name).unwrap(), false);
+            }
+        }
+        Ok(chain)
+    }
+
+    pub fn with_effect(mut self, effect: Box<dyn PostEffect>, enabled: bool) -> Self {
+        self.effects.push((effect, enabled));
+        self
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.effects.iter().position(|(effect, _)| effect.name() == name)
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.position(name).is_some_and(|i| self.effects[i].1)
+    }
+
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) {
+        if let Some(i) = self.position(name) {
+            self.effects[i].1 = enabled;
+        }
+    }
+
+    // Alternar un efecto; devuelve el nuevo estado
+    pub fn toggle(&mut self, name: &str) -> bool {
+        let enabled = !self.is_enabled(name);
+        self.set_enabled(name, enabled);
+        enabled
+    }
+
+    // Acceso a un efecto por tipo para cambiar sus parámetros en vivo
+    pub fn get<T: PostEffect>(&self) -> Option<&T> {
+        self.effects.iter().find_map(|(effect, _)| (effect.as_ref() as &dyn Any).downcast_ref::<T>())
+    }
+
+    pub fn get_mut<T: PostEffect>(&mut self) -> Option<&mut T> {
+        self.effects.iter_mut().find_map(|(effect, _)| (effect.as_mut() as &mut dyn Any).downcast_mut::<T>())
+    }
+
+    // Nombres de los efectos activos, en orden de aplicación
+    pub fn enabled_names(&self) -> Vec<&'static str> {
+        self.effects.iter().filter(|(_, enabled)| *enabled).map(|(effect, _)| effect.name()).collect()
+    }
+
+    // Aplicar los efectos activos y escribir el color_buffer (0x00RRGGBB) que
+    // se muestra o guarda. Es el único punto donde el color se cuantiza a 8 bits
+    pub fn run(&self, fb: &mut Framebuffer, time: f32) {
+        for (effect, enabled) in &self.effects {
+            if *enabled {
+                effect.apply(fb, time);
+            }
+        }
+        let quantize = |x: f32| (x.clamp(0.0, 1.0) * 255.0) as u32;
+        for (out, c) in fb.color_buffer.iter_mut().zip(&fb.hdr_buffer) {
+            *out = (quantize(c[0]) << 16) | (quantize(c[1]) << 8) | quantize(c[2]);
+        }
+    }
+}
+
+fn luminance(c: &Rgba) -> f32 {
+    0.2126 * c[0] + 0.7152 * c[1] + 0.0722 * c[2]
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0).max(1e-6)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Distancia normalizada al centro de la imagen (1 = esquina) y dirección en píxeles
+fn from_center(fb: &Framebuffer, x: usize, y: usize) -> (f32, f32, f32) {
+    let (cx, cy) = (fb.width as f32 * 0.5, fb.height as f32 * 0.5);
+    let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+    let half_diagonal = (cx * cx + cy * cy).sqrt().max(1e-6);
+    ((dx * dx + dy * dy).sqrt() / half_diagonal, dx, dy)
+}
+
+// Oscurecer los bordes de la imagen
+#[derive(Debug, Clone, Copy)]
+pub struct Vignette {
+    // Oscurecimiento máximo en las esquinas (0 = nada, 1 = negro)
+    pub intensity: f32,
+    // Distancia al centro (1 = esquina) donde empieza a oscurecer
+    pub radius: f32,
+    // Ancho de la transición
+    pub softness: f32,
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Vignette { intensity: 0.6, radius: 0.45, softness: 0.55 }
+    }
+}
+
+impl PostEffect for Vignette {
+    fn name(&self) -> &'static str {
+        "vignette"
+    }
+
+    fn apply(&self, fb: &mut Framebuffer, _time: f32) {
+        for y in 0..fb.height {
+            for x in 0..fb.width {
+                let (r, _, _) = from_center(fb, x, y);
+                let factor = 1.0 - self.intensity * smoothstep(self.radius, self.radius + self.softness, r);
+                let c = &mut fb.hdr_buffer[y * fb.width + x];
+                for v in &mut c[..3] {
+                    *v *= factor;
+                }
+            }
+        }
+    }
+}
+
+// Separar los canales rojo y azul hacia los bordes, como una lente barata
+#[derive(Debug, Clone, Copy)]
+pub struct ChromaticAberration {
+    // Desplazamiento de rojo y azul en las esquinas, en píxeles
+    pub strength: f32,
+}
+
+impl Default for ChromaticAberration {
+    fn default() -> Self {
+        ChromaticAberration { strength: 3.0 }
+    }
+}
+
+impl PostEffect for ChromaticAberration {
+    fn name(&self) -> &'static str {
+        "aberration"
+    }
+
+    fn apply(&self, fb: &mut Framebuffer, _time: f32) {
+        let source = fb.hdr_buffer.clone();
+        let (w, h) = (fb.width, fb.height);
+        // Canal `k` muestreado bilinealmente en coordenadas de píxel (centros en .5)
+        let sample = |x: f32, y: f32, k: usize| {
+            let (fx, fy) = (x - 0.5, y - 0.5);
+            let (x0, y0) = (fx.floor() as isize, fy.floor() as isize);
+            let (tx, ty) = (fx - fx.floor(), fy - fy.floor());
+            let at = |x: isize, y: isize| {
+                let x = x.clamp(0, w as isize - 1) as usize;
+                let y = y.clamp(0, h as isize - 1) as usize;
+                source[y * w + x][k]
+            };
+            let top = at(x0, y0) * (1.0 - tx) + at(x0 + 1, y0) * tx;
+            let bottom = at(x0, y0 + 1) * (1.0 - tx) + at(x0 + 1, y0 + 1) * tx;
+            top * (1.0 - ty) + bottom * ty
+        };
+
+        for y in 0..h {
+            for x in 0..w {
+                // El desplazamiento crece con la distancia al centro
+                let (r, dx, dy) = from_center(fb, x, y);
+                let length = (dx * dx + dy * dy).sqrt().max(1e-6);
+                let (ox, oy) = (dx / length * self.strength * r, dy / length * self.strength * r);
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let c = &mut fb.hdr_buffer[y * w + x];
+                c[0] = sample(px - ox, py - oy, 0);
+                c[2] = sample(px + ox, py + oy, 2);
+            }
+        }
+    }
+}
+
+// Ruido de película monocromático, distinto en cada frame
+#[derive(Debug, Clone, Copy)]
+pub struct FilmGrain {
+    // Amplitud del ruido
+    pub intensity: f32,
+}
+
+impl Default for FilmGrain {
+    fn default() -> Self {
+        FilmGrain { intensity: 0.06 }
+    }
+}
+
+// Hash entero -> [0, 1) (PCG), suficiente para ruido por píxel
+fn hash(x: u32, y: u32, seed: u32) -> f32 {
+    let mut v = x.wrapping_mul(1973).wrapping_add(y.wrapping_mul(9277)).wrapping_add(seed.wrapping_mul(26699)) | 1;
+    v = v.wrapping_mul(747796405).wrapping_add(2891336453);
+    v = ((v >> ((v >> 28) + 4)) ^ v).wrapping_mul(277803737);
+    v = (v >> 22) ^ v;
+    v as f32 / u32::MAX as f32
+}
+
+impl PostEffect for FilmGrain {
+    fn name(&self) -> &'static str {
+        "grain"
+    }
+
+    fn apply(&self, fb: &mut Framebuffer, time: f32) {
+        // Semilla derivada del tiempo: el mismo frame siempre da el mismo grano
+        let seed = time.to_bits();
+        for y in 0..fb.height {
+            for x in 0..fb.width {
+                // Suma de dos uniformes: distribución triangular en [-1, 1]
+                let noise = hash(x as u32, y as u32, seed) + hash(y as u32, x as u32, seed ^ 0x9E37_79B9) - 1.0;
+                let c = &mut fb.hdr_buffer[y * fb.width + x];
+                // Más visible en los medios tonos que en negros y blancos
+                let l = luminance(c).clamp(0.0, 1.0);
+                let amount = noise * self.intensity * (0.1 + 3.0 * l * (1.0 - l));
+                for v in &mut c[..3] {
+                    *v += amount;
+                }
+            }
+        }
+    }
+}
+
+// LUT 3D de color: `size`³ entradas RGB con el rojo variando más rápido
+// (el orden del formato .cube)
+#[derive(Debug, Clone)]
+pub struct ColorLut {
+    size: usize,
+    data: Vec<[f32; 3]>,
+}
+
+impl ColorLut {
+    pub fn from_fn(size: usize, f: impl Fn([f32; 3]) -> [f32; 3]) -> Self {
+        let size = size.max(2);
+        let step = 1.0 / (size - 1) as f32;
+        let mut data = Vec::with_capacity(size * size * size);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    data.push(f([r as f32 * step, g as f32 * step, b as f32 * step]));
+                }
+            }
+        }
+        ColorLut { size, data }
+    }
+
+    // Leer un archivo .cube (Adobe/Resolve) con LUT_3D_SIZE y dominio [0, 1]
+    pub fn load_cube(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("No se pudo leer {}: {}", path.display(), e))?;
+        let invalid = |line: &str| format!("Linea invalida en {}: {}", path.display(), line);
+
+        let mut size = None;
+        let mut data = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let first = parts.next().unwrap();
+            if first.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                if first == "LUT_3D_SIZE" {
+                    let n: usize = parts.next().and_then(|v| v.parse().ok()).ok_or_else(|| invalid(line))?;
+                    size = Some(n);
+                }
+                // TITLE, DOMAIN_MIN/MAX, etc.: se asume el dominio por defecto
+                continue;
+            }
+            let values: Vec<f32> = line.split_whitespace().map(|v| v.parse()).collect::<Result<_, _>>().map_err(|_| invalid(line))?;
+            if values.len() != 3 {
+                return Err(invalid(line));
+            }
+            data.push([values[0], values[1], values[2]]);
+        }
+
+        let size = size.ok_or_else(|| format!("{} no tiene LUT_3D_SIZE", path.display()))?;
+        if size < 2 || data.len() != size * size * size {
+            return Err(format!(
+                "{}: se esperaban {} entradas para LUT_3D_SIZE {}, hay {}",
+                path.display(),
+                size * size * size,
+                size,
+                data.len()
+            ));
+        }
+        Ok(ColorLut { size, data })
+    }
+
+    fn at(&self, r: usize, g: usize, b: usize) -> [f32; 3] {
+        self.data[(b * self.size + g) * self.size + r]
+    }
+
+    // Interpolación trilineal; la entrada se recorta a [0, 1]
+    pub fn sample(&self, c: [f32; 3]) -> [f32; 3] {
+        let max = (self.size - 1) as f32;
+        let mut i0 = [0; 3];
+        let mut t = [0.0; 3];
+        for k in 0..3 {
+            let x = c[k].clamp(0.0, 1.0) * max;
+            i0[k] = (x.floor() as usize).min(self.size - 2);
+            t[k] = x - i0[k] as f32;
+        }
+
+        let mut out = [0.0; 3];
+        for corner in 0..8 {
+            let (dr, dg, db) = (corner & 1, (corner >> 1) & 1, (corner >> 2) & 1);
+            let w = (if dr == 1 { t[0] } else { 1.0 - t[0] })
+                * (if dg == 1 { t[1] } else { 1.0 - t[1] })
+                * (if db == 1 { t[2] } else { 1.0 - t[2] });
+            let v = self.at(i0[0] + dr, i0[1] + dg, i0[2] + db);
+            for k in 0..3 {
+                out[k] += v[k] * w;
+            }
+        }
+        out
+    }
+}
+
+// Corrección de color con una LUT 3D (sobre el color ya en rango de pantalla)
+#[derive(Debug, Clone)]
+pub struct ColorGrading {
+    pub lut: ColorLut,
+    // Mezcla entre la imagen original (0) y la corregida (1)
+    pub strength: f32,
+}
+
+impl Default for ColorGrading {
+    // Sin archivo .cube: sombras hacia el cian, luces hacia el naranja y un
+    // poco más de contraste
+    fn default() -> Self {
+        let lut = ColorLut::from_fn(17, |c| {
+            let l = 0.2126 * c[0] + 0.7152 * c[1] + 0.0722 * c[2];
+            // Sin tocar el negro ni el blanco puros
+            let weight = (l - 0.5) * 4.0 * l * (1.0 - l);
+            let tint = [0.2, 0.05, -0.2];
+            let mut out = [0.0; 3];
+            for k in 0..3 {
+                let v = c[k] + weight * tint[k];
+                out[k] = v + (smoothstep(0.0, 1.0, v) - v) * 0.35;
+            }
+            out
+        });
+        ColorGrading { lut, strength: 1.0 }
+    }
+}
+
+impl PostEffect for ColorGrading {
+    fn name(&self) -> &'static str {
+        "grade"
+    }
+
+    fn apply(&self, fb: &mut Framebuffer, _time: f32) {
+        for c in fb.hdr_buffer.iter_mut() {
+            let graded = self.lut.sample([c[0], c[1], c[2]]);
+            for k in 0..3 {
+                c[k] += (graded[k] - c[k]) * self.strength;
+            }
+        }
+    }
+}
+
+// Codificación gamma: color^(1/gamma)
+#[derive(Debug, Clone, Copy)]
+pub struct Gamma {
+    pub gamma: f32,
+}
+
+impl Default for Gamma {
+    fn default() -> Self {
+        Gamma { gamma: 2.2 }
+    }
+}
+
+impl PostEffect for Gamma {
+    fn name(&self) -> &'static str {
+        "gamma"
+    }
+
+    fn apply(&self, fb: &mut Framebuffer, _time: f32) {
+        let exponent = 1.0 / self.gamma.max(1e-3);
+        for c in fb.hdr_buffer.iter_mut() {
+            for v in &mut c[..3] {
+                *v = v.max(0.0).powf(exponent);
+            }
+        }
+    }
+}
@@ -1,11 +1,23 @@
-// Legacy code no usado en pipeline actual (renderer.rs hace rasterización directa)
-#[allow(unused_imports)]
-use nalgebra::{Vector3, Vector4};
+// Color lineal RGBA sin límite superior (HDR)
+pub type Rgba = [f32; 4];
+
+// 0x00RRGGBB -> RGBA en [0, 1]
+pub fn unpack_color(c: u32) -> Rgba {
+    [
+        ((c >> 16) & 0xFF) as f32 / 255.0,
+        ((c >> 8) & 0xFF) as f32 / 255.0,
+        (c & 0xFF) as f32 / 255.0,
+        1.0,
+    ]
+}
 
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
+    // Imagen final para mostrar/guardar (0x00RRGGBB), escrita por el tone mapping
     pub color_buffer: Vec<u32>,
+    // Donde rasteriza el pipeline: color HDR en f32, sin recortar a [0, 1]
+    pub hdr_buffer: Vec<Rgba>,
     pub depth_buffer: Vec<f32>,
 }
 
@@ -15,120 +27,160 @@ impl Framebuffer {
             width,
             height,
             color_buffer: vec![0; width * height],
+            hdr_buffer: vec![[0.0, 0.0, 0.0, 1.0]; width * height],
+            depth_buffer: vec![f32::INFINITY; width * height],
+        }
+    }
+
+    // Solo profundidad (p. ej. shadow maps): sin buffers de color
+    pub fn new_depth_only(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            color_buffer: Vec::new(),
+            hdr_buffer: Vec::new(),
             depth_buffer: vec![f32::INFINITY; width * height],
         }
     }
 
     pub fn clear(&mut self, color: u32) {
         self.color_buffer.fill(color);
+        self.hdr_buffer.fill(unpack_color(color));
         self.depth_buffer.fill(f32::INFINITY);
     }
 
-    pub fn set_pixel(&mut self, x: usize, y: usize, depth: f32, color: u32) {
+    // Combina el color con lo ya dibujado según `mode` si pasa el test de
+    // profundidad; `depth_write` decide si además ocupa el depth buffer
+    pub fn blend_pixel(&mut self, x: usize, y: usize, depth: f32, color: Rgba, mode: BlendMode, depth_write: bool) {
         if x >= self.width || y >= self.height {
             return;
         }
-        
+
         let index = y * self.width + x;
         if depth < self.depth_buffer[index] {
-            self.depth_buffer[index] = depth;
-            self.color_buffer[index] = color;
+            if depth_write {
+                self.depth_buffer[index] = depth;
+            }
+            blend(&mut self.hdr_buffer[index], color, mode);
         }
     }
 }
 
-// Legacy structures/functions no usados, mantenidos por compatibilidad
-#[allow(dead_code)]
-#[derive(Clone, Copy, Debug)]
-pub struct VertexShaderOutput {
-    pub position: Vector4<f32>,
-    pub world_pos: Vector3<f32>,
-    pub v_pos: Vector3<f32>,
-}
-
-#[allow(dead_code)]
-pub fn ndc_to_screen(ndc: &Vector4<f32>, width: f32, height: f32) -> (f32, f32, f32) {
-    let x = (ndc.x / ndc.w + 1.0) * 0.5 * width;
-    let y = (1.0 - (ndc.y / ndc.w + 1.0) * 0.5) * height;
-    let z = ndc.z / ndc.w;
-    (x, y, z)
+// Cómo se combina el color de un fragmento (src) con el del framebuffer (dst)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    // dst = src
+    Opaque,
+    // dst = src * alfa + dst * (1 - alfa)
+    Alpha,
+    // dst = dst + src * alfa (luz que se suma: coronas, brillos)
+    Additive,
+    // dst = src + dst * (1 - alfa): el color ya viene multiplicado por alfa
+    // (dispersión, donde la luz emitida y la opacidad son independientes)
+    Premultiplied,
 }
 
-#[allow(dead_code)]
-pub fn draw_triangle<F>(
-    fb: &mut Framebuffer,
-    v0: &VertexShaderOutput,
-    v1: &VertexShaderOutput,
-    v2: &VertexShaderOutput,
-    fragment_shader: F,
-)
-where
-    F: Fn(&Vector3<f32>, &Vector3<f32>) -> u32,
-{
-    let width = fb.width as f32;
-    let height = fb.height as f32;
-
-    let (x0, y0, z0) = ndc_to_screen(&v0.position, width, height);
-    let (x1, y1, z1) = ndc_to_screen(&v1.position, width, height);
-    let (x2, y2, z2) = ndc_to_screen(&v2.position, width, height);
-
-    let min_x = x0.min(x1).min(x2).floor().max(0.0) as usize;
-    let max_x = x0.max(x1).max(x2).ceil().min(width - 1.0) as usize;
-    let min_y = y0.min(y1).min(y2).floor().max(0.0) as usize;
-    let max_y = y0.max(y1).max(y2).ceil().min(height - 1.0) as usize;
-
-    for py in min_y..=max_y {
-        for px in min_x..=max_x {
-            let p_x = px as f32 + 0.5;
-            let p_y = py as f32 + 0.5;
-
-            let (w0, w1, w2) = barycentric(x0, y0, x1, y1, x2, y2, p_x, p_y);
-
-            if w0 >= 0.0 && w1 >= 0.0 && w2 >= 0.0 {
-                let depth = w0 * z0 + w1 * z1 + w2 * z2;
-
-                let world_pos = Vector3::new(
-                    w0 * v0.world_pos.x + w1 * v1.world_pos.x + w2 * v2.world_pos.x,
-                    w0 * v0.world_pos.y + w1 * v1.world_pos.y + w2 * v2.world_pos.y,
-                    w0 * v0.world_pos.z + w1 * v1.world_pos.z + w2 * v2.world_pos.z,
-                );
-
-                let v_pos = Vector3::new(
-                    w0 * v0.v_pos.x + w1 * v1.v_pos.x + w2 * v2.v_pos.x,
-                    w0 * v0.v_pos.y + w1 * v1.v_pos.y + w2 * v2.v_pos.y,
-                    w0 * v0.v_pos.z + w1 * v1.v_pos.z + w2 * v2.v_pos.z,
-                );
-
-                let color = fragment_shader(&world_pos, &v_pos);
-                fb.set_pixel(px, py, depth, color);
+fn blend(dst: &mut Rgba, src: Rgba, mode: BlendMode) {
+    let a = src[3].clamp(0.0, 1.0);
+    match mode {
+        BlendMode::Opaque => *dst = src,
+        BlendMode::Alpha => {
+            for k in 0..3 {
+                dst[k] = src[k] * a + dst[k] * (1.0 - a);
+            }
+        }
+        BlendMode::Additive => {
+            for k in 0..3 {
+                dst[k] += src[k] * a;
+            }
+        }
+        BlendMode::Premultiplied => {
+            for k in 0..3 {
+                dst[k] = src[k] + dst[k] * (1.0 - a);
             }
         }
     }
 }
 
-#[allow(dead_code)]
-fn barycentric(
-    x0: f32, y0: f32,
-    x1: f32, y1: f32,
-    x2: f32, y2: f32,
-    px: f32, py: f32,
-) -> (f32, f32, f32) {
-    let denom = (y1 - y2) * (x0 - x2) + (x2 - x1) * (y0 - y2);
-    if denom.abs() < 1e-10 {
-        return (-1.0, -1.0, -1.0);
+// Destino de rasterización: el framebuffer completo o un tile de él
+pub trait RasterTarget {
+    // Rectángulo cubierto en coordenadas de pantalla: (x0, y0, x1, y1), extremos exclusivos
+    fn bounds(&self) -> (usize, usize, usize, usize);
+    fn depth_at(&self, x: usize, y: usize) -> f32;
+    fn blend_pixel(&mut self, x: usize, y: usize, depth: f32, color: Rgba, mode: BlendMode, depth_write: bool);
+}
+
+impl RasterTarget for Framebuffer {
+    fn bounds(&self) -> (usize, usize, usize, usize) {
+        (0, 0, self.width, self.height)
+    }
+
+    fn depth_at(&self, x: usize, y: usize) -> f32 {
+        self.depth_buffer[y * self.width + x]
+    }
+
+    fn blend_pixel(&mut self, x: usize, y: usize, depth: f32, color: Rgba, mode: BlendMode, depth_write: bool) {
+        Framebuffer::blend_pixel(self, x, y, depth, color, mode, depth_write);
     }
+}
+
+// Lado (en píxeles) de los tiles del rasterizador multihilo
+pub const TILE_SIZE: usize = 64;
 
-    let w0 = ((y1 - y2) * (px - x2) + (x2 - x1) * (py - y2)) / denom;
-    let w1 = ((y2 - y0) * (px - x2) + (x0 - x2) * (py - y2)) / denom;
-    let w2 = 1.0 - w0 - w1;
+// Copia local de una región del framebuffer; cada hilo rasteriza sobre la suya
+pub struct Tile {
+    pub x0: usize,
+    pub y0: usize,
+    pub width: usize,
+    pub height: usize,
+    pub hdr_buffer: Vec<Rgba>,
+    pub depth_buffer: Vec<f32>,
+}
 
-    (w0, w1, w2)
+impl Tile {
+    pub fn from_framebuffer(fb: &Framebuffer, x0: usize, y0: usize, width: usize, height: usize) -> Self {
+        let mut hdr_buffer = Vec::with_capacity(width * height);
+        let mut depth_buffer = Vec::with_capacity(width * height);
+        for y in y0..y0 + height {
+            let row = y * fb.width;
+            hdr_buffer.extend_from_slice(&fb.hdr_buffer[row + x0..row + x0 + width]);
+            depth_buffer.extend_from_slice(&fb.depth_buffer[row + x0..row + x0 + width]);
+        }
+        Tile { x0, y0, width, height, hdr_buffer, depth_buffer }
+    }
+
+    // Devolver el contenido del tile a su región del framebuffer
+    pub fn write_back(&self, fb: &mut Framebuffer) {
+        for ty in 0..self.height {
+            let dst = (self.y0 + ty) * fb.width + self.x0;
+            let src = ty * self.width;
+            fb.hdr_buffer[dst..dst + self.width].copy_from_slice(&self.hdr_buffer[src..src + self.width]);
+            fb.depth_buffer[dst..dst + self.width].copy_from_slice(&self.depth_buffer[src..src + self.width]);
+        }
+    }
 }
 
-#[allow(dead_code)]
-pub fn rgb_to_u32(r: f32, g: f32, b: f32) -> u32 {
-    let r = (r.clamp(0.0, 1.0) * 255.0) as u32;
-    let g = (g.clamp(0.0, 1.0) * 255.0) as u32;
-    let b = (b.clamp(0.0, 1.0) * 255.0) as u32;
-    (r << 16) | (g << 8) | b
+impl RasterTarget for Tile {
+    fn bounds(&self) -> (usize, usize, usize, usize) {
+        (self.x0, self.y0, self.x0 + self.width, self.y0 + self.height)
+    }
+
+    fn depth_at(&self, x: usize, y: usize) -> f32 {
+        self.depth_buffer[(y - self.y0) * self.width + (x - self.x0)]
+    }
+
+    fn blend_pixel(&mut self, x: usize, y: usize, depth: f32, color: Rgba, mode: BlendMode, depth_write: bool) {
+        let (x0, y0, x1, y1) = self.bounds();
+        if x < x0 || x >= x1 || y < y0 || y >= y1 {
+            return;
+        }
+
+        let index = (y - y0) * self.width + (x - x0);
+        if depth < self.depth_buffer[index] {
+            if depth_write {
+                self.depth_buffer[index] = depth;
+            }
+            blend(&mut self.hdr_buffer[index], color, mode);
+        }
+    }
 }
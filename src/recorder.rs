@@ -0,0 +1,551 @@
+use crate::image_io::{png_ihdr, png_scanlines, write_png_chunk, zlib_compress, BitWriter, PNG_SIGNATURE};
+use crate::rasterizer::Framebuffer;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Formatos de animación que sabe escribir el grabador
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+}
+
+impl AnimationFormat {
+    // Se elige por extensión: .gif -> GIF, .png / .apng -> APNG
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("gif") => Ok(AnimationFormat::Gif),
+            Some("png") | Some("apng") => Ok(AnimationFormat::Apng),
+            _ => Err(format!("Extension de animacion no soportada: {} (.gif, .png, .apng)", path.display())),
+        }
+    }
+}
+
+// Número de frames y dt ajustado para que `period` segundos se repitan sin
+// salto: se graban los frames 0..n-1 y el frame n coincidiría con el 0
+pub fn seamless_loop(period: f32, dt: f32) -> (usize, f32) {
+    let frames = ((period / dt).round() as usize).max(1);
+    (frames, period / frames as f32)
+}
+
+// Graba frames del framebuffer y los codifica como GIF animado o APNG.
+// Los frames se comprimen al llegar, así una grabación larga no guarda
+// todos los píxeles en memoria.
+pub struct Recorder {
+    path: PathBuf,
+    format: AnimationFormat,
+    width: usize,
+    height: usize,
+    // Datos ya codificados de cada frame (LZW para GIF, zlib para APNG)
+    frames: Vec<Vec<u8>>,
+    // Duración de cada frame en segundos
+    delays: Vec<f32>,
+    // Paleta global del GIF; se calcula con el primer frame para no guardar
+    // los píxeles de toda la grabación, así que los colores que aparecen
+    // después se aproximan con los de ese frame
+    palette: Option<Palette>,
+}
+
+impl Recorder {
+    pub fn new(path: &Path, width: usize, height: usize) -> Result<Self, String> {
+        let format = AnimationFormat::from_path(path)?;
+        // El GIF guarda ancho y alto en 16 bits
+        if format == AnimationFormat::Gif && (width > u16::MAX as usize || height > u16::MAX as usize) {
+            return Err(format!("GIF de {}x{}: el máximo es {} píxeles por lado", width, height, u16::MAX));
+        }
+        Ok(Recorder {
+            path: path.to_path_buf(),
+            format,
+            width,
+            height,
+            frames: Vec::new(),
+            delays: Vec::new(),
+            palette: None,
+        })
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Agregar un frame que dura `dt` segundos
+    pub fn push_frame(&mut self, fb: &Framebuffer, dt: f32) {
+        let data = match self.format {
+            AnimationFormat::Gif => {
+                let palette = self
+                    .palette
+                    .get_or_insert_with(|| Palette::median_cut(&fb.color_buffer, 256));
+                let indices: Vec<u8> = fb.color_buffer.iter().map(|&c| palette.index_of(c)).collect();
+                lzw_encode(&indices, 8)
+            }
+            AnimationFormat::Apng => zlib_compress(&png_scanlines(self.width, self.height, &fb.color_buffer)),
+        };
+        self.frames.push(data);
+        self.delays.push(dt);
+    }
+
+    // Escribir el archivo con todos los frames grabados
+    pub fn finish(&self) -> Result<(), String> {
+        if self.frames.is_empty() {
+            return Err("No hay frames grabados".to_string());
+        }
+        let bytes = match self.format {
+            AnimationFormat::Gif => self.encode_gif(),
+            AnimationFormat::Apng => self.encode_apng(),
+        };
+        fs::write(&self.path, bytes).map_err(|e| format!("No se pudo escribir {}: {}", self.path.display(), e))
+    }
+
+    fn encode_gif(&self) -> Vec<u8> {
+        let palette = self.palette.as_ref().expect("Paleta sin calcular");
+        let mut out = Vec::new();
+
+        // Cabecera + Logical Screen Descriptor con tabla de color global de 256
+        out.extend_from_slice(b"GIF89a");
+        out.extend_from_slice(&(self.width as u16).to_le_bytes());
+        out.extend_from_slice(&(self.height as u16).to_le_bytes());
+        out.extend_from_slice(&[0xF7, 0, 0]);
+        for i in 0..256 {
+            let c = palette.colors.get(i).copied().unwrap_or(0);
+            out.extend_from_slice(&[(c >> 16) as u8, (c >> 8) as u8, c as u8]);
+        }
+
+        // Extensión NETSCAPE2.0: repetir indefinidamente
+        out.extend_from_slice(&[0x21, 0xFF, 0x0B]);
+        out.extend_from_slice(b"NETSCAPE2.0");
+        out.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+
+        for (data, delay) in self.frames.iter().zip(quantize_delays(&self.delays, 100)) {
+            // Graphic Control Extension con el retardo del frame
+            out.extend_from_slice(&[0x21, 0xF9, 0x04, 0x00]);
+            out.extend_from_slice(&delay.to_le_bytes());
+            out.extend_from_slice(&[0x00, 0x00]);
+
+            // Image Descriptor a pantalla completa, sin tabla local
+            out.push(0x2C);
+            out.extend_from_slice(&[0, 0, 0, 0]);
+            out.extend_from_slice(&(self.width as u16).to_le_bytes());
+            out.extend_from_slice(&(self.height as u16).to_le_bytes());
+            out.push(0x00);
+
+            // Datos LZW en sub-bloques de hasta 255 bytes
+            out.push(8);
+            for block in data.chunks(255) {
+                out.push(block.len() as u8);
+                out.extend_from_slice(block);
+            }
+            out.push(0x00);
+        }
+
+        out.push(0x3B);
+        out
+    }
+
+    fn encode_apng(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&PNG_SIGNATURE);
+        write_png_chunk(&mut out, b"IHDR", &png_ihdr(self.width, self.height));
+
+        // acTL: número de frames y repeticiones (0 = infinito)
+        let mut actl = Vec::with_capacity(8);
+        actl.extend_from_slice(&(self.frames.len() as u32).to_be_bytes());
+        actl.extend_from_slice(&0u32.to_be_bytes());
+        write_png_chunk(&mut out, b"acTL", &actl);
+
+        let mut sequence = 0u32;
+        let delays = quantize_delays(&self.delays, 1000);
+        for (i, (data, delay)) in self.frames.iter().zip(delays).enumerate() {
+            let mut fctl = Vec::with_capacity(26);
+            fctl.extend_from_slice(&sequence.to_be_bytes());
+            fctl.extend_from_slice(&(self.width as u32).to_be_bytes());
+            fctl.extend_from_slice(&(self.height as u32).to_be_bytes());
+            fctl.extend_from_slice(&0u32.to_be_bytes());
+            fctl.extend_from_slice(&0u32.to_be_bytes());
+            // Retardo en milisegundos
+            fctl.extend_from_slice(&delay.to_be_bytes());
+            fctl.extend_from_slice(&1000u16.to_be_bytes());
+            // dispose = none, blend = source
+            fctl.extend_from_slice(&[0, 0]);
+            write_png_chunk(&mut out, b"fcTL", &fctl);
+            sequence += 1;
+
+            if i == 0 {
+                // El primer frame es también la imagen por defecto
+                write_png_chunk(&mut out, b"IDAT", data);
+            } else {
+                let mut fdat = Vec::with_capacity(data.len() + 4);
+                fdat.extend_from_slice(&sequence.to_be_bytes());
+                fdat.extend_from_slice(data);
+                write_png_chunk(&mut out, b"fdAT", &fdat);
+                sequence += 1;
+            }
+        }
+
+        write_png_chunk(&mut out, b"IEND", &[]);
+        out
+    }
+}
+
+// Retardos en ticks (centésimas para GIF, milésimas para APNG) acumulando el
+// error de redondeo, para que la duración total coincida con la grabada
+fn quantize_delays(delays: &[f32], ticks_per_second: u32) -> Vec<u16> {
+    let mut elapsed = 0.0f64;
+    let mut written = 0u64;
+    delays
+        .iter()
+        .map(|&dt| {
+            elapsed += dt as f64;
+            let target = (elapsed * ticks_per_second as f64).round() as u64;
+            let delay = target.saturating_sub(written).clamp(1, u16::MAX as u64);
+            written += delay;
+            delay as u16
+        })
+        .collect()
+}
+
+// ============================================================================
+// CUANTIZACIÓN DE PALETA (median cut sobre histograma RGB 5:5:5)
+// ============================================================================
+pub struct Palette {
+    pub colors: Vec<u32>,
+    // Índice de paleta más cercano para cada color 5:5:5
+    lookup: Vec<u8>,
+}
+
+fn to_rgb555(c: u32) -> usize {
+    let r = (c >> 19) & 0x1F;
+    let g = (c >> 11) & 0x1F;
+    let b = (c >> 3) & 0x1F;
+    ((r << 10) | (g << 5) | b) as usize
+}
+
+impl Palette {
+    pub fn median_cut(pixels: &[u32], max_colors: usize) -> Self {
+        let mut histogram: Vec<Bin> = (0..1u32 << 15)
+            .map(|i| Bin { key: i as usize, rgb5: [(i >> 10) & 0x1F, (i >> 5) & 0x1F, i & 0x1F], count: 0, sum: [0; 3] })
+            .collect();
+        for &p in pixels {
+            let bin = &mut histogram[to_rgb555(p)];
+            bin.count += 1;
+            bin.sum[0] += ((p >> 16) & 0xFF) as u64;
+            bin.sum[1] += ((p >> 8) & 0xFF) as u64;
+            bin.sum[2] += (p & 0xFF) as u64;
+        }
+        histogram.retain(|b| b.count > 0);
+
+        let mut boxes: Vec<Vec<Bin>> = vec![histogram];
+        while boxes.len() < max_colors {
+            // Partir la caja con mayor rango en algún canal (ponderado por píxeles)
+            let candidate = boxes
+                .iter()
+                .enumerate()
+                .filter(|(_, b)| b.len() > 1)
+                .map(|(i, b)| {
+                    let (channel, range) = widest_channel(b);
+                    let weight: u64 = b.iter().map(|e| e.count as u64).sum();
+                    (i, channel, range as u64 * weight.max(1))
+                })
+                .max_by_key(|&(_, _, score)| score);
+
+            let Some((index, channel, _)) = candidate else { break };
+            let mut b = boxes.swap_remove(index);
+            b.sort_by_key(|e| e.rgb5[channel]);
+
+            // Cortar en la mediana por número de píxeles
+            let total: u64 = b.iter().map(|e| e.count as u64).sum();
+            let mut acc = 0;
+            let mut cut = 1;
+            for (i, e) in b.iter().enumerate() {
+                acc += e.count as u64;
+                if acc * 2 >= total {
+                    cut = (i + 1).clamp(1, b.len() - 1);
+                    break;
+                }
+            }
+            let upper = b.split_off(cut);
+            boxes.push(b);
+            boxes.push(upper);
+        }
+
+        // Color de cada caja: promedio real de los píxeles que contiene
+        let colors: Vec<u32> = boxes
+            .iter()
+            .map(|b| {
+                let total: u64 = b.iter().map(|e| e.count as u64).sum::<u64>().max(1);
+                let avg = |ch: usize| {
+                    let sum: u64 = b.iter().map(|e| e.sum[ch]).sum();
+                    ((sum / total) as u32).min(255)
+                };
+                (avg(0) << 16) | (avg(1) << 8) | avg(2)
+            })
+            .collect();
+
+        // Tabla de búsqueda: los colores vistos van a su caja; el resto
+        // (colores que aparecen en frames posteriores) al más cercano
+        let mut lookup: Vec<Option<u8>> = vec![None; 1 << 15];
+        for (index, b) in boxes.iter().enumerate() {
+            for e in b {
+                lookup[e.key] = Some(index as u8);
+            }
+        }
+        let lookup = lookup
+            .iter()
+            .enumerate()
+            .map(|(i, known)| {
+                known.unwrap_or_else(|| {
+                    let (r, g, b) = (((i >> 10) & 0x1F) * 8 + 4, ((i >> 5) & 0x1F) * 8 + 4, (i & 0x1F) * 8 + 4);
+                    colors
+                        .iter()
+                        .enumerate()
+                        .min_by_key(|(_, &c)| {
+                            let dr = r as i32 - ((c >> 16) & 0xFF) as i32;
+                            let dg = g as i32 - ((c >> 8) & 0xFF) as i32;
+                            let db = b as i32 - (c & 0xFF) as i32;
+                            dr * dr + dg * dg + db * db
+                        })
+                        .map(|(idx, _)| idx as u8)
+                        .unwrap_or(0)
+                })
+            })
+            .collect();
+
+        Palette { colors, lookup }
+    }
+
+    pub fn index_of(&self, color: u32) -> u8 {
+        self.lookup[to_rgb555(color)]
+    }
+}
+
+// Entrada del histograma: color 5:5:5 y suma real de los píxeles que caen en él
+#[derive(Clone)]
+struct Bin {
+    key: usize,
+    rgb5: [u32; 3],
+    count: u32,
+    sum: [u64; 3],
+}
+
+fn widest_channel(entries: &[Bin]) -> (usize, u32) {
+    (0..3)
+        .map(|ch| {
+            let min = entries.iter().map(|e| e.rgb5[ch]).min().unwrap_or(0);
+            let max = entries.iter().map(|e| e.rgb5[ch]).max().unwrap_or(0);
+            (ch, max - min)
+        })
+        .max_by_key(|&(_, range)| range)
+        .unwrap_or((0, 0))
+}
+
+// ============================================================================
+// LZW de GIF (códigos de ancho variable hasta 12 bits)
+// ============================================================================
+fn lzw_encode(indices: &[u8], min_code_size: u32) -> Vec<u8> {
+    let clear_code = 1u32 << min_code_size;
+    let end_code = clear_code + 1;
+    let mut code_size = min_code_size + 1;
+    let mut next_code = end_code + 1;
+    let mut dictionary: HashMap<(u32, u8), u32> = HashMap::new();
+    let mut w = BitWriter::new();
+
+    // El decodificador va una entrada por detrás: se agranda el código cuando
+    // la siguiente entrada ya no cabe en el ancho actual
+    let emit = |w: &mut BitWriter, code: u32, code_size: &mut u32, next_code: u32| {
+        if next_code > (1 << *code_size) && *code_size < 12 {
+            *code_size += 1;
+        }
+        w.write_bits(code, *code_size);
+    };
+
+    emit(&mut w, clear_code, &mut code_size, next_code);
+    let Some((&first, rest)) = indices.split_first() else {
+        emit(&mut w, end_code, &mut code_size, next_code);
+        return w.finish();
+    };
+
+    let mut prefix = first as u32;
+    for &k in rest {
+        if let Some(&code) = dictionary.get(&(prefix, k)) {
+            prefix = code;
+            continue;
+        }
+
+        emit(&mut w, prefix, &mut code_size, next_code);
+        if next_code < 4096 {
+            dictionary.insert((prefix, k), next_code);
+            next_code += 1;
+        } else {
+            // Tabla llena: reiniciar el diccionario
+            emit(&mut w, clear_code, &mut code_size, next_code);
+            dictionary.clear();
+            code_size = min_code_size + 1;
+            next_code = end_code + 1;
+        }
+        prefix = k as u32;
+    }
+
+    emit(&mut w, prefix, &mut code_size, next_code);
+    // El decodificador agrega una entrada al leer el último prefijo
+    emit(&mut w, end_code, &mut code_size, next_code + 1);
+    w.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Decodificador LZW de GIF de referencia: lectura LSB primero, el ancho
+    // crece cuando la tabla llena el ancho actual y el código clear la reinicia
+    fn lzw_decode(data: &[u8], min_code_size: u32) -> Vec<u8> {
+        let clear_code = 1usize << min_code_size;
+        let end_code = clear_code + 1;
+        let mut table: Vec<Vec<u8>> = (0..clear_code).map(|i| vec![i as u8]).collect();
+        table.push(Vec::new());
+        table.push(Vec::new());
+
+        let (mut bit, mut code_size) = (0usize, min_code_size + 1);
+        let mut read = |size: u32| {
+            let mut code = 0usize;
+            for i in 0..size as usize {
+                code |= ((data[bit / 8] >> (bit % 8)) as usize & 1) << i;
+                bit += 1;
+            }
+            code
+        };
+
+        let mut out = Vec::new();
+        let mut prev: Option<Vec<u8>> = None;
+        loop {
+            let code = read(code_size);
+            if code == clear_code {
+                table.truncate(end_code + 1);
+                code_size = min_code_size + 1;
+                prev = None;
+                continue;
+            }
+            if code == end_code {
+                return out;
+            }
+            let entry = match (&prev, table.get(code)) {
+                (_, Some(entry)) => entry.clone(),
+                (Some(p), None) if code == table.len() => [p.as_slice(), &p[..1]].concat(),
+                _ => panic!("código LZW {} fuera de la tabla ({})", code, table.len()),
+            };
+            out.extend_from_slice(&entry);
+            if let Some(p) = prev {
+                if table.len() < 4096 {
+                    table.push([p.as_slice(), &entry[..1]].concat());
+                }
+            }
+            if table.len() == 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+            prev = Some(entry);
+        }
+    }
+
+    fn noise(len: usize, mut state: u32) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lzw_roundtrip_small_inputs() {
+        for data in [vec![], vec![7], vec![1, 1], vec![3; 1000], (0..=255).collect::<Vec<u8>>()] {
+            assert_eq!(lzw_decode(&lzw_encode(&data, 8), 8), data);
+        }
+        // Paleta chica: el código arranca en 3 bits
+        let data: Vec<u8> = noise(5000, 7).iter().map(|b| b & 3).collect();
+        assert_eq!(lzw_decode(&lzw_encode(&data, 2), 2), data);
+    }
+
+    #[test]
+    fn lzw_roundtrip_past_full_table() {
+        // Datos aleatorios: casi cada código agrega una entrada, así que la
+        // tabla llega a 4096 y se reinicia varias veces; los códigos pasan
+        // por todos los anchos de 9 a 12 bits
+        let data = noise(60_000, 0xDEAD_BEEF);
+        assert_eq!(lzw_decode(&lzw_encode(&data, 8), 8), data);
+
+        // Mezcla de tramos repetidos y ruido, con reinicios a mitad de tramo
+        let mut data = Vec::new();
+        for i in 0..40 {
+            data.extend_from_slice(&noise(900, i));
+            data.extend(std::iter::repeat_n(i as u8, 300 + i as usize * 37));
+        }
+        assert_eq!(lzw_decode(&lzw_encode(&data, 8), 8), data);
+    }
+
+    #[test]
+    fn median_cut_keeps_few_colors_exact() {
+        let colors = [0x000000, 0xFF0000, 0x00FF00, 0x0000FF, 0xFFFFFF, 0x808080];
+        let pixels: Vec<u32> = (0..600).map(|i| colors[i % colors.len()]).collect();
+        let palette = Palette::median_cut(&pixels, 256);
+        assert_eq!(palette.colors.len(), colors.len());
+        for &c in &colors {
+            assert_eq!(palette.colors[palette.index_of(c) as usize], c);
+        }
+    }
+
+    #[test]
+    fn median_cut_limits_and_approximates() {
+        // Degradé con más colores que la paleta
+        let pixels: Vec<u32> = (0..256 * 64).map(|i| ((i % 256) << 16 | (i / 256 * 4) << 8 | 0x40) as u32).collect();
+        let palette = Palette::median_cut(&pixels, 16);
+        assert_eq!(palette.colors.len(), 16);
+
+        let channel = |c: u32, shift: u32| ((c >> shift) & 0xFF) as i32;
+        let max_error = pixels
+            .iter()
+            .map(|&p| {
+                let q = palette.colors[palette.index_of(p) as usize];
+                [16, 8, 0].iter().map(|&s| (channel(p, s) - channel(q, s)).abs()).max().unwrap()
+            })
+            .max()
+            .unwrap();
+        assert!(max_error <= 40, "error máximo {}", max_error);
+
+        // Un color que no estaba en el frame va al más cercano de la paleta
+        let palette = Palette::median_cut(&[0x000000, 0xFFFFFF], 4);
+        assert_eq!(palette.colors.len(), 2);
+        assert_eq!(palette.colors[palette.index_of(0xF0F0E0) as usize], 0xFFFFFF);
+        assert_eq!(palette.colors[palette.index_of(0x102000) as usize], 0x000000);
+    }
+
+    #[test]
+    fn quantized_delays_do_not_drift() {
+        // 1/30 s son 33.3 ms: redondear cada frame perdería 10 ms por segundo
+        let delays = quantize_delays(&[1.0 / 30.0; 300], 1000);
+        assert_eq!(delays.iter().map(|&d| d as u32).sum::<u32>(), 10_000);
+        assert!(delays.iter().all(|&d| d == 33 || d == 34));
+
+        let delays = quantize_delays(&[1.0 / 60.0; 120], 100);
+        assert_eq!(delays.iter().map(|&d| d as u32).sum::<u32>(), 200);
+        // Nunca 0: algunos visores lo tratan como "lo más rápido posible"
+        assert!(quantize_delays(&[0.001; 5], 100).iter().all(|&d| d >= 1));
+    }
+
+    #[test]
+    fn gif_dimensions_fit_in_16_bits() {
+        assert!(Recorder::new(Path::new("a.gif"), 65_535, 10).is_ok());
+        assert!(Recorder::new(Path::new("a.gif"), 65_536, 10).is_err());
+        assert!(Recorder::new(Path::new("a.gif"), 10, 70_000).is_err());
+        // APNG usa 32 bits
+        assert!(Recorder::new(Path::new("a.png"), 70_000, 10).is_ok());
+    }
+}
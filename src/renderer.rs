@@ -1,7 +1,11 @@
-use crate::rasterizer::Framebuffer;
+use crate::clipping::{clip_triangle, ClipVertex};
+use crate::sphere::compute_vertex_normals;
+use crate::rasterizer::{Framebuffer, RasterTarget, Tile, TILE_SIZE};
 use crate::vector::Vector3;
 use crate::shaders::{PlanetShader, ShaderUniforms};
 use nalgebra::{Matrix4, Vector4};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
 
 pub const WIDTH: usize = 1024;
 pub const HEIGHT: usize = 768;
@@ -14,12 +18,12 @@ pub struct Star {
 }
 
 // Generar campo de estrellas aleatorias
-pub fn generate_stars(count: usize) -> Vec<Star> {
+pub fn generate_stars(count: usize, width: usize, height: usize) -> Vec<Star> {
     let mut stars = Vec::new();
     for i in 0..count {
         let seed = i as f32 * 12.9898;
-        let x = ((seed.sin() * 43758.5453).fract() * WIDTH as f32) as f32;
-        let y = (((seed + 1.0).sin() * 43758.5453).fract() * HEIGHT as f32) as f32;
+        let x = (seed.sin() * 43_758.545).fract() * width as f32;
+        let y = ((seed + 1.0).sin() * 43_758.545).fract() * height as f32;
         let brightness = ((seed * 2.0).sin() * 0.5 + 0.5) * 0.8 + 0.2;
         stars.push(Star { x, y, brightness });
     }
@@ -32,13 +36,44 @@ pub fn render_stars(fb: &mut Framebuffer, stars: &[Star]) {
         let x = star.x as usize;
         let y = star.y as usize;
         if x < fb.width && y < fb.height {
-            let intensity = (star.brightness * 255.0) as u32;
-            let color = (intensity << 16) | (intensity << 8) | intensity;
-            fb.color_buffer[y * fb.width + x] = color;
+            let intensity = star.brightness;
+            fb.hdr_buffer[y * fb.width + x] = [intensity, intensity, intensity, 1.0];
         }
     }
 }
 
+// Modo de interpolación de atributos (position/normal/uv) en el rasterizador
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    // Interpola atributos/w y 1/w en pantalla (correcto en perspectiva)
+    PerspectiveCorrect,
+    // Baricéntricas de pantalla directas (modo antiguo, solo para comparar)
+    Affine,
+}
+
+// Opciones del pipeline de rasterización
+#[derive(Debug, Clone, Copy)]
+pub struct RenderOptions {
+    pub interpolation: Interpolation,
+    // Hilos para rasterizar por tiles (1 = rasterizador secuencial)
+    pub threads: usize,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            interpolation: Interpolation::PerspectiveCorrect,
+            threads: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        }
+    }
+}
+
+// Triángulo ya recortado y proyectado, listo para rasterizar
+struct ScreenTriangle {
+    screen: [(f32, f32, f32); 3],
+    verts: [ClipVertex; 3],
+}
+
 // Renderizar un planeta completo
 pub fn render_planet(
     fb: &mut Framebuffer,
@@ -46,161 +81,255 @@ pub fn render_planet(
     mvp: &Matrix4<f32>,
     shader: &dyn PlanetShader,
     uniforms: &ShaderUniforms,
+    options: &RenderOptions,
 ) {
-    for tri_idx in (0..mesh.indices.len()).step_by(3) {
-        let i0 = mesh.indices[tri_idx] as usize;
-        let i1 = mesh.indices[tri_idx + 1] as usize;
-        let i2 = mesh.indices[tri_idx + 2] as usize;
-
-        // Extraer posiciones originales
-        let p0_local = Vector3::new(
-            mesh.positions[i0 * 3],
-            mesh.positions[i0 * 3 + 1],
-            mesh.positions[i0 * 3 + 2],
-        );
-        let p1_local = Vector3::new(
-            mesh.positions[i1 * 3],
-            mesh.positions[i1 * 3 + 1],
-            mesh.positions[i1 * 3 + 2],
-        );
-        let p2_local = Vector3::new(
-            mesh.positions[i2 * 3],
-            mesh.positions[i2 * 3 + 1],
-            mesh.positions[i2 * 3 + 2],
-        );
+    let triangles = process_geometry(mesh, mvp, shader, uniforms, fb.width, fb.height);
 
-        // Calcular normal del triángulo
-        let edge1 = p1_local - p0_local;
-        let edge2 = p2_local - p0_local;
-        let tri_normal = edge1.cross(&edge2).normalize();
+    if options.threads <= 1 {
+        for tri in &triangles {
+            draw_triangle_with_shader(fb, tri, shader, uniforms, options);
+        }
+    } else {
+        rasterize_tiled(fb, &triangles, shader, uniforms, options);
+    }
+}
 
-        // UVs esféricos
-        let uv0 = calculate_spherical_uv(&p0_local);
-        let uv1 = calculate_spherical_uv(&p1_local);
-        let uv2 = calculate_spherical_uv(&p2_local);
+// Dibujo translúcido pendiente, con la distancia a la cámara con la que se ordena
+struct TransparentDraw<'a> {
+    distance: f32,
+    mesh: &'a tobj::Mesh,
+    mvp: Matrix4<f32>,
+    shader: Box<dyn PlanetShader + 'a>,
+    uniforms: ShaderUniforms,
+}
 
-        // Aplicar vertex shader
-        let (v0_deformed, v0_norm) = shader.vertex_shader(p0_local, tri_normal, uv0, uniforms);
-        let (v1_deformed, v1_norm) = shader.vertex_shader(p1_local, tri_normal, uv1, uniforms);
-        let (v2_deformed, v2_norm) = shader.vertex_shader(p2_local, tri_normal, uv2, uniforms);
+// Dibujos translúcidos de un frame: se acumulan mientras se dibuja lo opaco y
+// se rasterizan después, del más lejano al más cercano a la cámara (la mezcla
+// no es conmutativa). Se ordena por el centro de cada objeto, no por triángulo
+#[derive(Default)]
+pub struct TransparentQueue<'a> {
+    draws: Vec<TransparentDraw<'a>>,
+}
 
-        // Transformar a clip space
-        let clip0 = transform_vertex(&v0_deformed, mvp);
-        let clip1 = transform_vertex(&v1_deformed, mvp);
-        let clip2 = transform_vertex(&v2_deformed, mvp);
+impl<'a> TransparentQueue<'a> {
+    // El centro del objeto es el origen de su matriz de modelo (uniforms.model)
+    pub fn push(&mut self, mesh: &'a tobj::Mesh, mvp: Matrix4<f32>, shader: Box<dyn PlanetShader + 'a>, uniforms: ShaderUniforms) {
+        let distance = (uniforms.world_position(Vector3::zero()) - uniforms.camera_position).length();
+        self.draws.push(TransparentDraw { distance, mesh, mvp, shader, uniforms });
+    }
 
-        // Frustum culling
-        if clip0.3 <= 0.0 || clip1.3 <= 0.0 || clip2.3 <= 0.0 {
-            continue;
+    // Dibuja (y quita de la cola) los que están a `distance` o más de la cámara,
+    // para intercalar efectos que no son meshes (la corona) en su lugar del orden
+    pub fn flush_beyond(&mut self, fb: &mut Framebuffer, options: &RenderOptions, distance: f32) {
+        // Orden estable: a igual distancia se respeta el orden de push
+        self.draws.sort_by(|a, b| b.distance.total_cmp(&a.distance));
+        let split = self.draws.partition_point(|draw| draw.distance >= distance);
+        for draw in self.draws.drain(..split) {
+            render_planet(fb, draw.mesh, &draw.mvp, draw.shader.as_ref(), &draw.uniforms, options);
         }
+    }
+
+    pub fn flush(&mut self, fb: &mut Framebuffer, options: &RenderOptions) {
+        self.flush_beyond(fb, options, f32::NEG_INFINITY);
+    }
+}
 
-        // Backface culling
-        let screen0 = to_screen_coords(&clip0, WIDTH as f32, HEIGHT as f32);
-        let screen1 = to_screen_coords(&clip1, WIDTH as f32, HEIGHT as f32);
-        let screen2 = to_screen_coords(&clip2, WIDTH as f32, HEIGHT as f32);
-        
-        let edge_a = (screen1.0 - screen0.0, screen1.1 - screen0.1);
-        let edge_b = (screen2.0 - screen0.0, screen2.1 - screen0.1);
-        let cross = edge_a.0 * edge_b.1 - edge_a.1 * edge_b.0;
-        
-        if cross <= 0.0 {
+// Solo profundidad (pase de sombras): mismo vertex shader y recorte que
+// render_planet, sin fragment shader. `fb` puede ser de solo profundidad
+pub fn render_depth(
+    fb: &mut Framebuffer,
+    mesh: &tobj::Mesh,
+    mvp: &Matrix4<f32>,
+    shader: &dyn PlanetShader,
+    uniforms: &ShaderUniforms,
+) {
+    let triangles = process_geometry(mesh, mvp, shader, uniforms, fb.width, fb.height);
+    for tri in &triangles {
+        draw_triangle_depth(fb, tri);
+    }
+}
+
+// Vertex shader, recorte, proyección y backface culling de todo el mesh
+fn process_geometry(
+    mesh: &tobj::Mesh,
+    mvp: &Matrix4<f32>,
+    shader: &dyn PlanetShader,
+    uniforms: &ShaderUniforms,
+    width: usize,
+    height: usize,
+) -> Vec<ScreenTriangle> {
+    // Vertex shader: una vez por vértice del mesh
+    let vertex_count = mesh.positions.len() / 3;
+    let texcoords = shader.uses_texcoords() && mesh.texcoords.len() >= vertex_count * 2;
+    let mut shaded: Vec<ClipVertex> = (0..vertex_count)
+        .map(|i| {
+            let local = Vector3::new(
+                mesh.positions[i * 3],
+                mesh.positions[i * 3 + 1],
+                mesh.positions[i * 3 + 2],
+            );
+            // Normal por vértice (del OBJ o calculada al cargar el mesh)
+            let normal = vertex_normal(mesh, i);
+            // UVs del mesh si el shader los pide y los trae; si no, esféricos
+            let uv = if texcoords {
+                (mesh.texcoords[i * 2], mesh.texcoords[i * 2 + 1])
+            } else {
+                calculate_spherical_uv(&local)
+            };
+
+            let (deformed, deformed_normal) = shader.vertex_shader(local, normal, uv, uniforms);
+            ClipVertex {
+                clip: transform_vertex_h(&deformed, mvp),
+                position: deformed,
+                normal: deformed_normal,
+                uv,
+            }
+        })
+        .collect();
+
+    // Si el shader desplaza vértices, reconstruir las normales sobre la
+    // geometría deformada para que la iluminación siga cráteres y flares
+    if shader.displaces_geometry() {
+        let displaced: Vec<f32> = shaded
+            .iter()
+            .flat_map(|v| [v.position.x, v.position.y, v.position.z])
+            .collect();
+        let normals = compute_vertex_normals(&displaced, &mesh.indices);
+        for (i, v) in shaded.iter_mut().enumerate() {
+            v.normal = Vector3::new(normals[i * 3], normals[i * 3 + 1], normals[i * 3 + 2]);
+        }
+    }
+
+    let cull_backfaces = !shader.double_sided();
+    let mut triangles = Vec::with_capacity(mesh.indices.len() / 3);
+    let mut polygon: Vec<ClipVertex> = Vec::with_capacity(9);
+
+    for tri in mesh.indices.chunks_exact(3) {
+        let triangle = [
+            shaded[tri[0] as usize],
+            shaded[tri[1] as usize],
+            shaded[tri[2] as usize],
+        ];
+
+        // Recorte contra near/far y guard-band (puede partir el triángulo en un polígono)
+        clip_triangle(triangle, &mut polygon);
+        if polygon.is_empty() {
             continue;
         }
 
-        // Rasterizar
-        draw_triangle_with_shader(
-            fb,
-            &screen0,
-            &screen1,
-            &screen2,
-            &v0_deformed,
-            &v1_deformed,
-            &v2_deformed,
-            &v0_norm,
-            &v1_norm,
-            &v2_norm,
-            &uv0,
-            &uv1,
-            &uv2,
-            shader,
-            uniforms,
-        );
+        let screen: Vec<(f32, f32, f32)> = polygon
+            .iter()
+            .map(|v| to_screen_coords(&(v.clip.x, v.clip.y, v.clip.z, v.clip.w), width as f32, height as f32))
+            .collect();
+
+        // Re-triangular el polígono como abanico
+        for k in 1..polygon.len() - 1 {
+            let (s0, s1, s2) = (screen[0], screen[k], screen[k + 1]);
+
+            // Backface culling: caras frontales antihorarias (convención OBJ);
+            // con la y de pantalla hacia abajo quedan con cross negativo
+            let edge_a = (s1.0 - s0.0, s1.1 - s0.1);
+            let edge_b = (s2.0 - s0.0, s2.1 - s0.1);
+            let cross = edge_a.0 * edge_b.1 - edge_a.1 * edge_b.0;
+
+            if cross >= 0.0 && (cull_backfaces || cross == 0.0) {
+                continue;
+            }
+
+            triangles.push(ScreenTriangle {
+                screen: [s0, s1, s2],
+                verts: [polygon[0], polygon[k], polygon[k + 1]],
+            });
+        }
     }
+
+    triangles
 }
 
-// Renderizar anillos (simplificado como disco plano) - Legacy, no usado
-#[allow(dead_code)]
-pub fn render_rings(
+// Bounding box de un triángulo en píxeles, recortado a [0, width) x [0, height)
+fn pixel_bounds(screen: &[(f32, f32, f32); 3], width: usize, height: usize) -> Option<(usize, usize, usize, usize)> {
+    let [(x0, y0, _), (x1, y1, _), (x2, y2, _)] = *screen;
+    let min_x = x0.min(x1).min(x2).floor().max(0.0);
+    let max_x = x0.max(x1).max(x2).ceil().min(width as f32 - 1.0);
+    let min_y = y0.min(y1).min(y2).floor().max(0.0);
+    let max_y = y0.max(y1).max(y2).ceil().min(height as f32 - 1.0);
+
+    if min_x > max_x || min_y > max_y {
+        return None;
+    }
+    Some((min_x as usize, min_y as usize, max_x as usize, max_y as usize))
+}
+
+// Rasterizador por tiles: los triángulos se asignan (binning) a los tiles que
+// tocan y cada hilo procesa tiles completos respetando el orden de envío, así
+// el resultado es idéntico al secuencial.
+fn rasterize_tiled(
     fb: &mut Framebuffer,
-    projection: &Matrix4<f32>,
-    view: &Matrix4<f32>,
-    model: &Matrix4<f32>,
-    _time: f32,
+    triangles: &[ScreenTriangle],
+    shader: &dyn PlanetShader,
+    uniforms: &ShaderUniforms,
+    options: &RenderOptions,
 ) {
-    let mvp = projection * view * model;
-    let ring_color = 0xCCAA88; // Color beige/dorado
-    
-    // Crear 3 anillos concéntricos
-    for ring_radius_step in 14..17 {
-        let ring_radius = ring_radius_step as f32 * 0.1;
-        let segments = 64;
-        
-        for i in 0..segments {
-            let angle1 = (i as f32 / segments as f32) * std::f32::consts::PI * 2.0;
-            let angle2 = ((i + 1) as f32 / segments as f32) * std::f32::consts::PI * 2.0;
-            
-            let x1 = angle1.cos() * ring_radius;
-            let z1 = angle1.sin() * ring_radius;
-            let x2 = angle2.cos() * ring_radius;
-            let z2 = angle2.sin() * ring_radius;
-            
-            let p1 = Vector3::new(x1, 0.0, z1);
-            let p2 = Vector3::new(x2, 0.0, z2);
-            
-            let clip1 = transform_vertex(&p1, &mvp);
-            let clip2 = transform_vertex(&p2, &mvp);
-            
-            if clip1.3 > 0.0 && clip2.3 > 0.0 {
-                let screen1 = to_screen_coords(&clip1, WIDTH as f32, HEIGHT as f32);
-                let screen2 = to_screen_coords(&clip2, WIDTH as f32, HEIGHT as f32);
-                
-                draw_line(fb, screen1, screen2, ring_color);
+    let tiles_x = fb.width.div_ceil(TILE_SIZE);
+    let tiles_y = fb.height.div_ceil(TILE_SIZE);
+
+    let mut bins: Vec<Vec<usize>> = vec![Vec::new(); tiles_x * tiles_y];
+    for (i, tri) in triangles.iter().enumerate() {
+        if let Some((min_x, min_y, max_x, max_y)) = pixel_bounds(&tri.screen, fb.width, fb.height) {
+            for ty in min_y / TILE_SIZE..=max_y / TILE_SIZE {
+                for tx in min_x / TILE_SIZE..=max_x / TILE_SIZE {
+                    bins[ty * tiles_x + tx].push(i);
+                }
             }
         }
     }
+
+    let work: Vec<usize> = (0..bins.len()).filter(|&t| !bins[t].is_empty()).collect();
+    let next = AtomicUsize::new(0);
+    let fb_ref: &Framebuffer = fb;
+
+    let finished: Vec<Tile> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..options.threads.min(work.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let w = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(&tile_idx) = work.get(w) else { break };
+
+                        let x0 = (tile_idx % tiles_x) * TILE_SIZE;
+                        let y0 = (tile_idx / tiles_x) * TILE_SIZE;
+                        let width = TILE_SIZE.min(fb_ref.width - x0);
+                        let height = TILE_SIZE.min(fb_ref.height - y0);
+
+                        let mut tile = Tile::from_framebuffer(fb_ref, x0, y0, width, height);
+                        for &i in &bins[tile_idx] {
+                            draw_triangle_with_shader(&mut tile, &triangles[i], shader, uniforms, options);
+                        }
+                        done.push(tile);
+                    }
+                    done
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|w| w.join().expect("Hilo de rasterizado falló"))
+            .collect()
+    });
+
+    for tile in &finished {
+        tile.write_back(fb);
+    }
 }
 
-// Dibujar línea simple (Bresenham) - Legacy, no usado
-#[allow(dead_code)]
-fn draw_line(fb: &mut Framebuffer, p1: (f32, f32, f32), p2: (f32, f32, f32), color: u32) {
-    let (mut x0, mut y0, z0) = (p1.0 as i32, p1.1 as i32, p1.2);
-    let (x1, y1, z1) = (p2.0 as i32, p2.1 as i32, p2.2);
-    
-    let dx = (x1 - x0).abs();
-    let dy = -(y1 - y0).abs();
-    let sx = if x0 < x1 { 1 } else { -1 };
-    let sy = if y0 < y1 { 1 } else { -1 };
-    let mut err = dx + dy;
-    
-    loop {
-        if x0 >= 0 && x0 < fb.width as i32 && y0 >= 0 && y0 < fb.height as i32 {
-            let depth = (z0 + z1) * 0.5;
-            fb.set_pixel(x0 as usize, y0 as usize, depth, color);
-        }
-        
-        if x0 == x1 && y0 == y1 {
-            break;
-        }
-        
-        let e2 = 2 * err;
-        if e2 >= dy {
-            err += dy;
-            x0 += sx;
-        }
-        if e2 <= dx {
-            err += dx;
-            y0 += sy;
-        }
+// Normal del vértice i; si el mesh no trae normales se usa la dirección radial
+fn vertex_normal(mesh: &tobj::Mesh, i: usize) -> Vector3 {
+    if mesh.normals.len() >= (i + 1) * 3 {
+        Vector3::new(mesh.normals[i * 3], mesh.normals[i * 3 + 1], mesh.normals[i * 3 + 2])
+    } else {
+        Vector3::new(mesh.positions[i * 3], mesh.positions[i * 3 + 1], mesh.positions[i * 3 + 2]).normalize()
     }
 }
 
@@ -219,6 +348,11 @@ pub fn transform_vertex(pos: &Vector3, mvp: &Matrix4<f32>) -> (f32, f32, f32, f3
     (transformed.x, transformed.y, transformed.z, transformed.w)
 }
 
+// Igual que transform_vertex pero devolviendo el vector homogéneo (para recorte)
+pub fn transform_vertex_h(pos: &Vector3, mvp: &Matrix4<f32>) -> Vector4<f32> {
+    mvp * Vector4::new(pos.x, pos.y, pos.z, 1.0)
+}
+
 // Convertir de clip space a screen space
 pub fn to_screen_coords(clip: &(f32, f32, f32, f32), width: f32, height: f32) -> (f32, f32, f32) {
     let ndc_x = clip.0 / clip.3;
@@ -231,33 +365,51 @@ pub fn to_screen_coords(clip: &(f32, f32, f32, f32), width: f32, height: f32) ->
     (screen_x, screen_y, ndc_z)
 }
 
+// Rasterizar triángulo escribiendo solo el depth buffer
+fn draw_triangle_depth(fb: &mut Framebuffer, tri: &ScreenTriangle) {
+    let [(x0, y0, z0), (x1, y1, z1), (x2, y2, z2)] = tri.screen;
+    let Some((min_x, min_y, max_x, max_y)) = pixel_bounds(&tri.screen, fb.width, fb.height) else {
+        return;
+    };
+
+    for py in min_y..=max_y {
+        for px in min_x..=max_x {
+            let (w0, w1, w2) = barycentric((x0, y0), (x1, y1), (x2, y2), (px as f32 + 0.5, py as f32 + 0.5));
+            if w0 >= -0.001 && w1 >= -0.001 && w2 >= -0.001 {
+                let depth = w0 * z0 + w1 * z1 + w2 * z2;
+                let index = py * fb.width + px;
+                if (-1.0..=1.0).contains(&depth) && depth < fb.depth_buffer[index] {
+                    fb.depth_buffer[index] = depth;
+                }
+            }
+        }
+    }
+}
+
 // Rasterizar triángulo
-fn draw_triangle_with_shader(
-    fb: &mut Framebuffer,
-    screen0: &(f32, f32, f32),
-    screen1: &(f32, f32, f32),
-    screen2: &(f32, f32, f32),
-    pos0: &Vector3,
-    pos1: &Vector3,
-    pos2: &Vector3,
-    norm0: &Vector3,
-    norm1: &Vector3,
-    norm2: &Vector3,
-    uv0: &(f32, f32),
-    uv1: &(f32, f32),
-    uv2: &(f32, f32),
+fn draw_triangle_with_shader<T: RasterTarget>(
+    target: &mut T,
+    tri: &ScreenTriangle,
     shader: &dyn PlanetShader,
     uniforms: &ShaderUniforms,
+    options: &RenderOptions,
 ) {
-    let (x0, y0, z0) = *screen0;
-    let (x1, y1, z1) = *screen1;
-    let (x2, y2, z2) = *screen2;
-
-    // Bounding box
-    let min_x = x0.min(x1).min(x2).floor().max(0.0) as usize;
-    let max_x = x0.max(x1).max(x2).ceil().min(fb.width as f32 - 1.0) as usize;
-    let min_y = y0.min(y1).min(y2).floor().max(0.0) as usize;
-    let max_y = y0.max(y1).max(y2).ceil().min(fb.height as f32 - 1.0) as usize;
+    let [(x0, y0, z0), (x1, y1, z1), (x2, y2, z2)] = tri.screen;
+    let [v0, v1, v2] = &tri.verts;
+
+    // 1/w de cada vértice (w > 0 garantizado por el recorte contra near)
+    let inv_w0 = 1.0 / v0.clip.w;
+    let inv_w1 = 1.0 / v1.clip.w;
+    let inv_w2 = 1.0 / v2.clip.w;
+
+    // Bounding box, limitado a la región del destino
+    let (bx0, by0, bx1, by1) = target.bounds();
+    let Some((min_x, min_y, max_x, max_y)) = pixel_bounds(&tri.screen, bx1, by1) else {
+        return;
+    };
+    let (min_x, min_y) = (min_x.max(bx0), min_y.max(by0));
+    let (blend_mode, depth_write) = (shader.blend_mode(), shader.depth_write());
+    let alpha_cutoff = shader.alpha_cutoff();
 
     for py in min_y..=max_y {
         for px in min_x..=max_x {
@@ -265,43 +417,45 @@ fn draw_triangle_with_shader(
             let p_y = py as f32 + 0.5;
 
             // Coordenadas baricéntricas
-            let (w0, w1, w2) = barycentric(x0, y0, x1, y1, x2, y2, p_x, p_y);
+            let (w0, w1, w2) = barycentric((x0, y0), (x1, y1), (x2, y2), (p_x, p_y));
 
             // Usar un pequeño bias negativo para cubrir gaps entre triángulos
             let edge_bias = -0.001;
             if w0 >= edge_bias && w1 >= edge_bias && w2 >= edge_bias {
-                // Interpolar depth
+                // Interpolar depth (z/w es lineal en pantalla)
                 let depth = w0 * z0 + w1 * z1 + w2 * z2;
 
-                if depth >= -1.0 && depth <= 1.0 && depth < fb.depth_buffer[py * fb.width + px] {
-                    // Interpolar atributos
-                    let interp_pos = Vector3::new(
-                        w0 * pos0.x + w1 * pos1.x + w2 * pos2.x,
-                        w0 * pos0.y + w1 * pos1.y + w2 * pos2.y,
-                        w0 * pos0.z + w1 * pos1.z + w2 * pos2.z,
-                    );
-
-                    let interp_norm = Vector3::new(
-                        w0 * norm0.x + w1 * norm1.x + w2 * norm2.x,
-                        w0 * norm0.y + w1 * norm1.y + w2 * norm2.y,
-                        w0 * norm0.z + w1 * norm1.z + w2 * norm2.z,
-                    ).normalize();
+                if (-1.0..=1.0).contains(&depth) && depth < target.depth_at(px, py) {
+                    // Pesos para los atributos: corregidos por 1/w salvo en modo afín
+                    let (w0, w1, w2) = match options.interpolation {
+                        Interpolation::PerspectiveCorrect => {
+                            let a0 = w0 * inv_w0;
+                            let a1 = w1 * inv_w1;
+                            let a2 = w2 * inv_w2;
+                            let sum = a0 + a1 + a2;
+                            (a0 / sum, a1 / sum, a2 / sum)
+                        }
+                        Interpolation::Affine => (w0, w1, w2),
+                    };
 
+                    // Interpolar atributos
+                    let interp_pos = v0.position * w0 + v1.position * w1 + v2.position * w2;
+                    let interp_norm = (v0.normal * w0 + v1.normal * w1 + v2.normal * w2).normalize();
                     let interp_uv = (
-                        w0 * uv0.0 + w1 * uv1.0 + w2 * uv2.0,
-                        w0 * uv0.1 + w1 * uv1.1 + w2 * uv2.1,
+                        w0 * v0.uv.0 + w1 * v1.uv.0 + w2 * v2.uv.0,
+                        w0 * v0.uv.1 + w1 * v1.uv.1 + w2 * v2.uv.1,
                     );
 
                     // Fragment shader
                     let color = shader.fragment_shader(interp_pos, interp_norm, interp_uv, uniforms);
+                    if alpha_cutoff.is_some_and(|cutoff| color.a < cutoff) {
+                        continue;
+                    }
 
-                    // Convertir a u32
-                    let r = (color.r.clamp(0.0, 1.0) * 255.0) as u32;
-                    let g = (color.g.clamp(0.0, 1.0) * 255.0) as u32;
-                    let b = (color.b.clamp(0.0, 1.0) * 255.0) as u32;
-                    let pixel_color = (r << 16) | (g << 8) | b;
+                    // Guardar en HDR (sin recortar); el tone mapping cuantiza al mostrar
+                    let pixel_color = [color.r.max(0.0), color.g.max(0.0), color.b.max(0.0), color.a];
 
-                    fb.set_pixel(px, py, depth, pixel_color);
+                    target.blend_pixel(px, py, depth, pixel_color, blend_mode, depth_write);
                 }
             }
         }
@@ -309,10 +463,10 @@ fn draw_triangle_with_shader(
 }
 
 fn barycentric(
-    x0: f32, y0: f32,
-    x1: f32, y1: f32,
-    x2: f32, y2: f32,
-    px: f32, py: f32,
+    (x0, y0): (f32, f32),
+    (x1, y1): (f32, f32),
+    (x2, y2): (f32, f32),
+    (px, py): (f32, f32),
 ) -> (f32, f32, f32) {
     let denom = (y1 - y2) * (x0 - x2) + (x2 - x1) * (y0 - y2);
     if denom.abs() < 1e-10 {
@@ -325,3 +479,89 @@ fn barycentric(
 
     (w0, w1, w2)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lights::Light;
+    use crate::shaders::{ShaderColor, StarShader};
+    use crate::sphere::{generate_sphere, SphereMesh};
+    use nalgebra::{Point3, Vector3 as NVector3};
+
+    // Tamaño que no es múltiplo de TILE_SIZE: quedan tiles parciales en los bordes
+    const W: usize = 203;
+    const H: usize = 141;
+
+    // Esfera que desborda la pantalla y dos triángulos grandes que cruzan
+    // varios tiles, con el shader de la estrella (desplaza vértices)
+    fn render(options: &RenderOptions, tiled: bool) -> Framebuffer {
+        let sphere = generate_sphere(SphereMesh::Uv { segments: 24, rings: 16 }, 1.0);
+        let quad = tobj::Mesh {
+            positions: vec![-3.0, -2.0, -0.5, 3.0, -2.0, -0.5, 3.0, 2.0, -0.5, -3.0, 2.0, -0.5],
+            normals: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            indices: vec![0, 1, 2, 0, 2, 3],
+            ..Default::default()
+        };
+
+        let eye = Point3::new(0.3, 0.2, 2.2);
+        let view = Matrix4::look_at_rh(&eye, &Point3::origin(), &NVector3::y());
+        let projection = Matrix4::new_perspective(W as f32 / H as f32, 1.2, 0.1, 100.0);
+        let mvp = projection * view;
+
+        let shader = StarShader::default();
+        let uniforms = ShaderUniforms::new(
+            1.25,
+            vec![Light::directional(Vector3::new(-1.0, -0.5, -1.0), ShaderColor::new(1.0, 1.0, 1.0, 1.0), 1.0)],
+            Vector3::new(eye.x, eye.y, eye.z),
+            Matrix4::identity(),
+        );
+
+        let mut fb = Framebuffer::new(W, H);
+        fb.clear(0x000000);
+        for mesh in [&quad, &sphere] {
+            if tiled {
+                let triangles = process_geometry(mesh, &mvp, &shader, &uniforms, fb.width, fb.height);
+                rasterize_tiled(&mut fb, &triangles, &shader, &uniforms, options);
+            } else {
+                render_planet(&mut fb, mesh, &mvp, &shader, &uniforms, options);
+            }
+        }
+        fb
+    }
+
+    fn assert_same(a: &Framebuffer, b: &Framebuffer) {
+        let bits = |fb: &Framebuffer| -> (Vec<u32>, Vec<u32>) {
+            (
+                fb.hdr_buffer.iter().flatten().map(|c| c.to_bits()).collect(),
+                fb.depth_buffer.iter().map(|d| d.to_bits()).collect(),
+            )
+        };
+        let (hdr_a, depth_a) = bits(a);
+        let (hdr_b, depth_b) = bits(b);
+        assert!(hdr_a == hdr_b, "hdr_buffer distinto");
+        assert!(depth_a == depth_b, "depth_buffer distinto");
+    }
+
+    #[test]
+    fn tiled_matches_sequential_bit_for_bit() {
+        let options = |threads| RenderOptions { interpolation: Interpolation::PerspectiveCorrect, threads };
+        let sequential = render(&options(1), false);
+
+        // Algo se dibujó y cruza bordes de tiles en ambos ejes
+        let covered: Vec<usize> = (0..W * H).filter(|&i| sequential.depth_buffer[i].is_finite()).collect();
+        assert!(covered.iter().any(|i| i % W < TILE_SIZE) && covered.iter().any(|i| i % W >= 2 * TILE_SIZE));
+        assert!(covered.iter().any(|i| i / W < TILE_SIZE) && covered.iter().any(|i| i / W >= 2 * TILE_SIZE));
+
+        assert_same(&sequential, &render(&options(1), true));
+        for threads in [2, 3, 8] {
+            assert_same(&sequential, &render(&options(threads), false));
+            assert_same(&sequential, &render(&options(threads), true));
+        }
+    }
+
+    #[test]
+    fn tiled_matches_sequential_with_affine_interpolation() {
+        let options = |threads| RenderOptions { interpolation: Interpolation::Affine, threads };
+        assert_same(&render(&options(1), false), &render(&options(4), false));
+    }
+}
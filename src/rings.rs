@@ -0,0 +1,176 @@
+use crate::rasterizer::BlendMode;
+use crate::shaders::{fbm_simplex, PlanetShader, ShaderColor, ShaderUniforms};
+use crate::vector::Vector3;
+
+// Sistema de anillos de un planeta, en el plano ecuatorial del cuerpo (XZ
+// local). Los radios van en radios del planeta
+#[derive(Debug, Clone, Copy)]
+pub struct Rings {
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+    // Opacidad máxima de las bandas más densas
+    pub opacity: f32,
+    // Cantidad aproximada de bandas de inner a outer
+    pub band_frequency: f32,
+    pub color: ShaderColor,
+}
+
+impl Default for Rings {
+    fn default() -> Self {
+        Rings {
+            inner_radius: 1.35,
+            outer_radius: 2.4,
+            opacity: 0.85,
+            band_frequency: 9.0,
+            color: ShaderColor::from_rgb(215, 190, 150),
+        }
+    }
+}
+
+// Divisiones de claro a claro; centro y semiancho como fracción del ancho del anillo
+const GAPS: [(f32, f32); 2] = [(0.62, 0.035), (0.86, 0.012)];
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0).max(1e-6)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+impl Rings {
+    // Opacidad a la distancia `r` del centro (en radios del planeta): bandas
+    // de ruido, divisiones fijas y bordes suavizados. Solo depende del radio,
+    // así la sombra sobre el planeta coincide con lo que se ve
+    pub fn density(&self, r: f32) -> f32 {
+        let v = (r - self.inner_radius) / (self.outer_radius - self.inner_radius);
+        if !(0.0..=1.0).contains(&v) {
+            return 0.0;
+        }
+        let bands = fbm_simplex(v * self.band_frequency, 0.37, 0.71, 4);
+        let fine = fbm_simplex(v * self.band_frequency * 6.0, 1.3, 0.2, 2);
+        let mut density = (0.15 + 0.85 * smoothstep(0.3, 0.7, bands)) * (0.75 + 0.5 * fine);
+        for (center, half_width) in GAPS {
+            density *= smoothstep(half_width * 0.5, half_width, (v - center).abs());
+        }
+        let edges = smoothstep(0.0, 0.04, v) * (1.0 - smoothstep(0.9, 1.0, v));
+        (density * edges * self.opacity).clamp(0.0, 1.0)
+    }
+
+    // Anillo plano (corona circular) con `segments` divisiones angulares y `radial`
+    // concéntricas. Cada cara existe dos veces (normal +Y y -Y, con el orden
+    // invertido) para que el backface culling deje ver el lado que mira a la cámara
+    pub fn mesh(&self, segments: u32, radial: u32) -> tobj::Mesh {
+        let (seg, rad) = (segments.max(3), radial.max(1));
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut indices = Vec::new();
+
+        for side in [1.0f32, -1.0] {
+            let base = (positions.len() / 3) as u32;
+            for i in 0..seg {
+                let angle = i as f32 / seg as f32 * std::f32::consts::TAU;
+                for j in 0..=rad {
+                    let r = self.inner_radius + (self.outer_radius - self.inner_radius) * j as f32 / rad as f32;
+                    positions.extend_from_slice(&[r * angle.cos(), 0.0, r * angle.sin()]);
+                    normals.extend_from_slice(&[0.0, side, 0.0]);
+                }
+            }
+            let index = |i: u32, j: u32| base + (i % seg) * (rad + 1) + j;
+            for i in 0..seg {
+                for j in 0..rad {
+                    let (a, b, c, d) = (index(i, j), index(i + 1, j), index(i, j + 1), index(i + 1, j + 1));
+                    // (a, b, c) es antihorario visto desde +Y
+                    if side > 0.0 {
+                        indices.extend_from_slice(&[a, b, c, b, d, c]);
+                    } else {
+                        indices.extend_from_slice(&[a, c, b, b, c, d]);
+                    }
+                }
+            }
+        }
+
+        tobj::Mesh { positions, normals, indices, ..Default::default() }
+    }
+}
+
+// Anillos de un cuerpo colocados en el mundo, para sombrear al propio planeta
+#[derive(Debug, Clone, Copy)]
+pub struct RingShadow {
+    pub rings: Rings,
+    // Centro y eje (normal del plano de los anillos) del planeta en mundo
+    pub center: Vector3,
+    pub normal: Vector3,
+    // Radio del planeta en mundo (unidad de los radios de `rings`)
+    pub scale: f32,
+}
+
+impl RingShadow {
+    // Fracción de luz que atraviesa los anillos en el camino de `position` hacia la luz
+    pub fn transmittance(&self, position: Vector3, to_light: Vector3) -> f32 {
+        let denom = to_light.dot(&self.normal);
+        if denom.abs() < 1e-5 {
+            return 1.0;
+        }
+        let t = (self.center - position).dot(&self.normal) / denom;
+        if t <= 0.0 {
+            return 1.0;
+        }
+        let hit = position + to_light * t;
+        1.0 - self.rings.density((hit - self.center).length() / self.scale)
+    }
+}
+
+// Anillos de un cuerpo: color y opacidad por radio con algo de variación
+// angular, iluminados desde ambos lados y con la sombra del planeta (shadow map)
+pub struct RingShader {
+    pub rings: Rings,
+}
+
+impl PlanetShader for RingShader {
+    fn vertex_shader(&self, position: Vector3, normal: Vector3, _uv: (f32, f32), _uniforms: &ShaderUniforms) -> (Vector3, Vector3) {
+        (position, normal)
+    }
+
+    fn blend_mode(&self) -> BlendMode {
+        BlendMode::Alpha
+    }
+
+    fn fragment_shader(&self, position: Vector3, normal: Vector3, _uv: (f32, f32), uniforms: &ShaderUniforms) -> ShaderColor {
+        let rings = &self.rings;
+        // Posición local: radio en el plano y ángulo (grumos que giran con el planeta)
+        let r = (position.x * position.x + position.z * position.z).sqrt();
+        let angle = position.z.atan2(position.x);
+        let clumps = fbm_simplex(angle.cos() * 3.0, angle.sin() * 3.0, r * 4.0, 2);
+        let alpha = (rings.density(r) * (0.85 + 0.3 * clumps)).clamp(0.0, 1.0);
+        if alpha <= 0.002 {
+            return ShaderColor::new(0.0, 0.0, 0.0, 0.0);
+        }
+
+        // Bandas más claras y más oscuras según la misma densidad
+        let v = (r - rings.inner_radius) / (rings.outer_radius - rings.inner_radius);
+        let tone = 0.75 + 0.35 * fbm_simplex(v * rings.band_frequency * 2.0, 4.1, 2.3, 3);
+        let base = ShaderColor::new(rings.color.r * tone, rings.color.g * tone, rings.color.b * tone, 1.0);
+
+        // Las partículas dispersan la luz por ambas caras
+        let world = uniforms.world_position(position);
+        let world_normal = uniforms.world_normal(normal);
+        let mut light = [0.0; 3];
+        for (i, source) in uniforms.lights.iter().enumerate() {
+            let Some((to_light, radiance)) = source.illuminate(world) else {
+                continue;
+            };
+            // Normal del lado que mira a la luz (para el offset del shadow map)
+            let facing = if world_normal.dot(&to_light) >= 0.0 { world_normal } else { world_normal * -1.0 };
+            let diffuse = 0.35 + 0.65 * world_normal.dot(&to_light).abs();
+            let visibility = uniforms.visibility(i, world, facing);
+            light[0] += radiance.r * diffuse * visibility;
+            light[1] += radiance.g * diffuse * visibility;
+            light[2] += radiance.b * diffuse * visibility;
+        }
+        let ambient = 0.12;
+        ShaderColor::new(
+            base.r * (ambient + light[0]),
+            base.g * (ambient + light[1]),
+            base.b * (ambient + light[2]),
+            alpha,
+        )
+    }
+}
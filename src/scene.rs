@@ -0,0 +1,520 @@
+use crate::atmosphere::{Atmosphere, AtmosphereShader};
+use crate::corona::Corona;
+use crate::rings::{RingShader, RingShadow, Rings};
+use crate::lights::Light;
+use crate::lod::MeshLod;
+use crate::model::SceneObject;
+use crate::rasterizer::{BlendMode, Framebuffer};
+use crate::shadows::ShadowMap;
+use crate::sphere::SphereMesh;
+use crate::tessellation::AdaptiveSphere;
+use crate::renderer::{generate_stars, render_planet, render_stars, RenderOptions, Star, TransparentQueue};
+use crate::shaders::{
+    BioLuminescentShader, GasGiantShader, IcePlanetShader, PlanetShader, RockyPlanetShader, ShaderColor,
+    ShaderUniforms, StarShader, VolcanicPlanetShader,
+};
+use crate::vector::Vector3;
+use nalgebra::{Matrix4, Point3};
+use std::f32::consts::TAU;
+use std::sync::Arc;
+
+// Paso de tiempo fijo del loop (~60 FPS)
+pub const FIXED_DT: f32 = 0.016;
+
+// Rotación propia de la estrella en rad/s (0.005 por frame a 60 FPS)
+const ROTATION_SPEED: f32 = 0.005 / FIXED_DT;
+
+// Con --loop, cuánto puede alejarse cada órbita o giro de su periodo al
+// redondearlo a vueltas enteras, y cuántos periodos de los shaders puede
+// durar como mucho el loop para lograrlo
+const LOOP_PERIOD_TOLERANCE: f32 = 0.1;
+const MAX_LOOP_REPEATS: u32 = 32;
+
+// Resolución de cada cara del shadow map de la estrella
+const SHADOW_MAP_SIZE: usize = 512;
+
+// Campo de visión vertical de la cámara
+const FOV: f32 = std::f32::consts::PI / 3.0;
+
+// Esfera más densa y cantidad de niveles del LOD de los cuerpos
+const DEFAULT_LOD_SPHERE: SphereMesh = SphereMesh::Icosphere { subdivisions: 4 };
+pub const LOD_LEVELS: usize = 5;
+
+// Divisiones angulares y radiales del mesh de los anillos
+const RING_SEGMENTS: u32 = 128;
+const RING_DIVISIONS: u32 = 6;
+
+// Planeta (o luna) en órbita circular alrededor de la estrella o de su padre (plano XZ)
+pub struct Body {
+    pub shader: Box<dyn PlanetShader>,
+    // Radio del cuerpo en unidades de mundo
+    pub radius: f32,
+    pub orbit_radius: f32,
+    // Segundos por órbita y por rotación propia
+    pub orbit_period: f32,
+    pub spin_period: f32,
+    // Inclinación del eje de rotación (rad)
+    pub axial_tilt: f32,
+    // Ángulo orbital en t = 0 (rad)
+    pub orbit_phase: f32,
+    // Índice en `Scene::bodies` del cuerpo que orbita (None = la estrella);
+    // el padre debe aparecer antes en la lista
+    pub parent: Option<usize>,
+    // Capa de atmósfera opcional alrededor del cuerpo
+    pub atmosphere: Option<Atmosphere>,
+    // Anillos opcionales en el plano ecuatorial y su mesh (en radios del cuerpo)
+    pub rings: Option<Rings>,
+    pub ring_mesh: Option<tobj::Mesh>,
+    // Nivel de `Scene::lod` del último frame (la histéresis parte de él)
+    pub lod_level: usize,
+}
+
+impl Body {
+    fn new(
+        shader: Box<dyn PlanetShader>,
+        radius: f32,
+        orbit_radius: f32,
+        orbit_period: f32,
+        spin_period: f32,
+        axial_tilt: f32,
+        orbit_phase: f32,
+    ) -> Self {
+        Body {
+            shader,
+            radius,
+            orbit_radius,
+            orbit_period,
+            spin_period,
+            axial_tilt,
+            orbit_phase,
+            parent: None,
+            atmosphere: None,
+            rings: None,
+            ring_mesh: None,
+            lod_level: 0,
+        }
+    }
+
+    // Convertir en luna del cuerpo `parent`
+    fn around(mut self, parent: usize) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    fn with_atmosphere(mut self, atmosphere: Atmosphere) -> Self {
+        self.atmosphere = Some(atmosphere);
+        self
+    }
+
+    fn with_rings(mut self, rings: Rings) -> Self {
+        self.ring_mesh = Some(rings.mesh(RING_SEGMENTS, RING_DIVISIONS));
+        self.rings = Some(rings);
+        self
+    }
+
+    // Posición relativa al centro de su órbita (estrella o padre)
+    pub fn orbit_offset(&self, time: f32) -> Vector3 {
+        let angle = self.orbit_phase + TAU * time / self.orbit_period;
+        Vector3::new(self.orbit_radius * angle.cos(), 0.0, -self.orbit_radius * angle.sin())
+    }
+
+    // Traslación a `position` * inclinación del eje * giro propio * escala.
+    // `mesh_radius` es el radio del mesh compartido, para escalarlo a `radius`
+    pub fn model_matrix(&self, time: f32, position: Vector3, mesh_radius: f32) -> Matrix4<f32> {
+        let p = position;
+        let spin = TAU * time / self.spin_period;
+        Matrix4::new_translation(&nalgebra::Vector3::new(p.x, p.y, p.z))
+            * Matrix4::from_axis_angle(&nalgebra::Vector3::z_axis(), self.axial_tilt)
+            * Matrix4::from_axis_angle(&nalgebra::Vector3::y_axis(), spin)
+            * Matrix4::new_scaling(self.radius / mesh_radius)
+    }
+}
+
+// Sistema por defecto: un planeta por shader, de adentro hacia afuera
+fn default_bodies() -> Vec<Body> {
+    vec![
+        Body::new(Box::new(RockyPlanetShader), 0.12, 1.4, 9.0, 6.0, 0.05, 0.0).with_atmosphere(Atmosphere::dusty(0.025)),
+        Body::new(Box::new(VolcanicPlanetShader::default()), 0.15, 2.0, 14.0, 8.0, 0.2, 2.1),
+        Body::new(Box::new(BioLuminescentShader::default()), 0.2, 2.8, 21.0, 5.0, 0.41, 4.0)
+            .with_atmosphere(Atmosphere::earth_like(0.05)),
+        Body::new(Box::new(GasGiantShader::default()), 0.38, 3.9, 32.0, 3.0, 0.45, 1.0).with_rings(Rings::default()),
+        Body::new(Box::new(IcePlanetShader), 0.18, 5.0, 45.0, 7.0, 0.5, 5.3),
+        // Luna del planeta oceánico, en su mismo plano: lo eclipsa en cada órbita
+        Body::new(Box::new(RockyPlanetShader), 0.06, 0.45, 3.5, 3.5, 0.0, 0.0).around(2),
+    ]
+}
+
+// Luces de apoyo para tomas cinematográficas: relleno frío direccional que
+// aclara el lado nocturno y un foco de contraluz desde arriba y detrás
+pub fn cinematic_lights() -> Vec<Light> {
+    vec![
+        Light::directional(Vector3::new(1.0, -0.4, -1.0), ShaderColor::new(0.45, 0.55, 1.0, 1.0), 0.25),
+        Light::spot(
+            Vector3::new(0.0, 6.0, -8.0),
+            Vector3::new(0.0, -6.0, 8.0),
+            0.35,
+            0.6,
+            ShaderColor::new(1.0, 0.95, 0.9, 1.0),
+            1.2,
+        )
+        .with_attenuation(1.0, 0.0, 0.005),
+    ]
+}
+
+// Estado de la escena compartido por el loop interactivo y el modo headless
+pub struct Scene {
+    pub mesh: tobj::Mesh,
+    // Radio del mesh (distancia máxima al origen), para escalar los cuerpos
+    pub mesh_radius: f32,
+    // Distancia mínima de un vértice al origen (el mesh no es una esfera perfecta)
+    pub mesh_inner_radius: f32,
+    pub stars: Vec<Star>,
+    pub star_shader: StarShader,
+    pub bodies: Vec<Body>,
+    // Modelos cargados de archivo (naves, estaciones), cada uno con su transformación
+    pub objects: Vec<SceneObject>,
+    // Luces además de la estrella (relleno, contraluz, otra estrella...)
+    pub extra_lights: Vec<Light>,
+    // Sombras de la luz de la estrella (eclipses entre cuerpos)
+    pub shadows: bool,
+    // Corona de filamentos alrededor de la estrella
+    pub corona: Corona,
+    pub show_corona: bool,
+    // Capas de atmósfera de los cuerpos que la tienen
+    pub atmospheres: bool,
+    // Niveles de detalle para los cuerpos (la estrella siempre usa `mesh`)
+    pub lod: MeshLod,
+    pub use_lod: bool,
+    // Teselado adaptativo según la cámara para la estrella y los cuerpos cuyo
+    // shader desplaza vértices (tiene prioridad sobre el LOD)
+    pub adaptive: AdaptiveSphere,
+    pub use_adaptive: bool,
+    pub time: f32,
+    pub rotation_angle: f32,
+    pub rotation_speed: f32,
+    pub camera_distance: f32,
+    pub camera_height: f32,
+    pub camera_angle: f32,
+}
+
+impl Scene {
+    pub fn new(mesh: tobj::Mesh, width: usize, height: usize) -> Self {
+        let mesh_radius = mesh
+            .positions
+            .chunks_exact(3)
+            .map(|p| Vector3::new(p[0], p[1], p[2]).length())
+            .fold(0.0, f32::max)
+            .max(f32::EPSILON);
+        let mesh_inner_radius = mesh
+            .positions
+            .chunks_exact(3)
+            .map(|p| Vector3::new(p[0], p[1], p[2]).length())
+            .fold(mesh_radius, f32::min);
+        Scene {
+            mesh,
+            mesh_radius,
+            mesh_inner_radius,
+            // Generar campo de estrellas
+            stars: generate_stars(500, width, height),
+            star_shader: StarShader::default(),
+            bodies: default_bodies(),
+            objects: Vec::new(),
+            extra_lights: Vec::new(),
+            shadows: true,
+            corona: Corona::default(),
+            show_corona: true,
+            atmospheres: true,
+            lod: MeshLod::from_sphere(DEFAULT_LOD_SPHERE, mesh_radius, LOD_LEVELS),
+            use_lod: false,
+            adaptive: AdaptiveSphere::default(),
+            use_adaptive: false,
+            time: 0.0,
+            rotation_angle: 0.0,
+            rotation_speed: ROTATION_SPEED,
+            camera_distance: 9.0,
+            camera_height: 3.5,
+            camera_angle: 0.0,
+        }
+    }
+
+    // Avanzar la animación dt segundos
+    pub fn update(&mut self, dt: f32) {
+        self.time += dt;
+        self.rotation_angle += self.rotation_speed * dt;
+    }
+
+    // Duración de un ciclo completo de la animación (periodo de la estrella)
+    pub fn loop_period(&self) -> f32 {
+        self.star_shader.loop_period
+    }
+
+    // Cambiar el periodo de loop de todos los shaders animados
+    pub fn set_loop_period(&mut self, period: f32) {
+        self.star_shader.loop_period = period;
+        self.corona.loop_period = period;
+        for body in &mut self.bodies {
+            body.shader.set_loop_period(period);
+        }
+    }
+
+    // Duración de un loop sin salto para shaders que repiten cada `period`
+    pub fn loop_length(&self, period: f32) -> f32 {
+        let mut periods: Vec<f32> = self.bodies.iter().flat_map(|b| [b.orbit_period, b.spin_period]).collect();
+        if self.rotation_speed > 0.0 {
+            periods.push(TAU / self.rotation_speed);
+        }
+        seamless_loop_length(&periods, period)
+    }
+
+    // Ajustar giros y órbitas para que den un número entero de vueltas (al
+    // menos una) por periodo; así el último frame del loop empalma con el primero
+    pub fn snap_rotation_to_loop(&mut self, period: f32) {
+        let turns = (self.rotation_speed * period / TAU).round().max(1.0);
+        self.rotation_speed = turns * TAU / period;
+        for body in &mut self.bodies {
+            body.orbit_period = snap_period(body.orbit_period, period);
+            body.spin_period = snap_period(body.spin_period, period);
+        }
+    }
+
+    // Posición en el mundo de cada cuerpo (las lunas suman la de su padre)
+    pub fn body_positions(&self) -> Vec<Vector3> {
+        let mut positions: Vec<Vector3> = Vec::with_capacity(self.bodies.len());
+        for body in &self.bodies {
+            let center = body.parent.map_or(Vector3::zero(), |p| positions[p]);
+            positions.push(center + body.orbit_offset(self.time));
+        }
+        positions
+    }
+
+    // Elegir el nivel de detalle de cada cuerpo por su radio proyectado (px)
+    // en una imagen de `height` px de alto
+    pub fn update_lod(&mut self, height: usize) {
+        let camera_pos = self.camera_position();
+        let focal = focal_length(height);
+        let positions = self.body_positions();
+        for (body, position) in self.bodies.iter_mut().zip(positions) {
+            let distance = (position - camera_pos).length();
+            let screen_radius = if distance > body.radius { body.radius * focal / distance } else { f32::INFINITY };
+            body.lod_level = self.lod.select(body.lod_level, screen_radius);
+        }
+    }
+
+    // Mesh con el que se dibuja el cuerpo y su radio interior (para las atmósferas)
+    fn body_mesh(&self, body: &Body) -> (&tobj::Mesh, f32) {
+        if self.use_lod {
+            let level = &self.lod.levels[body.lod_level.min(self.lod.levels.len() - 1)];
+            (&level.mesh, level.inner_radius)
+        } else {
+            (&self.mesh, self.mesh_inner_radius)
+        }
+    }
+
+    // Mesh teselado para la vista actual si el shader desplaza vértices y el
+    // teselado adaptativo está activo; la cámara se lleva al espacio del mesh
+    fn adaptive_mesh(&self, shader: &dyn PlanetShader, model: &Matrix4<f32>, camera: Vector3, height: usize) -> Option<tobj::Mesh> {
+        if !self.use_adaptive || !shader.displaces_geometry() {
+            return None;
+        }
+        let local = model.try_inverse()?.transform_point(&Point3::new(camera.x, camera.y, camera.z));
+        let camera = Vector3::new(local.x, local.y, local.z);
+        Some(self.adaptive.mesh(camera, self.mesh_radius, focal_length(height)))
+    }
+
+    pub fn camera_position(&self) -> Vector3 {
+        Vector3::new(
+            self.camera_distance * self.camera_angle.sin(),
+            self.camera_height,
+            self.camera_distance * self.camera_angle.cos(),
+        )
+    }
+
+    // Dibujar fondo, estrella y planetas en el buffer HDR del framebuffer (sin
+    // HUD). El color_buffer lo escribe después la cadena de post-proceso
+    pub fn render(&mut self, fb: &mut Framebuffer, options: &RenderOptions) {
+        if self.use_lod {
+            self.update_lod(fb.height);
+        }
+
+        // Clear framebuffer
+        fb.clear(0x000000);
+
+        // Renderizar fondo de estrellas
+        render_stars(fb, &self.stars);
+
+        // Matrices de transformación para cámara
+        let camera_pos = self.camera_position();
+        let eye = Point3::new(camera_pos.x, camera_pos.y, camera_pos.z);
+        let target = Point3::new(0.0, 0.0, 0.0);
+        let up = nalgebra::Vector3::new(0.0, 1.0, 0.0);
+        let view = Matrix4::look_at_rh(&eye, &target, &up);
+
+        let aspect = fb.width as f32 / fb.height as f32;
+        let projection = Matrix4::new_perspective(aspect, FOV, 0.1, 100.0);
+        let view_projection = projection * view;
+
+        // La estrella (en el origen) es la luz puntual que ilumina los planetas
+        let mut lights = vec![self.star_shader.light(Vector3::zero(), self.time)];
+        lights.extend_from_slice(&self.extra_lights);
+
+        let positions = self.body_positions();
+        let models: Vec<Matrix4<f32>> = positions
+            .iter()
+            .zip(&self.bodies)
+            .map(|(position, body)| body.model_matrix(self.time, *position, self.mesh_radius))
+            .collect();
+        let adaptive: Vec<Option<tobj::Mesh>> = self
+            .bodies
+            .iter()
+            .zip(&models)
+            .map(|(body, model)| self.adaptive_mesh(body.shader.as_ref(), model, camera_pos, fb.height))
+            .collect();
+        let meshes: Vec<&tobj::Mesh> = self
+            .bodies
+            .iter()
+            .zip(&adaptive)
+            .map(|(body, adaptive)| adaptive.as_ref().unwrap_or(self.body_mesh(body).0))
+            .collect();
+
+        // Pase de sombras desde la estrella: todos los cuerpos y objetos
+        // proyectan sombra (la estrella no, contiene a la propia luz)
+        let shadow = self.shadows.then(|| {
+            let mut shadow_map = ShadowMap::new(0, Vector3::zero(), SHADOW_MAP_SIZE);
+            for ((body, model), mesh) in self.bodies.iter().zip(&models).zip(&meshes) {
+                let uniforms = ShaderUniforms::new(self.time, Vec::new(), camera_pos, *model);
+                shadow_map.render_caster(mesh, model, body.shader.as_ref(), &uniforms);
+            }
+            for object in &self.objects {
+                let uniforms = ShaderUniforms::new(self.time, Vec::new(), camera_pos, object.transform);
+                shadow_map.render_caster(&object.mesh, &object.transform, &object.shader, &uniforms);
+            }
+            Arc::new(shadow_map)
+        });
+
+        // Renderizar la estrella
+        let model = Matrix4::from_axis_angle(&nalgebra::Vector3::y_axis(), self.rotation_angle);
+        let uniforms = ShaderUniforms::new(self.time, lights.clone(), camera_pos, model);
+        let shader: &dyn PlanetShader = &self.star_shader;
+        let star_mesh = self.adaptive_mesh(shader, &model, camera_pos, fb.height);
+        render_planet(fb, star_mesh.as_ref().unwrap_or(&self.mesh), &(view_projection * model), shader, &uniforms, options);
+
+        // Cada planeta con su propio shader y matriz de modelo
+        for (((body, model), position), mesh) in self.bodies.iter().zip(&models).zip(&positions).zip(&meshes) {
+            // Los anillos sombrean a su planeta junto con el resto de las sombras
+            let ring_shadow = body.rings.filter(|_| self.shadows).map(|rings| {
+                let axis = model.transform_vector(&nalgebra::Vector3::y()).normalize();
+                RingShadow { rings, center: *position, normal: Vector3::new(axis.x, axis.y, axis.z), scale: body.radius }
+            });
+            let uniforms = ShaderUniforms::new(self.time, lights.clone(), camera_pos, *model)
+                .with_shadow(shadow.clone())
+                .with_ring_shadow(ring_shadow);
+            render_planet(fb, mesh, &(view_projection * model), body.shader.as_ref(), &uniforms, options);
+        }
+
+        // Anillos, atmósferas y materiales con transparencia: después de todo
+        // lo opaco y ordenados de atrás hacia adelante por la cola
+        let mut translucent = TransparentQueue::default();
+        for object in &self.objects {
+            let uniforms = ShaderUniforms::new(self.time, lights.clone(), camera_pos, object.transform).with_shadow(shadow.clone());
+            let mvp = view_projection * object.transform;
+            if object.shader.blend_mode() == BlendMode::Opaque {
+                render_planet(fb, &object.mesh, &mvp, &object.shader, &uniforms, options);
+            } else {
+                translucent.push(&object.mesh, mvp, Box::new(object.shader.clone()), uniforms);
+            }
+        }
+        for (body, model) in self.bodies.iter().zip(&models) {
+            if let (Some(rings), Some(ring_mesh)) = (body.rings, &body.ring_mesh) {
+                // El mesh de los anillos está en radios del cuerpo
+                let model = model * Matrix4::new_scaling(self.mesh_radius);
+                let uniforms = ShaderUniforms::new(self.time, lights.clone(), camera_pos, model).with_shadow(shadow.clone());
+                translucent.push(ring_mesh, view_projection * model, Box::new(RingShader { rings }), uniforms);
+            }
+            if let Some(atmosphere) = body.atmosphere.filter(|_| self.atmospheres) {
+                let (mesh, inner_radius) = self.body_mesh(body);
+                let shader = AtmosphereShader {
+                    atmosphere,
+                    planet_radius: body.radius,
+                    limb_softness: 1.0 - inner_radius / self.mesh_radius,
+                };
+                let model = model * Matrix4::new_scaling((body.radius + atmosphere.height) / body.radius);
+                let uniforms = ShaderUniforms::new(self.time, lights.clone(), camera_pos, model).with_shadow(shadow.clone());
+                translucent.push(mesh, view_projection * model, Box::new(shader), uniforms);
+            }
+        }
+
+        // La corona (aditiva, sin mesh) va en el lugar de la estrella dentro del
+        // orden: detrás de lo translúcido que está más cerca que la estrella
+        if self.show_corona {
+            translucent.flush_beyond(fb, options, camera_pos.length());
+            let right = Vector3::new(view[(0, 0)], view[(0, 1)], view[(0, 2)]);
+            self.corona.render(fb, &view_projection, right, Vector3::zero(), self.mesh_radius, &uniforms);
+        }
+        translucent.flush(fb, options);
+    }
+}
+
+// Distancia focal en píxeles para una imagen de `height` px de alto
+fn focal_length(height: usize) -> f32 {
+    height as f32 * 0.5 / (FOV * 0.5).tan()
+}
+
+// Periodo ajustado a un número entero de ciclos (al menos uno) dentro de `loop_period`
+fn snap_period(period: f32, loop_period: f32) -> f32 {
+    let cycles = (loop_period / period).round().max(1.0);
+    loop_period / cycles
+}
+
+// Múltiplo de `period` en el que todos los `periods` dan un número entero de
+// vueltas sin alejarse más de LOOP_PERIOD_TOLERANCE de su valor: cada cuerpo
+// redondea a sus propias vueltas y conserva su velocidad relativa (con un solo
+// periodo de shader, todas las órbitas más lentas quedarían en una vuelta y
+// los planetas se moverían a la par). Si ningún múltiplo hasta
+// MAX_LOOP_REPEATS alcanza, el que menos se aleja
+fn seamless_loop_length(periods: &[f32], period: f32) -> f32 {
+    let error = |length: f32| {
+        periods
+            .iter()
+            .map(|&p| (snap_period(p, length) / p - 1.0).abs())
+            .fold(0.0, f32::max)
+    };
+    let lengths = (1..=MAX_LOOP_REPEATS).map(|k| k as f32 * period);
+    lengths
+        .clone()
+        .find(|&length| error(length) <= LOOP_PERIOD_TOLERANCE)
+        .unwrap_or_else(|| lengths.min_by(|&a, &b| error(a).total_cmp(&error(b))).unwrap_or(period))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loop_length_keeps_orbit_ratios() {
+        let period = std::f32::consts::TAU / 0.6;
+        let orbits = [9.0, 14.0, 21.0, 32.0, 45.0, 3.5];
+        let length = seamless_loop_length(&orbits, period);
+
+        // Múltiplo entero del periodo de los shaders
+        let repeats = length / period;
+        assert!((repeats - repeats.round()).abs() < 1e-4);
+
+        // Cada órbita cerca de su periodo y en el mismo orden: ninguna se
+        // sincroniza con otra
+        let snapped: Vec<f32> = orbits.iter().map(|&p| snap_period(p, length)).collect();
+        for (&p, &s) in orbits.iter().zip(&snapped) {
+            assert!((s / p - 1.0).abs() <= LOOP_PERIOD_TOLERANCE, "{} -> {}", p, s);
+            let cycles = length / s;
+            assert!((cycles - cycles.round()).abs() < 1e-3);
+        }
+        assert!(snapped.windows(2).all(|w| w[0] != w[1]));
+    }
+
+    #[test]
+    fn loop_length_is_one_period_when_it_already_fits() {
+        assert_eq!(seamless_loop_length(&[2.0, 5.0], 10.0), 10.0);
+        assert_eq!(seamless_loop_length(&[], 10.0), 10.0);
+        // Un periodo más largo que el loop obliga a alargarlo
+        assert_eq!(seamless_loop_length(&[30.0], 10.0), 30.0);
+    }
+}
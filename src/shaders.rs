@@ -1,4 +1,10 @@
+use crate::lights::Light;
+use crate::rasterizer::BlendMode;
+use crate::rings::RingShadow;
+use crate::shadows::ShadowMap;
 use crate::vector::Vector3;
+use nalgebra::{Matrix3, Matrix4, Point3};
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Copy)]
 pub struct ShaderColor {
@@ -25,19 +31,123 @@ impl ShaderColor {
 
 pub struct ShaderUniforms {
     pub time: f32,
-    pub light_direction: Vector3,
+    // Todas las luces de la escena, en espacio de mundo
+    pub lights: Vec<Light>,
     pub camera_position: Vector3,
+    // Matriz de modelo del cuerpo (objeto -> mundo) y su matriz de normales
+    pub model: Matrix4<f32>,
+    pub normal_matrix: Matrix3<f32>,
+    // Shadow map de una de las luces (compartido por todos los cuerpos del frame)
+    pub shadow: Option<Arc<ShadowMap>>,
+    // Anillos del propio cuerpo, que le dan sombra con cualquier luz
+    pub ring_shadow: Option<RingShadow>,
 }
 
-pub trait PlanetShader {
+impl ShaderUniforms {
+    pub fn new(time: f32, lights: Vec<Light>, camera_position: Vector3, model: Matrix4<f32>) -> Self {
+        // Inversa transpuesta de la parte 3x3 (sirve también con escala no uniforme)
+        let linear = model.fixed_view::<3, 3>(0, 0).into_owned();
+        let normal_matrix = linear
+            .try_inverse()
+            .map(|inv| inv.transpose())
+            .unwrap_or(linear);
+        ShaderUniforms { time, lights, camera_position, model, normal_matrix, shadow: None, ring_shadow: None }
+    }
+
+    pub fn with_shadow(mut self, shadow: Option<Arc<ShadowMap>>) -> Self {
+        self.shadow = shadow;
+        self
+    }
+
+    pub fn with_ring_shadow(mut self, ring_shadow: Option<RingShadow>) -> Self {
+        self.ring_shadow = ring_shadow;
+        self
+    }
+
+    // Término de visibilidad de la luz `light_index` en `position` (mundo):
+    // 1 = iluminado, 0 = en sombra. Combina el shadow map (si es de esa luz)
+    // y la luz que dejan pasar los anillos del cuerpo
+    pub fn visibility(&self, light_index: usize, position: Vector3, normal: Vector3) -> f32 {
+        let shadow = match &self.shadow {
+            Some(shadow) if shadow.light_index == light_index => shadow.visibility(position, normal),
+            _ => 1.0,
+        };
+        let rings = match (&self.ring_shadow, self.lights.get(light_index)) {
+            (Some(rings), Some(light)) => light
+                .illuminate(position)
+                .map_or(1.0, |(to_light, _)| rings.transmittance(position, to_light)),
+            _ => 1.0,
+        };
+        shadow * rings
+    }
+
+    // Posición en espacio de objeto (la que recibe el fragment shader) -> mundo
+    pub fn world_position(&self, p: Vector3) -> Vector3 {
+        let w = self.model.transform_point(&Point3::new(p.x, p.y, p.z));
+        Vector3::new(w.x, w.y, w.z)
+    }
+
+    // Normal en espacio de objeto -> mundo (normalizada)
+    pub fn world_normal(&self, n: Vector3) -> Vector3 {
+        let w = self.normal_matrix * nalgebra::Vector3::new(n.x, n.y, n.z);
+        Vector3::new(w.x, w.y, w.z).normalize()
+    }
+}
+
+// Sync: el rasterizador por tiles comparte el shader entre hilos
+pub trait PlanetShader: Sync {
     fn vertex_shader(&self, position: Vector3, normal: Vector3, uv: (f32, f32), uniforms: &ShaderUniforms) -> (Vector3, Vector3);
     fn fragment_shader(&self, position: Vector3, normal: Vector3, uv: (f32, f32), uniforms: &ShaderUniforms) -> ShaderColor;
+
+    // true si el vertex shader mueve los vértices; el renderer recalcula
+    // entonces las normales a partir de la geometría desplazada
+    fn displaces_geometry(&self) -> bool {
+        false
+    }
+
+    // Periodo (s) en que se repite la animación del shader; los shaders sin
+    // animación lo ignoran
+    fn set_loop_period(&mut self, _period: f32) {}
+
+    // Cómo se combinan sus fragmentos con el framebuffer; los translúcidos
+    // (anillos, atmósferas) se dibujan después de los opacos
+    fn blend_mode(&self) -> BlendMode {
+        BlendMode::Opaque
+    }
+
+    // Si sus fragmentos ocupan el depth buffer. Por defecto solo los opacos,
+    // así lo translúcido no tapa lo que se dibuje detrás después
+    fn depth_write(&self) -> bool {
+        self.blend_mode() == BlendMode::Opaque
+    }
+
+    // true si el fragment shader usa los UV del mesh (texturas) en vez de los
+    // esféricos que se calculan desde la posición
+    fn uses_texcoords(&self) -> bool {
+        false
+    }
+
+    // Alfa mínimo de un fragmento; los de menos se descartan sin tocar color
+    // ni profundidad (alpha test). None = no descartar
+    fn alpha_cutoff(&self) -> Option<f32> {
+        None
+    }
+
+    // true = dibujar también las caras traseras (sin backface culling)
+    fn double_sided(&self) -> bool {
+        false
+    }
 }
 
 // ============================================================================
 // FUNCIONES AUXILIARES
 // ============================================================================
 
+// Periodo de loop (s) de los shaders animados con --loop sin --loop-period:
+// un ciclo del pulso de la estrella, así toda la escena repite junta.
+// Sin --loop ni --loop-period los shaders no repiten (loop_period = 0)
+pub const DEFAULT_LOOP_PERIOD: f32 = std::f32::consts::TAU / STAR_PULSE_SPEED;
+
 // ============================================================================
 // SIMPLEX NOISE (3D) + FBM
 // Implementacion de Simplex noise 3D (mas eficiente que Perlin, menos artefactos)
@@ -180,7 +290,7 @@ fn simplex_noise(x: f32, y: f32, z: f32) -> f32 {
     (result * 0.5) + 0.5
 }
 
-fn fbm_simplex(x: f32, y: f32, z: f32, octaves: i32) -> f32 {
+pub fn fbm_simplex(x: f32, y: f32, z: f32, octaves: i32) -> f32 {
     let mut value = 0.0;
     let mut amplitude = 0.5;
     let mut frequency = 1.0;
@@ -194,6 +304,139 @@ fn fbm_simplex(x: f32, y: f32, z: f32, octaves: i32) -> f32 {
     value
 }
 
+// ============================================================================
+// SIMPLEX NOISE (4D) + ANIMACION PERIODICA
+// La cuarta dimension permite recorrer el tiempo en un circulo: el patron
+// vuelve exactamente al mismo estado cada `loop_period` segundos
+// ============================================================================
+
+// Gradientes 4D: permutaciones de (0, ±1, ±1, ±1)
+static GRAD4: [[f32; 4]; 32] = [
+    [0.0, 1.0, 1.0, 1.0], [0.0, 1.0, 1.0, -1.0], [0.0, 1.0, -1.0, 1.0], [0.0, 1.0, -1.0, -1.0],
+    [0.0, -1.0, 1.0, 1.0], [0.0, -1.0, 1.0, -1.0], [0.0, -1.0, -1.0, 1.0], [0.0, -1.0, -1.0, -1.0],
+    [1.0, 0.0, 1.0, 1.0], [1.0, 0.0, 1.0, -1.0], [1.0, 0.0, -1.0, 1.0], [1.0, 0.0, -1.0, -1.0],
+    [-1.0, 0.0, 1.0, 1.0], [-1.0, 0.0, 1.0, -1.0], [-1.0, 0.0, -1.0, 1.0], [-1.0, 0.0, -1.0, -1.0],
+    [1.0, 1.0, 0.0, 1.0], [1.0, 1.0, 0.0, -1.0], [1.0, -1.0, 0.0, 1.0], [1.0, -1.0, 0.0, -1.0],
+    [-1.0, 1.0, 0.0, 1.0], [-1.0, 1.0, 0.0, -1.0], [-1.0, -1.0, 0.0, 1.0], [-1.0, -1.0, 0.0, -1.0],
+    [1.0, 1.0, 1.0, 0.0], [1.0, 1.0, -1.0, 0.0], [1.0, -1.0, 1.0, 0.0], [1.0, -1.0, -1.0, 0.0],
+    [-1.0, 1.0, 1.0, 0.0], [-1.0, 1.0, -1.0, 0.0], [-1.0, -1.0, 1.0, 0.0], [-1.0, -1.0, -1.0, 0.0],
+];
+
+fn simplex_noise_4d(x: f32, y: f32, z: f32, w: f32) -> f32 {
+    // Skewing/unskewing factors para 4D
+    let f4: f32 = (5.0f32.sqrt() - 1.0) / 4.0;
+    let g4: f32 = (5.0 - 5.0f32.sqrt()) / 20.0;
+
+    // Skew input space
+    let s = (x + y + z + w) * f4;
+    let i = (x + s).floor();
+    let j = (y + s).floor();
+    let k = (z + s).floor();
+    let l = (w + s).floor();
+
+    let t = (i + j + k + l) * g4;
+    let x0 = x - (i - t);
+    let y0 = y - (j - t);
+    let z0 = z - (k - t);
+    let w0 = w - (l - t);
+
+    // Ordenar las coordenadas para elegir el simplice (ranking por magnitud)
+    let mut rank = [0; 4];
+    let c = [x0, y0, z0, w0];
+    for a in 0..4 {
+        for b in (a + 1)..4 {
+            if c[a] > c[b] {
+                rank[a] += 1;
+            } else {
+                rank[b] += 1;
+            }
+        }
+    }
+    let offset = |threshold: i32| -> [i32; 4] { rank.map(|r| (r >= threshold) as i32) };
+    let corners = [[0; 4], offset(3), offset(2), offset(1), [1; 4]];
+
+    // Hash coordinates
+    let ii = i as i32 & 255;
+    let jj = j as i32 & 255;
+    let kk = k as i32 & 255;
+    let ll = l as i32 & 255;
+
+    let mut total = 0.0;
+    for (n, corner) in corners.iter().enumerate() {
+        let g = n as f32 * g4;
+        let dx = x0 - corner[0] as f32 + g;
+        let dy = y0 - corner[1] as f32 + g;
+        let dz = z0 - corner[2] as f32 + g;
+        let dw = w0 - corner[3] as f32 + g;
+
+        let t = 0.6 - dx * dx - dy * dy - dz * dz - dw * dw;
+        if t > 0.0 {
+            let h = PERM[(ii + corner[0]
+                + PERM[(jj + corner[1] + PERM[(kk + corner[2] + PERM[(ll + corner[3]) as usize] as i32) as usize] as i32) as usize] as i32) as usize]
+                as usize
+                % 32;
+            let grad = GRAD4[h];
+            let t_sq = t * t;
+            total += t_sq * t_sq * (grad[0] * dx + grad[1] * dy + grad[2] * dz + grad[3] * dw);
+        }
+    }
+
+    // Suma y normaliza a [0,1]
+    let result = 27.0 * total;
+    (result * 0.5) + 0.5
+}
+
+fn fbm_simplex_4d(x: f32, y: f32, z: f32, w: f32, octaves: i32) -> f32 {
+    let mut value = 0.0;
+    let mut amplitude = 0.5;
+    let mut frequency = 1.0;
+
+    for _ in 0..octaves {
+        value += amplitude * simplex_noise_4d(x * frequency, y * frequency, z * frequency, w * frequency);
+        frequency *= 2.0;
+        amplitude *= 0.5;
+    }
+
+    value
+}
+
+// FBM que se desplaza en z con el tiempo (z + time * speed). Con period > 0 el
+// desplazamiento recorre un circulo en el plano (z, w) cuyo perimetro es lo que
+// se avanzaria en un periodo: misma velocidad aparente, pero periodico.
+// Con period <= 0 se usa el ruido 3D original (no repite nunca).
+pub fn fbm_animated(x: f32, y: f32, z: f32, time: f32, speed: f32, period: f32, octaves: i32) -> f32 {
+    if period > 0.0 {
+        let radius = speed * period / std::f32::consts::TAU;
+        let angle = std::f32::consts::TAU * time.rem_euclid(period) / period;
+        fbm_simplex_4d(x, y, z + radius * angle.cos(), radius * angle.sin(), octaves)
+    } else {
+        fbm_simplex(x, y, z + time * speed, octaves)
+    }
+}
+
+// Fase time * speed de una onda, con speed redondeada a un numero entero de
+// ciclos por periodo (minimo uno) para que sin(fase) repita cada `period`
+fn looped_phase(time: f32, speed: f32, period: f32) -> f32 {
+    if period > 0.0 {
+        loop_cycles(speed, period) * std::f32::consts::TAU * time.rem_euclid(period) / period
+    } else {
+        time * speed
+    }
+}
+
+// Velocidad (rad/s) con la que looped_phase avanza de verdad
+fn looped_speed(speed: f32, period: f32) -> f32 {
+    if period > 0.0 {
+        loop_cycles(speed, period) * std::f32::consts::TAU / period
+    } else {
+        speed
+    }
+}
+
+fn loop_cycles(speed: f32, period: f32) -> f32 {
+    (speed * period / std::f32::consts::TAU).round().max(1.0)
+}
+
 fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
     let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
     t * t * (3.0 - 2.0 * t)
@@ -212,6 +455,46 @@ fn mix_color(a: ShaderColor, b: ShaderColor, t: f32) -> ShaderColor {
     )
 }
 
+// Especular Blinn-Phong con el half-vector entre luz y vista
+pub fn blinn_phong(normal: Vector3, to_light: Vector3, view_dir: Vector3, shininess: f32) -> f32 {
+    if normal.dot(&to_light) <= 0.0 {
+        return 0.0;
+    }
+    let half = (to_light + view_dir).normalize();
+    normal.dot(&half).max(0.0).powf(shininess)
+}
+
+// Suma sobre todas las luces de (difusa * kd + especular * ks) * color de la luz.
+// `position` y `normal` en espacio de mundo; ks = 0 desactiva el especular
+fn direct_lighting(uniforms: &ShaderUniforms, position: Vector3, normal: Vector3, kd: f32, ks: f32, shininess: f32) -> ShaderColor {
+    let view_dir = (uniforms.camera_position - position).normalize();
+    let mut total = ShaderColor::new(0.0, 0.0, 0.0, 1.0);
+    for (i, light) in uniforms.lights.iter().enumerate() {
+        if let Some((to_light, radiance)) = light.illuminate(position) {
+            let diffuse = normal.dot(&to_light).max(0.0);
+            if diffuse <= 0.0 {
+                continue;
+            }
+            let specular = if ks > 0.0 { blinn_phong(normal, to_light, view_dir, shininess) } else { 0.0 };
+            let k = (diffuse * kd + specular * ks) * uniforms.visibility(i, position, normal);
+            total.r += radiance.r * k;
+            total.g += radiance.g * k;
+            total.b += radiance.b * k;
+        }
+    }
+    total
+}
+
+// base * (ambient + direct), por canal y con tope `max` (HDR: puede pasar de 1)
+fn apply_lighting(base: ShaderColor, ambient: f32, direct: ShaderColor, max: f32) -> ShaderColor {
+    ShaderColor::new(
+        (base.r * (ambient + direct.r).min(max)).max(0.0),
+        (base.g * (ambient + direct.g).min(max)).max(0.0),
+        (base.b * (ambient + direct.b).min(max)).max(0.0),
+        1.0,
+    )
+}
+
 // ============================================================================
 // PLANETA 1: PLANETA ROCOSO CON CRÁTERES (MÁS SUAVE)
 // ============================================================================
@@ -233,7 +516,13 @@ impl PlanetShader for RockyPlanetShader {
         (deformed, normal)
     }
 
-    fn fragment_shader(&self, _position: Vector3, normal: Vector3, uv: (f32, f32), uniforms: &ShaderUniforms) -> ShaderColor {
+    fn displaces_geometry(&self) -> bool {
+        true
+    }
+
+    fn fragment_shader(&self, position: Vector3, normal: Vector3, uv: (f32, f32), uniforms: &ShaderUniforms) -> ShaderColor {
+        let position = uniforms.world_position(position);
+        let normal = uniforms.world_normal(normal);
         // Colores más suaves
         let base_brown = ShaderColor::from_rgb(120, 90, 70);
         let light_brown = ShaderColor::from_rgb(160, 130, 100);
@@ -248,40 +537,48 @@ impl PlanetShader for RockyPlanetShader {
             mix_color(dark_brown, base_brown, smoothstep(0.4, 0.55, texture))
         };
         
-        // Iluminación
-        let light_dir = uniforms.light_direction.normalize();
-        let diffuse = normal.dot(&light_dir).max(0.0);
+        // Iluminación (todas las luces)
+        let direct = direct_lighting(uniforms, position, normal, 0.7, 0.0, 1.0);
         let ambient = 0.3;
-        let lighting = (ambient + diffuse * 0.7).min(1.0);
-        
-        ShaderColor::new(
-            (base_color.r * lighting).clamp(0.0, 1.0),
-            (base_color.g * lighting).clamp(0.0, 1.0),
-            (base_color.b * lighting).clamp(0.0, 1.0),
-            1.0,
-        )
+
+        apply_lighting(base_color, ambient, direct, 1.0)
     }
 }
 
 // ============================================================================
 // PLANETA 2: GIGANTE GASEOSO CON ANILLOS
 // ============================================================================
-pub struct GasGiantShader;
+pub struct GasGiantShader {
+    // Periodo (s) en que la animacion se repite exactamente; <= 0 = sin loop
+    pub loop_period: f32,
+}
+
+impl Default for GasGiantShader {
+    fn default() -> Self {
+        GasGiantShader { loop_period: 0.0 }
+    }
+}
 
 impl PlanetShader for GasGiantShader {
+    fn set_loop_period(&mut self, period: f32) {
+        self.loop_period = period;
+    }
+
     fn vertex_shader(&self, position: Vector3, normal: Vector3, _uv: (f32, f32), _uniforms: &ShaderUniforms) -> (Vector3, Vector3) {
         (position, normal)
     }
 
-    fn fragment_shader(&self, _position: Vector3, normal: Vector3, uv: (f32, f32), uniforms: &ShaderUniforms) -> ShaderColor {
+    fn fragment_shader(&self, position: Vector3, normal: Vector3, uv: (f32, f32), uniforms: &ShaderUniforms) -> ShaderColor {
+        let position = uniforms.world_position(position);
+        let normal = uniforms.world_normal(normal);
         // Colores de gigante gaseoso (naranja/crema)
         let orange = ShaderColor::from_rgb(220, 150, 80);
         let cream = ShaderColor::from_rgb(240, 200, 150);
         let dark_orange = ShaderColor::from_rgb(180, 100, 50);
         
         // Bandas horizontales suaves
-        let bands = (uv.1 * 12.0 + uniforms.time * 0.1).sin() * 0.5 + 0.5;
-        let turbulence = fbm_simplex(uv.0 * 10.0, uv.1 * 5.0, uniforms.time * 0.05, 2) * 0.3;
+        let bands = (uv.1 * 12.0 + looped_phase(uniforms.time, 0.1, self.loop_period)).sin() * 0.5 + 0.5;
+        let turbulence = fbm_animated(uv.0 * 10.0, uv.1 * 5.0, 0.0, uniforms.time, 0.05, self.loop_period, 2) * 0.3;
         
         let color_mix = bands + turbulence;
         let base_color = if color_mix > 0.6 {
@@ -290,33 +587,41 @@ impl PlanetShader for GasGiantShader {
             mix_color(dark_orange, orange, smoothstep(0.3, 0.6, color_mix))
         };
         
-        // Iluminación
-        let light_dir = uniforms.light_direction.normalize();
-        let diffuse = normal.dot(&light_dir).max(0.0);
+        // Iluminación (todas las luces)
+        let direct = direct_lighting(uniforms, position, normal, 0.65, 0.0, 1.0);
         let ambient = 0.35;
-        let lighting = (ambient + diffuse * 0.65).min(1.0);
-        
-        ShaderColor::new(
-            (base_color.r * lighting).clamp(0.0, 1.0),
-            (base_color.g * lighting).clamp(0.0, 1.0),
-            (base_color.b * lighting).clamp(0.0, 1.0),
-            1.0,
-        )
+
+        apply_lighting(base_color, ambient, direct, 1.0)
     }
 }
 
 // ============================================================================
 // PLANETA 3: PLANETA OCEÁNICO (MÁS SUAVE)
 // ============================================================================
-pub struct BioLuminescentShader;
+pub struct BioLuminescentShader {
+    // Periodo (s) en que la animacion se repite exactamente; <= 0 = sin loop
+    pub loop_period: f32,
+}
+
+impl Default for BioLuminescentShader {
+    fn default() -> Self {
+        BioLuminescentShader { loop_period: 0.0 }
+    }
+}
 
 impl PlanetShader for BioLuminescentShader {
+    fn set_loop_period(&mut self, period: f32) {
+        self.loop_period = period;
+    }
+
     fn vertex_shader(&self, position: Vector3, normal: Vector3, _uv: (f32, f32), _uniforms: &ShaderUniforms) -> (Vector3, Vector3) {
         // Sin deformaciones - superficie completamente lisa
         (position, normal)
     }
 
     fn fragment_shader(&self, position: Vector3, normal: Vector3, uv: (f32, f32), uniforms: &ShaderUniforms) -> ShaderColor {
+        let position = uniforms.world_position(position);
+        let normal = uniforms.world_normal(normal);
         // Colores oceánicos suaves
         let deep_blue = ShaderColor::from_rgb(30, 60, 120);
         let ocean_blue = ShaderColor::from_rgb(50, 100, 180);
@@ -324,8 +629,8 @@ impl PlanetShader for BioLuminescentShader {
         let foam = ShaderColor::from_rgb(200, 220, 240);
         
         // Patrón oceánico suave
-        let ocean_pattern = fbm_simplex(uv.0 * 6.0, uv.1 * 6.0, uniforms.time * 0.1, 3);
-        let wave_pattern = (uv.0 * 15.0 + uniforms.time * 0.2).sin() * 0.5 + 0.5;
+        let ocean_pattern = fbm_animated(uv.0 * 6.0, uv.1 * 6.0, 0.0, uniforms.time, 0.1, self.loop_period, 3);
+        let wave_pattern = (uv.0 * 15.0 + looped_phase(uniforms.time, 0.2, self.loop_period)).sin() * 0.5 + 0.5;
         
         let combined = ocean_pattern * 0.7 + wave_pattern * 0.3;
         
@@ -338,19 +643,10 @@ impl PlanetShader for BioLuminescentShader {
         };
         
         // Iluminación con brillo oceánico
-        let light_dir = uniforms.light_direction.normalize();
-        let view_dir = (uniforms.camera_position - position).normalize();
-        let diffuse = normal.dot(&light_dir).max(0.0);
-        let specular = view_dir.dot(&normal).max(0.0).powf(20.0) * 0.3;
+        let direct = direct_lighting(uniforms, position, normal, 0.6, 0.3, 20.0);
         let ambient = 0.25;
-        let lighting = (ambient + diffuse * 0.6 + specular).min(1.2);
-        
-        ShaderColor::new(
-            (base_color.r * lighting).clamp(0.0, 1.0),
-            (base_color.g * lighting).clamp(0.0, 1.0),
-            (base_color.b * lighting).clamp(0.0, 1.0),
-            1.0,
-        )
+
+        apply_lighting(base_color, ambient, direct, 1.2)
     }
 }
 
@@ -366,6 +662,8 @@ impl PlanetShader for IcePlanetShader {
     }
 
     fn fragment_shader(&self, position: Vector3, normal: Vector3, uv: (f32, f32), uniforms: &ShaderUniforms) -> ShaderColor {
+        let position = uniforms.world_position(position);
+        let normal = uniforms.world_normal(normal);
         // Colores de hielo
         let ice_white = ShaderColor::from_rgb(240, 245, 255);
         let ice_blue = ShaderColor::from_rgb(180, 210, 240);
@@ -388,34 +686,40 @@ impl PlanetShader for IcePlanetShader {
         };
         
         // Iluminación brillante (hielo refleja mucha luz)
-        let light_dir = uniforms.light_direction.normalize();
-        let view_dir = (uniforms.camera_position - position).normalize();
-        let diffuse = normal.dot(&light_dir).max(0.0);
-        let specular = view_dir.dot(&normal).max(0.0).powf(30.0) * 0.5;
+        let direct = direct_lighting(uniforms, position, normal, 0.5, 0.5, 30.0);
         let ambient = 0.4;
-        let lighting = (ambient + diffuse * 0.5 + specular).min(1.3);
-        
-        ShaderColor::new(
-            (final_color.r * lighting).clamp(0.0, 1.0),
-            (final_color.g * lighting).clamp(0.0, 1.0),
-            (final_color.b * lighting).clamp(0.0, 1.0),
-            1.0,
-        )
+
+        apply_lighting(final_color, ambient, direct, 1.3)
     }
 }
 
 // ============================================================================
 // PLANETA 5: PLANETA VOLCÁNICO
 // ============================================================================
-pub struct VolcanicPlanetShader;
+pub struct VolcanicPlanetShader {
+    // Periodo (s) en que la animacion se repite exactamente; <= 0 = sin loop
+    pub loop_period: f32,
+}
+
+impl Default for VolcanicPlanetShader {
+    fn default() -> Self {
+        VolcanicPlanetShader { loop_period: 0.0 }
+    }
+}
 
 impl PlanetShader for VolcanicPlanetShader {
+    fn set_loop_period(&mut self, period: f32) {
+        self.loop_period = period;
+    }
+
     fn vertex_shader(&self, position: Vector3, normal: Vector3, _uv: (f32, f32), _uniforms: &ShaderUniforms) -> (Vector3, Vector3) {
         // Sin deformaciones - superficie completamente lisa
         (position, normal)
     }
 
-    fn fragment_shader(&self, _position: Vector3, normal: Vector3, uv: (f32, f32), uniforms: &ShaderUniforms) -> ShaderColor {
+    fn fragment_shader(&self, position: Vector3, normal: Vector3, uv: (f32, f32), uniforms: &ShaderUniforms) -> ShaderColor {
+        let position = uniforms.world_position(position);
+        let normal = uniforms.world_normal(normal);
         // Colores volcánicos
         let dark_rock = ShaderColor::from_rgb(40, 30, 30);
         let lava_red = ShaderColor::from_rgb(200, 50, 20);
@@ -423,8 +727,8 @@ impl PlanetShader for VolcanicPlanetShader {
         let lava_yellow = ShaderColor::from_rgb(255, 220, 100);
         
         // Patrón de lava
-        let lava_flow = fbm_simplex(uv.0 * 8.0, uv.1 * 8.0, uniforms.time * 0.2, 3);
-        let pulse = (uniforms.time * 2.0).sin() * 0.5 + 0.5;
+        let lava_flow = fbm_animated(uv.0 * 8.0, uv.1 * 8.0, 0.0, uniforms.time, 0.2, self.loop_period, 3);
+        let pulse = looped_phase(uniforms.time, 2.0, self.loop_period).sin() * 0.5 + 0.5;
         
         let heat = lava_flow * 0.7 + pulse * 0.3;
         
@@ -437,18 +741,11 @@ impl PlanetShader for VolcanicPlanetShader {
         };
         
         // Iluminación + emisión de lava
-        let light_dir = uniforms.light_direction.normalize();
-        let diffuse = normal.dot(&light_dir).max(0.0);
+        let direct = direct_lighting(uniforms, position, normal, 0.3, 0.0, 1.0);
         let emission = heat * 0.6; // La lava emite luz
         let ambient = 0.2;
-        let lighting = (ambient + diffuse * 0.3 + emission).min(1.5);
-        
-        ShaderColor::new(
-            (base_color.r * lighting).clamp(0.0, 1.0),
-            (base_color.g * lighting).clamp(0.0, 1.0),
-            (base_color.b * lighting).clamp(0.0, 1.0),
-            1.0,
-        )
+
+        apply_lighting(base_color, ambient + emission, direct, 1.5)
     }
 }
 
@@ -464,8 +761,13 @@ pub struct StarShader {
     pub octaves: i32,
     pub displacement_scale: f32,
     pub flare_strength: f32,
+    // Periodo (s) en que la animacion se repite exactamente; <= 0 = sin loop
+    pub loop_period: f32,
 }
 
+// Frecuencia angular (rad/s) del pulso de emision de la estrella
+pub const STAR_PULSE_SPEED: f32 = 0.6;
+
 impl Default for StarShader {
     fn default() -> Self {
         StarShader {
@@ -474,14 +776,19 @@ impl Default for StarShader {
             octaves: 6,
             displacement_scale: 0.08,
             flare_strength: 0.35,
+            loop_period: 0.0,
         }
     }
 }
 
 impl PlanetShader for StarShader {
+    fn set_loop_period(&mut self, period: f32) {
+        self.loop_period = period;
+    }
+
     fn vertex_shader(&self, position: Vector3, normal: Vector3, _uv: (f32, f32), uniforms: &ShaderUniforms) -> (Vector3, Vector3) {
         // Displacement basado en FBM de Simplex para turbulencias en la superficie
-        let noise = fbm_simplex(position.x * self.freq, position.y * self.freq, position.z * self.freq + uniforms.time * self.speed, self.octaves);
+        let noise = fbm_animated(position.x * self.freq, position.y * self.freq, position.z * self.freq, uniforms.time, self.speed, self.loop_period, self.octaves);
 
         // Centrar alrededor de 0
         let centered = (noise - 0.5) * 2.0;
@@ -503,46 +810,137 @@ impl PlanetShader for StarShader {
         (deformed, normal)
     }
 
+    fn displaces_geometry(&self) -> bool {
+        true
+    }
+
     fn fragment_shader(&self, position: Vector3, _normal: Vector3, _uv: (f32, f32), uniforms: &ShaderUniforms) -> ShaderColor {
         // Usamos coordinates del espacio 3D para ruido (menos costuras)
         let p = position.normalize();
 
         // Mapear ruido FBM a intensidad base usando parametros del shader
-        let n = fbm_simplex(p.x * (self.freq * 0.6), p.y * (self.freq * 0.6), p.z * (self.freq * 0.6) + uniforms.time * (self.speed * 0.7), self.octaves);
+        let n = fbm_animated(p.x * (self.freq * 0.6), p.y * (self.freq * 0.6), p.z * (self.freq * 0.6), uniforms.time, self.speed * 0.7, self.loop_period, self.octaves);
 
         // Pulso ciclico para que la animacion sea repetible y ciclica
-        let pulse = ((uniforms.time * 0.6).sin() * 0.5) + 0.5; // [0,1]
+        let pulse = (looped_phase(uniforms.time, STAR_PULSE_SPEED, self.loop_period).sin() * 0.5) + 0.5; // [0,1]
 
         // Intensidad combinada: ruido + pulso
         let intensity = (n * 0.75 + pulse * 0.25).clamp(0.0, 1.0);
 
-        // Emision variable: picos donde el ruido es alto
-        // Base emission ALTA para evitar áreas oscuras/negras
-        let base_emission = 0.95; // Emisión mínima muy alta (sin negro)
-        let emission = base_emission + smoothstep(0.3, 0.9, intensity) * (0.5 + intensity * 0.8);
+        let emission = star_emission(intensity);
+        let final_color = star_color(intensity);
 
-        // Gradiente de color: negro -> rojo oscuro -> naranja -> rojo brillante
-        let color_core = ShaderColor::from_rgb(10, 5, 0);       // Negro/marrón muy oscuro (núcleo)
-        let color_mid = ShaderColor::from_rgb(180, 40, 0);      // Rojo oscuro anaranjado (medio)
-        let color_hot = ShaderColor::from_rgb(255, 100, 0);     // Naranja rojizo brillante (caliente)
-        let color_peak = ShaderColor::from_rgb(255, 150, 50);   // Naranja brillante (picos)
-
-        // Mix en múltiples etapas para gradiente suave
-        let t1 = smoothstep(0.0, 0.35, intensity);
-        let t2 = smoothstep(0.35, 0.65, intensity);
-        let t3 = smoothstep(0.65, 1.0, intensity);
-
-        let c1 = mix_color(color_core, color_mid, t1);
-        let c2 = mix_color(c1, color_hot, t2);
-        let final_color = mix_color(c2, color_peak, t3);
-
-        // Aplicar emision al color (la estrella emite luz propia)
+        // Aplicar emision al color (la estrella emite luz propia, en HDR puede pasar de 1)
         ShaderColor::new(
-            (final_color.r * emission).clamp(0.0, 1.0),
-            (final_color.g * emission).clamp(0.0, 1.0),
-            (final_color.b * emission).clamp(0.0, 1.0),
+            final_color.r * emission,
+            final_color.g * emission,
+            final_color.b * emission,
             1.0,
         )
     }
 }
 
+impl StarShader {
+    // Frecuencia del pulso con el loop actual: con loop_period se redondea a
+    // ciclos enteros y puede alejarse de STAR_PULSE_SPEED
+    pub fn pulse_speed(&self) -> f32 {
+        looped_speed(STAR_PULSE_SPEED, self.loop_period)
+    }
+
+    // Luz puntual que emite la estrella en `position` en el instante `time`.
+    // Usa la intensidad media de la superficie (ruido FBM ~0.5) con el pulso
+    // actual, así el brillo de los planetas late junto con la estrella
+    pub fn light(&self, position: Vector3, time: f32) -> Light {
+        let pulse = (looped_phase(time, STAR_PULSE_SPEED, self.loop_period).sin() * 0.5) + 0.5;
+        let intensity = 0.5 * 0.75 + pulse * 0.25;
+
+        // Color de la superficie aclarado hacia blanco: la luz total de la
+        // estrella es menos saturada que su gradiente visible
+        let white = ShaderColor::new(1.0, 1.0, 1.0, 1.0);
+        let color = mix_color(star_color(intensity), white, 0.6);
+
+        // Normalizada para que el brillo medio del pulso sea ~1
+        Light::point(position, color, star_emission(intensity) / star_emission(0.5))
+    }
+}
+
+// Emision variable: picos donde la intensidad es alta
+fn star_emission(intensity: f32) -> f32 {
+    // Base emission ALTA para evitar áreas oscuras/negras
+    let base_emission = 0.95; // Emisión mínima muy alta (sin negro)
+    base_emission + smoothstep(0.3, 0.9, intensity) * (0.5 + intensity * 0.8)
+}
+
+// Gradiente de color: negro -> rojo oscuro -> naranja -> rojo brillante
+fn star_color(intensity: f32) -> ShaderColor {
+    let color_core = ShaderColor::from_rgb(10, 5, 0);       // Negro/marrón muy oscuro (núcleo)
+    let color_mid = ShaderColor::from_rgb(180, 40, 0);      // Rojo oscuro anaranjado (medio)
+    let color_hot = ShaderColor::from_rgb(255, 100, 0);     // Naranja rojizo brillante (caliente)
+    let color_peak = ShaderColor::from_rgb(255, 150, 50);   // Naranja brillante (picos)
+
+    // Mix en múltiples etapas para gradiente suave
+    let t1 = smoothstep(0.0, 0.35, intensity);
+    let t2 = smoothstep(0.35, 0.65, intensity);
+    let t3 = smoothstep(0.65, 1.0, intensity);
+
+    let c1 = mix_color(color_core, color_mid, t1);
+    let c2 = mix_color(c1, color_hot, t2);
+    mix_color(c2, color_peak, t3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Periodo y tiempos exactos en f32: t + PERIOD y rem_euclid no redondean
+    const PERIOD: f32 = 8.0;
+    const TIMES: [f32; 4] = [0.0, 0.25, 3.5, 7.75];
+
+    fn uniforms(time: f32) -> ShaderUniforms {
+        let light = Light::directional(Vector3::new(1.0, 1.0, 1.0).normalize(), ShaderColor::new(1.0, 1.0, 1.0, 1.0), 1.0);
+        ShaderUniforms::new(time, vec![light], Vector3::new(0.0, 0.0, 5.0), Matrix4::identity())
+    }
+
+    #[test]
+    fn fbm_animated_repeats_every_period() {
+        for &t in &TIMES {
+            for (x, y, z) in [(0.3, -1.2, 0.7), (2.5, 0.1, -3.3)] {
+                let a = fbm_animated(x, y, z, t, 0.35, PERIOD, 6);
+                assert_eq!(a, fbm_animated(x, y, z, t + PERIOD, 0.35, PERIOD, 6));
+                assert_eq!(a, fbm_animated(x, y, z, t + 3.0 * PERIOD, 0.35, PERIOD, 6));
+            }
+        }
+        // Con el periodo por defecto t + period redondea: igual salvo error de f32
+        let (a, b) = (fbm_animated(0.3, -1.2, 0.7, 1.3, 0.35, DEFAULT_LOOP_PERIOD, 6), fbm_animated(0.3, -1.2, 0.7, 1.3 + DEFAULT_LOOP_PERIOD, 0.35, DEFAULT_LOOP_PERIOD, 6));
+        assert!((a - b).abs() < 1e-4);
+        // Sin loop el ruido sigue avanzando
+        assert_ne!(fbm_animated(0.3, -1.2, 0.7, 0.25, 0.35, 0.0, 6), fbm_animated(0.3, -1.2, 0.7, 0.25 + PERIOD, 0.35, 0.0, 6));
+    }
+
+    #[test]
+    fn star_shader_repeats_every_period() {
+        let mut star = StarShader::default();
+        star.set_loop_period(PERIOD);
+        let (position, normal) = (Vector3::new(0.6, 0.0, 0.8), Vector3::new(0.6, 0.0, 0.8));
+        for &t in &TIMES {
+            let (now, later) = (uniforms(t), uniforms(t + PERIOD));
+            let (a, b) = (star.vertex_shader(position, normal, (0.2, 0.4), &now).0, star.vertex_shader(position, normal, (0.2, 0.4), &later).0);
+            assert_eq!((a.x, a.y, a.z), (b.x, b.y, b.z));
+            let (a, b) = (star.fragment_shader(position, normal, (0.2, 0.4), &now), star.fragment_shader(position, normal, (0.2, 0.4), &later));
+            assert_eq!((a.r, a.g, a.b, a.a), (b.r, b.g, b.b, b.a));
+            let (a, b) = (star.light(Vector3::zero(), t).color, star.light(Vector3::zero(), t + PERIOD).color);
+            assert_eq!((a.r, a.g, a.b), (b.r, b.g, b.b));
+        }
+    }
+
+    #[test]
+    fn pulse_speed_reports_rounding() {
+        let mut star = StarShader::default();
+        assert_eq!(star.pulse_speed(), STAR_PULSE_SPEED);
+        star.set_loop_period(DEFAULT_LOOP_PERIOD);
+        assert!((star.pulse_speed() - STAR_PULSE_SPEED).abs() < 1e-6);
+        // 5 s: 0.48 ciclos se redondean a uno, casi el doble de rápido
+        star.set_loop_period(5.0);
+        assert!((star.pulse_speed() - std::f32::consts::TAU / 5.0).abs() < 1e-6);
+    }
+}
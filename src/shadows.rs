@@ -0,0 +1,180 @@
+use crate::rasterizer::Framebuffer;
+use crate::renderer::{render_depth, to_screen_coords, transform_vertex};
+use crate::shaders::{PlanetShader, ShaderUniforms};
+use crate::vector::Vector3;
+use nalgebra::{Matrix4, Point3};
+
+// Planos de la proyección de cada cara (unidades de mundo)
+const SHADOW_NEAR: f32 = 0.05;
+const SHADOW_FAR: f32 = 50.0;
+
+// Sesgo constante contra el acné de sombras, en unidades de mundo
+const DEPTH_BIAS: f32 = 0.01;
+
+// Shadow map omnidireccional (cubo de 6 caras) para una luz puntual: cada cara
+// es un Framebuffer de solo profundidad visto desde la luz con FOV de 90°
+pub struct ShadowMap {
+    // Índice de la luz en `ShaderUniforms::lights` a la que pertenece
+    pub light_index: usize,
+    light_position: Vector3,
+    size: usize,
+    faces: Vec<Framebuffer>,
+    view_projection: [Matrix4<f32>; 6],
+}
+
+impl ShadowMap {
+    pub fn new(light_index: usize, light_position: Vector3, size: usize) -> Self {
+        // Orden de caras +X, -X, +Y, -Y, +Z, -Z (eje dominante al consultar)
+        let axes = [
+            (nalgebra::Vector3::x(), -nalgebra::Vector3::y()),
+            (-nalgebra::Vector3::x(), -nalgebra::Vector3::y()),
+            (nalgebra::Vector3::y(), nalgebra::Vector3::z()),
+            (-nalgebra::Vector3::y(), -nalgebra::Vector3::z()),
+            (nalgebra::Vector3::z(), -nalgebra::Vector3::y()),
+            (-nalgebra::Vector3::z(), -nalgebra::Vector3::y()),
+        ];
+        let eye = Point3::new(light_position.x, light_position.y, light_position.z);
+        let projection = Matrix4::new_perspective(1.0, std::f32::consts::FRAC_PI_2, SHADOW_NEAR, SHADOW_FAR);
+        let view_projection = axes.map(|(dir, up)| projection * Matrix4::look_at_rh(&eye, &(eye + dir), &up));
+
+        ShadowMap {
+            light_index,
+            light_position,
+            size,
+            faces: (0..6).map(|_| Framebuffer::new_depth_only(size, size)).collect(),
+            view_projection,
+        }
+    }
+
+    // Dibujar un cuerpo que proyecta sombra en las 6 caras
+    pub fn render_caster(
+        &mut self,
+        mesh: &tobj::Mesh,
+        model: &Matrix4<f32>,
+        shader: &dyn PlanetShader,
+        uniforms: &ShaderUniforms,
+    ) {
+        for (face, view_projection) in self.faces.iter_mut().zip(&self.view_projection) {
+            render_depth(face, mesh, &(view_projection * model), shader, uniforms);
+        }
+    }
+
+    // Fracción de luz que llega a `position` (mundo): 0 = en sombra, 1 = iluminado.
+    // PCF 3x3 con comparaciones interpoladas bilinealmente (borde suave) sobre
+    // la cara del cubo que contiene la dirección luz -> punto
+    pub fn visibility(&self, position: Vector3, normal: Vector3) -> f32 {
+        let to_point = position - self.light_position;
+        let distance = to_point.length();
+
+        // Normal offset: separar el punto de la superficie ~1.5 texels (un texel
+        // mide 2*d/size a distancia d con FOV de 90°)
+        let texel = 2.0 * distance / self.size as f32;
+        let p = position + normal * (1.5 * texel);
+        let d = p - self.light_position;
+
+        let face = cube_face(d);
+        let clip = transform_vertex(&p, &self.view_projection[face]);
+        if clip.3 <= SHADOW_NEAR {
+            return 1.0;
+        }
+        // w = distancia a lo largo del eje de la cara, igual que la profundidad linealizada
+        let (sx, sy, _) = to_screen_coords(&clip, self.size as f32, self.size as f32);
+        let receiver = clip.3 - DEPTH_BIAS;
+
+        // Los 9 filtros bilineales de un kernel 3x3 cubren 4x4 texels; cada fila
+        // y columna pesa [1 - t, 1, 1, t] (suma 3 por eje)
+        let (fx, fy) = (sx - 0.5, sy - 0.5);
+        let (bx, by) = (fx.floor() as i32 - 1, fy.floor() as i32 - 1);
+        let (tx, ty) = (fx - fx.floor(), fy - fy.floor());
+        let wx = [1.0 - tx, 1.0, 1.0, tx];
+        let wy = [1.0 - ty, 1.0, 1.0, ty];
+
+        let depth = &self.faces[face].depth_buffer;
+        let max = self.size as i32 - 1;
+        let mut lit = 0.0;
+        for (j, wy) in wy.iter().enumerate() {
+            let y = (by + j as i32).clamp(0, max) as usize;
+            for (i, wx) in wx.iter().enumerate() {
+                let x = (bx + i as i32).clamp(0, max) as usize;
+                if receiver <= linearize(depth[y * self.size + x]) {
+                    lit += wx * wy;
+                }
+            }
+        }
+        lit / 9.0
+    }
+}
+
+// Cara del cubo según el eje dominante de la dirección
+fn cube_face(d: Vector3) -> usize {
+    let (ax, ay, az) = (d.x.abs(), d.y.abs(), d.z.abs());
+    if ax >= ay && ax >= az {
+        if d.x >= 0.0 { 0 } else { 1 }
+    } else if ay >= az {
+        if d.y >= 0.0 { 2 } else { 3 }
+    } else if d.z >= 0.0 {
+        4
+    } else {
+        5
+    }
+}
+
+// Profundidad NDC [-1, 1] -> distancia lineal a lo largo del eje de la cara
+fn linearize(ndc_z: f32) -> f32 {
+    if ndc_z.is_infinite() {
+        return f32::INFINITY;
+    }
+    2.0 * SHADOW_NEAR * SHADOW_FAR / (SHADOW_FAR + SHADOW_NEAR - ndc_z * (SHADOW_FAR - SHADOW_NEAR))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lights::Light;
+    use crate::shaders::{IcePlanetShader, ShaderColor};
+    use crate::sphere::{generate_sphere, SphereMesh};
+
+    // Luz en el origen y una esfera de radio 0.5 en x = 3 que hace sombra
+    fn shadow_map() -> ShadowMap {
+        let light = Light::point(Vector3::zero(), ShaderColor::new(1.0, 1.0, 1.0, 1.0), 1.0);
+        let uniforms = ShaderUniforms::new(0.0, vec![light], Vector3::new(0.0, 0.0, 10.0), Matrix4::identity());
+        let mut map = ShadowMap::new(0, Vector3::zero(), 128);
+        let sphere = generate_sphere(SphereMesh::Icosphere { subdivisions: 3 }, 0.5);
+        let model = Matrix4::new_translation(&nalgebra::Vector3::new(3.0, 0.0, 0.0));
+        map.render_caster(&sphere, &model, &IcePlanetShader, &uniforms);
+        map
+    }
+
+    #[test]
+    fn occluder_between_light_and_point_casts_shadow() {
+        let map = shadow_map();
+        // Detrás de la esfera, de frente a la luz
+        let hidden = map.visibility(Vector3::new(6.0, 0.0, 0.0), Vector3::new(-1.0, 0.0, 0.0));
+        assert!(hidden < 0.01, "visibilidad {}", hidden);
+        let hidden = map.visibility(Vector3::new(9.0, 0.3, -0.2), Vector3::new(-1.0, 0.0, 0.0));
+        assert!(hidden < 0.01, "visibilidad {}", hidden);
+    }
+
+    #[test]
+    fn unoccluded_points_are_lit() {
+        let map = shadow_map();
+        for (position, normal) in [
+            (Vector3::new(0.0, 6.0, 0.0), Vector3::new(0.0, -1.0, 0.0)),
+            (Vector3::new(-6.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0)),
+            (Vector3::new(6.0, 0.0, 3.0), Vector3::new(-1.0, 0.0, 0.0)),
+            // La cara iluminada del propio oclusor: sin acné de sombras
+            (Vector3::new(2.5, 0.0, 0.0), Vector3::new(-1.0, 0.0, 0.0)),
+        ] {
+            let lit = map.visibility(position, normal);
+            assert!(lit > 0.99, "visibilidad {} en {:?}", lit, position);
+        }
+    }
+
+    #[test]
+    fn cube_face_picks_the_dominant_axis() {
+        let faces = [(1.0, 0.2, -0.5), (-2.0, 1.0, 1.0), (0.1, 3.0, 0.0), (0.5, -1.0, 0.2), (0.0, 0.3, 0.9), (0.4, 0.4, -0.5)];
+        for (expected, (x, y, z)) in faces.into_iter().enumerate() {
+            assert_eq!(cube_face(Vector3::new(x, y, z)), expected);
+        }
+    }
+}
@@ -1,11 +1,66 @@
-// Vertex struct no usado directamente, mantenido por compatibilidad
-#[allow(dead_code)]
-#[derive(Copy, Clone, Debug)]
-pub struct Vertex {
-    pub position: [f32; 3],
+use crate::vector::Vector3;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// Topología de la esfera procedimental y su resolución
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SphereMesh {
+    // Divisiones en longitud y en latitud; los triángulos se amontonan en los polos
+    Uv { segments: u32, rings: u32 },
+    // Icosaedro subdividido: cada nivel parte cada triángulo en 4, casi uniformes
+    Icosphere { subdivisions: u32 },
+    // Cubo con `resolution` x `resolution` quads por cara, proyectado a la esfera
+    CubeSphere { resolution: u32 },
 }
 
-pub fn load_sphere_or_generate() -> Result<(Vec<tobj::Model>, Vec<tobj::Material>, bool), String> {
+impl Default for SphereMesh {
+    fn default() -> Self {
+        SphereMesh::Uv { segments: 64, rings: 64 }
+    }
+}
+
+impl SphereMesh {
+    // Misma topología con menos triángulos (None si ya es la más simple)
+    pub fn coarser(&self) -> Option<SphereMesh> {
+        match *self {
+            SphereMesh::Uv { segments, rings } if segments > 8 && rings > 4 => {
+                Some(SphereMesh::Uv { segments: segments / 2, rings: rings / 2 })
+            }
+            SphereMesh::Icosphere { subdivisions } if subdivisions > 0 => {
+                Some(SphereMesh::Icosphere { subdivisions: subdivisions - 1 })
+            }
+            SphereMesh::CubeSphere { resolution } if resolution > 1 => {
+                Some(SphereMesh::CubeSphere { resolution: resolution / 2 })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SphereMesh::Uv { .. } => "procedural_uv_sphere",
+            SphereMesh::Icosphere { .. } => "procedural_icosphere",
+            SphereMesh::CubeSphere { .. } => "procedural_cube_sphere",
+        }
+    }
+}
+
+// Modelos, materiales y ruta del OBJ de donde salieron (None = procedimental)
+pub type LoadedSphere = (Vec<tobj::Model>, Vec<tobj::Material>, Option<PathBuf>);
+
+// Materiales del MTL de un OBJ, o por qué no se pudieron leer
+pub type MaterialsResult = Result<Vec<tobj::Material>, String>;
+
+// Con `sphere` = None carga sphere.obj y, si no está, genera la esfera UV por
+// defecto; con una topología elegida la genera directamente. La ruta del OBJ
+// es la base de las rutas de su MTL
+pub fn load_sphere_or_generate(sphere: Option<SphereMesh>) -> Result<LoadedSphere, String> {
+    if let Some(sphere) = sphere {
+        let (models, materials) = generate_sphere_models(sphere, 1.0);
+        log_mesh_info(&models);
+        return Ok((models, materials, None));
+    }
+
     // Intentar varias rutas comunes según el working dir
     let candidates = [
         "sphere.obj",
@@ -16,68 +71,133 @@ pub fn load_sphere_or_generate() -> Result<(Vec<tobj::Model>, Vec<tobj::Material
     ];
 
     for path in &candidates {
-        match tobj::load_obj(
-            path,
-            &tobj::LoadOptions {
-                single_index: true,
-                triangulate: true,
-                ..Default::default()
-            },
-        ) {
-            Ok((models, materials)) => {
-                let materials = materials.unwrap_or_else(|_| Vec::new());
-                println!("Modelo cargado desde: {}", path);
-                log_mesh_info(&models);
-                return Ok((models, materials, false)); // false = no fallback
-            }
-            Err(_) => { /* probar siguiente */ }
+        if let Ok((models, materials)) = load_obj(Path::new(path)) {
+            let materials = materials.unwrap_or_default();
+            eprintln!("Modelo cargado desde: {}", path);
+            log_mesh_info(&models);
+            return Ok((models, materials, Some(PathBuf::from(path))));
         }
     }
 
     // Si no se encontró: generar UV sphere procedimental
     eprintln!("No se encontró sphere.obj en rutas conocidas. Generando procedimental...");
-    let (models, materials) = generate_uv_sphere_models(64, 64, 1.0);
+    let (models, materials) = generate_sphere_models(SphereMesh::default(), 1.0);
     log_mesh_info(&models);
-    Ok((models, materials, true)) // true = usó fallback
+    Ok((models, materials, None))
+}
+
+// Cargar un OBJ triangulado con índice único y normales garantizadas. Los
+// materiales van aparte: un MTL que falta no impide usar la geometría
+pub fn load_obj(path: &Path) -> Result<(Vec<tobj::Model>, MaterialsResult), String> {
+    let options = tobj::LoadOptions { single_index: true, triangulate: true, ..Default::default() };
+    let (mut models, materials) = tobj::load_obj(path, &options).map_err(|e| format!("No se pudo cargar {}: {}", path.display(), e))?;
+    for model in &mut models {
+        ensure_vertex_normals(&mut model.mesh);
+    }
+    Ok((models, materials.map_err(|e| e.to_string())))
 }
 
-fn log_mesh_info(models: &Vec<tobj::Model>) {
-    println!("Número de meshes: {}", models.len());
+fn log_mesh_info(models: &[tobj::Model]) {
+    eprintln!("Número de meshes: {}", models.len());
     for (i, model) in models.iter().enumerate() {
         let mesh = &model.mesh;
-        println!("\n--- Mesh {}: {} ---", i, model.name);
-        println!("  Vértices: {}", mesh.positions.len() / 3);
-        println!("  Índices: {}", mesh.indices.len());
-        println!("  Triángulos: {}", mesh.indices.len() / 3);
+        eprintln!("\n--- Mesh {}: {} ---", i, model.name);
+        eprintln!("  Vértices: {}", mesh.positions.len() / 3);
+        eprintln!("  Índices: {}", mesh.indices.len());
+        eprintln!("  Triángulos: {}", mesh.indices.len() / 3);
+        eprintln!("  Normales: {}", mesh.normals.len() / 3);
 
-        println!("\n  Primeros 5 vértices:");
+        eprintln!("\n  Primeros 5 vértices:");
         for j in 0..5.min(mesh.positions.len() / 3) {
             let x = mesh.positions[j * 3];
             let y = mesh.positions[j * 3 + 1];
             let z = mesh.positions[j * 3 + 2];
-            println!("    Vértice {}: ({:.3}, {:.3}, {:.3})", j, x, y, z);
+            eprintln!("    Vértice {}: ({:.3}, {:.3}, {:.3})", j, x, y, z);
         }
     }
 }
 
-// Genera una esfera UV y la empaqueta en tobj::Model para no tocar el resto del pipeline
-fn generate_uv_sphere_models(segments: u32, rings: u32, radius: f32) -> (Vec<tobj::Model>, Vec<tobj::Material>) {
-    let (positions, indices) = generate_uv_sphere_data(segments, rings, radius);
+// Garantiza una normal por vértice: usa las del OBJ si vienen completas,
+// si no las calcula a partir de la geometría
+pub fn ensure_vertex_normals(mesh: &mut tobj::Mesh) {
+    if !mesh.normals.is_empty() && mesh.normals.len() == mesh.positions.len() {
+        return;
+    }
+    mesh.normals = compute_vertex_normals(&mesh.positions, &mesh.indices);
+}
 
-    let mesh = tobj::Mesh {
-        positions,
-        indices,
-        ..Default::default()
-    };
+// Normales suaves promediando las normales de cara ponderadas por el ángulo de
+// cada esquina. Los vértices duplicados en la misma posición (costura de la
+// esfera UV, polos) se sueldan para que la normal no tenga discontinuidades.
+pub fn compute_vertex_normals(positions: &[f32], indices: &[u32]) -> Vec<f32> {
+    let vertex_count = positions.len() / 3;
+    let position = |i: usize| Vector3::new(positions[i * 3], positions[i * 3 + 1], positions[i * 3 + 2]);
+
+    // Índice soldado por posición exacta
+    let mut welded: HashMap<[u32; 3], usize> = HashMap::new();
+    let slots: Vec<usize> = (0..vertex_count)
+        .map(|i| {
+            // + 0.0 convierte -0.0 en 0.0 para que suelden igual
+            let key = [
+                (positions[i * 3] + 0.0).to_bits(),
+                (positions[i * 3 + 1] + 0.0).to_bits(),
+                (positions[i * 3 + 2] + 0.0).to_bits(),
+            ];
+            let next = welded.len();
+            *welded.entry(key).or_insert(next)
+        })
+        .collect();
+
+    let mut accum = vec![Vector3::new(0.0, 0.0, 0.0); welded.len()];
+    for tri in indices.chunks_exact(3) {
+        let idx = [tri[0] as usize, tri[1] as usize, tri[2] as usize];
+        let p = idx.map(position);
+        let face_normal = (p[1] - p[0]).cross(&(p[2] - p[0])).normalize();
+
+        for corner in 0..3 {
+            let e1 = (p[(corner + 1) % 3] - p[corner]).normalize();
+            let e2 = (p[(corner + 2) % 3] - p[corner]).normalize();
+            let angle = e1.dot(&e2).clamp(-1.0, 1.0).acos();
+            let slot = slots[idx[corner]];
+            accum[slot] = accum[slot] + face_normal * angle;
+        }
+    }
 
+    let mut normals = Vec::with_capacity(positions.len());
+    for &slot in &slots {
+        let n = accum[slot].normalize();
+        normals.extend_from_slice(&[n.x, n.y, n.z]);
+    }
+    normals
+}
+
+// Genera la esfera pedida (con normales) y la empaqueta en tobj::Model para
+// no tocar el resto del pipeline
+fn generate_sphere_models(sphere: SphereMesh, radius: f32) -> (Vec<tobj::Model>, Vec<tobj::Material>) {
     let model = tobj::Model {
-        name: "procedural_uv_sphere".to_string(),
-        mesh,
+        name: sphere.name().to_string(),
+        mesh: generate_sphere(sphere, radius),
     };
 
     (vec![model], Vec::new())
 }
 
+pub fn generate_sphere(sphere: SphereMesh, radius: f32) -> tobj::Mesh {
+    let (positions, indices) = match sphere {
+        SphereMesh::Uv { segments, rings } => generate_uv_sphere_data(segments, rings, radius),
+        SphereMesh::Icosphere { subdivisions } => generate_icosphere_data(subdivisions, radius),
+        SphereMesh::CubeSphere { resolution } => generate_cube_sphere_data(resolution, radius),
+    };
+
+    let mut mesh = tobj::Mesh {
+        positions,
+        indices,
+        ..Default::default()
+    };
+    ensure_vertex_normals(&mut mesh);
+    mesh
+}
+
 // Devuelve posiciones & indices de una esfera UV triangulada
 fn generate_uv_sphere_data(segments: u32, rings: u32, radius: f32) -> (Vec<f32>, Vec<u32>) {
     let seg = segments.max(3);
@@ -94,9 +214,12 @@ fn generate_uv_sphere_data(segments: u32, rings: u32, radius: f32) -> (Vec<f32>,
             let u = x as f32 / seg as f32;
             let phi = u * std::f32::consts::TAU; // 0..2PI
 
-            let px = radius * phi.cos() * theta.sin();
+            // sin(PI) en f32 es apenas negativo: sin recortarlo el polo sur queda
+            // como un anillo diminuto espejado, con los triángulos invertidos
+            let sin_theta = theta.sin().max(0.0);
+            let px = radius * phi.cos() * sin_theta;
             let py = radius * theta.cos();
-            let pz = radius * phi.sin() * theta.sin();
+            let pz = radius * phi.sin() * sin_theta;
 
             positions.extend_from_slice(&[px, py, pz]);
         }
@@ -110,9 +233,106 @@ fn generate_uv_sphere_data(segments: u32, rings: u32, radius: f32) -> (Vec<f32>,
             let i2 = i0 + stride;
             let i3 = i2 + 1;
 
-            // Dos triángulos por quad
-            indices.extend_from_slice(&[i0 as u32, i2 as u32, i1 as u32]);
-            indices.extend_from_slice(&[i1 as u32, i2 as u32, i3 as u32]);
+            // Dos triángulos por quad, antihorario visto desde afuera (como el OBJ)
+            indices.extend_from_slice(&[i0, i1, i2]);
+            indices.extend_from_slice(&[i1, i3, i2]);
+        }
+    }
+
+    (positions, indices)
+}
+
+// Icosaedro subdividido `subdivisions` veces (máximo 7, ~330k triángulos) con
+// los puntos medios llevados a la esfera. Sin polos ni costuras: todos los
+// triángulos tienen casi el mismo tamaño
+fn generate_icosphere_data(subdivisions: u32, radius: f32) -> (Vec<f32>, Vec<u32>) {
+    let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+    let mut vertices: Vec<Vector3> = [
+        (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+        (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+        (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+    ]
+    .iter()
+    .map(|&(x, y, z)| Vector3::new(x, y, z).normalize())
+    .collect();
+
+    // Caras antihorarias vistas desde afuera
+    let mut faces: Vec<[u32; 3]> = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions.min(7) {
+        // Punto medio de cada arista, compartido por las dos caras que la usan
+        let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut midpoint = |a: u32, b: u32, vertices: &mut Vec<Vector3>| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                vertices.push(((vertices[a as usize] + vertices[b as usize]) * 0.5).normalize());
+                (vertices.len() - 1) as u32
+            })
+        };
+
+        let mut next = Vec::with_capacity(faces.len() * 4);
+        for &[a, b, c] in &faces {
+            let ab = midpoint(a, b, &mut vertices);
+            let bc = midpoint(b, c, &mut vertices);
+            let ca = midpoint(c, a, &mut vertices);
+            next.extend_from_slice(&[[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]);
+        }
+        faces = next;
+    }
+
+    let positions = vertices.iter().flat_map(|v| [v.x * radius, v.y * radius, v.z * radius]).collect();
+    (positions, faces.concat())
+}
+
+// Caras del cubo de lado 2 como (normal, eje u, eje v), con u × v = normal:
+// en cada cara (p00, p10, p01) queda antihorario visto desde afuera
+pub fn cube_faces() -> [(Vector3, Vector3, Vector3); 6] {
+    let x = Vector3::new(1.0, 0.0, 0.0);
+    let y = Vector3::new(0.0, 1.0, 0.0);
+    let z = Vector3::new(0.0, 0.0, 1.0);
+    [
+        (x, y, z),
+        (x * -1.0, z, y),
+        (y, z, x),
+        (y * -1.0, x, z),
+        (z, x, y),
+        (z * -1.0, y, x),
+    ]
+}
+
+// Cubo de lado 2 con `resolution` x `resolution` quads por cara, cada vértice
+// normalizado a la esfera. Las aristas entre caras se generan con las mismas
+// coordenadas exactas en ambas, así no quedan grietas entre caras
+fn generate_cube_sphere_data(resolution: u32, radius: f32) -> (Vec<f32>, Vec<u32>) {
+    let res = resolution.max(1);
+    let stride = res + 1;
+    let mut positions: Vec<f32> = Vec::with_capacity((6 * stride * stride * 3) as usize);
+    let mut indices: Vec<u32> = Vec::with_capacity((6 * res * res * 6) as usize);
+
+    for (normal, u_axis, v_axis) in cube_faces() {
+        let base = (positions.len() / 3) as u32;
+        for j in 0..=res {
+            let v = -1.0 + 2.0 * j as f32 / res as f32;
+            for i in 0..=res {
+                let u = -1.0 + 2.0 * i as f32 / res as f32;
+                let p = (normal + u_axis * u + v_axis * v).normalize() * radius;
+                positions.extend_from_slice(&[p.x, p.y, p.z]);
+            }
+        }
+
+        for j in 0..res {
+            for i in 0..res {
+                let i0 = base + j * stride + i;
+                let i1 = i0 + 1;
+                let i2 = i0 + stride;
+                let i3 = i2 + 1;
+                indices.extend_from_slice(&[i0, i1, i2]);
+                indices.extend_from_slice(&[i1, i3, i2]);
+            }
         }
     }
 
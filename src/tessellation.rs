@@ -0,0 +1,337 @@
+use crate::sphere::cube_faces;
+use crate::vector::Vector3;
+use std::collections::HashSet;
+
+// Parche del quadtree de una cara de la esfera-cubo: en el nivel `level` la
+// cara se parte en 2^level x 2^level parches y este es el (x, y)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Patch {
+    face: usize,
+    level: u32,
+    x: u32,
+    y: u32,
+}
+
+impl Patch {
+    fn children(&self) -> [Patch; 4] {
+        let (level, x, y) = (self.level + 1, self.x * 2, self.y * 2);
+        [
+            Patch { face: self.face, level, x, y },
+            Patch { face: self.face, level, x: x + 1, y },
+            Patch { face: self.face, level, x, y: y + 1 },
+            Patch { face: self.face, level, x: x + 1, y: y + 1 },
+        ]
+    }
+
+    // Lado del parche en coordenadas de cara ([-1, 1])
+    fn size(&self) -> f32 {
+        2.0 / (1u32 << self.level) as f32
+    }
+
+    // Esquina (u, v) mínima en coordenadas de cara
+    fn origin(&self) -> (f32, f32) {
+        (-1.0 + self.x as f32 * self.size(), -1.0 + self.y as f32 * self.size())
+    }
+}
+
+// Lados de un parche en orden antihorario visto desde afuera
+#[derive(Clone, Copy)]
+enum Side {
+    Bottom,
+    Right,
+    Top,
+    Left,
+}
+
+const SIDES: [Side; 4] = [Side::Bottom, Side::Right, Side::Top, Side::Left];
+
+// Teselado adaptativo de una esfera según la cámara (chunked LOD): un quadtree
+// por cara de la esfera-cubo que se refina donde las aristas se verían largas
+// en pantalla, con más detalle en la silueta. Cada hoja es una grilla de
+// `patch_grid` x `patch_grid` quads; el árbol se balancea (vecinos con a lo
+// sumo un nivel de diferencia) y el lado de una hoja junto a una vecina más
+// gruesa usa solo los vértices pares, los mismos que tiene la vecina, así el
+// desplazamiento del vertex shader no abre grietas entre parches
+#[derive(Debug, Clone, Copy)]
+pub struct AdaptiveSphere {
+    pub max_level: u32,
+    // Quads por lado de cada hoja (par, para poder coser contra la vecina)
+    pub patch_grid: u32,
+    // Largo (px) que puede alcanzar una arista antes de refinar
+    pub max_edge_pixels: f32,
+    // Factor sobre max_edge_pixels cerca de la silueta (< 1 = más detalle)
+    pub silhouette_factor: f32,
+}
+
+impl Default for AdaptiveSphere {
+    fn default() -> Self {
+        AdaptiveSphere {
+            max_level: 7,
+            patch_grid: 8,
+            max_edge_pixels: 6.0,
+            silhouette_factor: 0.35,
+        }
+    }
+}
+
+impl AdaptiveSphere {
+    // Mesh de una esfera de radio `radius` centrada en el origen vista desde
+    // `camera` (en el mismo espacio que el mesh); `focal` = distancia focal en
+    // píxeles (alto / 2 / tan(fov / 2))
+    pub fn mesh(&self, camera: Vector3, radius: f32, focal: f32) -> tobj::Mesh {
+        let grid = (self.patch_grid.max(2) + 1) & !1;
+        let max_level = self.max_level.min(12);
+        let faces = cube_faces();
+        let leaves = self.leaves(camera, radius, focal, grid, max_level);
+
+        // Orden fijo para que el mesh no dependa del orden del HashSet
+        let mut sorted: Vec<Patch> = leaves.iter().copied().collect();
+        sorted.sort();
+
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut indices = Vec::new();
+        let lattice = grid << max_level;
+        for patch in &sorted {
+            let (normal, u_axis, v_axis) = faces[patch.face];
+            let base = (positions.len() / 3) as u32;
+            // Vértices en una grilla entera común a todas las caras: el mismo
+            // punto de un borde sale con los mismos bits en ambos parches
+            let step = 1u32 << (max_level - patch.level);
+            for j in 0..=grid {
+                let v = -1.0 + 2.0 * ((patch.y * grid + j) * step) as f32 / lattice as f32;
+                for i in 0..=grid {
+                    let u = -1.0 + 2.0 * ((patch.x * grid + i) * step) as f32 / lattice as f32;
+                    let n = (normal + u_axis * u + v_axis * v).normalize();
+                    positions.extend_from_slice(&[n.x * radius, n.y * radius, n.z * radius]);
+                    normals.extend_from_slice(&[n.x, n.y, n.z]);
+                }
+            }
+
+            let index = |i: u32, j: u32| base + j * (grid + 1) + i;
+            // Interior: grilla regular sin la fila de borde
+            for j in 1..grid - 1 {
+                for i in 1..grid - 1 {
+                    let (a, b, c, d) = (index(i, j), index(i + 1, j), index(i, j + 1), index(i + 1, j + 1));
+                    indices.extend_from_slice(&[a, b, c, b, d, c]);
+                }
+            }
+            // Borde: una tira por lado entre el contorno del parche y el de la grilla interior
+            for side in SIDES {
+                let coarse = neighbor_leaf(&leaves, patch, side, max_level).level < patch.level;
+                let stride = if coarse { 2 } else { 1 };
+                let point = |k: u32, depth: u32| match side {
+                    Side::Bottom => index(k, depth),
+                    Side::Right => index(grid - depth, k),
+                    Side::Top => index(grid - k, grid - depth),
+                    Side::Left => index(depth, grid - k),
+                };
+                let outer: Vec<(u32, u32)> = (0..=grid).step_by(stride).map(|k| (point(k, 0), k)).collect();
+                let inner: Vec<(u32, u32)> = (1..grid).map(|k| (point(k, 1), k)).collect();
+                zip_strip(&outer, &inner, &mut indices);
+            }
+        }
+
+        tobj::Mesh { positions, normals, indices, ..Default::default() }
+    }
+
+    // Hojas del quadtree refinado y balanceado
+    fn leaves(&self, camera: Vector3, radius: f32, focal: f32, grid: u32, max_level: u32) -> HashSet<Patch> {
+        // Refinar desde las 6 caras
+        let mut leaves: HashSet<Patch> = HashSet::new();
+        let mut pending: Vec<Patch> = (0..6).map(|face| Patch { face, level: 0, x: 0, y: 0 }).collect();
+        while let Some(patch) = pending.pop() {
+            if patch.level < max_level && self.should_split(&patch, camera, radius, focal, grid) {
+                pending.extend_from_slice(&patch.children());
+            } else {
+                leaves.insert(patch);
+            }
+        }
+
+        // Balancear: partir toda hoja con más de un nivel de diferencia con una vecina
+        loop {
+            let mut splits: Vec<Patch> = Vec::new();
+            for patch in &leaves {
+                for side in SIDES {
+                    let neighbor = neighbor_leaf(&leaves, patch, side, max_level);
+                    if neighbor.level + 1 < patch.level {
+                        splits.push(neighbor);
+                    }
+                }
+            }
+            if splits.is_empty() {
+                break;
+            }
+            for patch in splits {
+                if leaves.remove(&patch) {
+                    leaves.extend(patch.children());
+                }
+            }
+        }
+        leaves
+    }
+
+    fn should_split(&self, patch: &Patch, camera: Vector3, radius: f32, focal: f32, grid: u32) -> bool {
+        let (normal, u_axis, v_axis) = cube_faces()[patch.face];
+        let (u0, v0) = patch.origin();
+        let half = patch.size() * 0.5;
+        let center = (normal + u_axis * (u0 + half) + v_axis * (v0 + half)).normalize();
+
+        // Ángulo (rad) que abarca el parche: una cara entera (lado 2) son 90°
+        let angle = patch.size() * std::f32::consts::FRAC_PI_4;
+        let to_camera = camera - center * radius;
+        let distance = to_camera.length();
+        // Distancia aproximada al punto más cercano del parche
+        let nearest = (distance - radius * angle * 0.71).max(radius * 1e-3);
+        let edge_pixels = radius * angle / grid as f32 * focal / nearest;
+
+        // cos entre la normal y la dirección a la cámara: 0 en la silueta
+        let facing = center.dot(&to_camera) / distance.max(1e-6);
+        if facing < -angle {
+            // Del lado de atrás, lejos de la silueta: no se ve
+            return false;
+        }
+        let target = if facing.abs() < angle { self.max_edge_pixels * self.silhouette_factor } else { self.max_edge_pixels };
+        edge_pixels > target
+    }
+}
+
+// Hoja que contiene el punto `p` de la superficie del cubo (puede estar en otra cara)
+fn leaf_at(leaves: &HashSet<Patch>, p: Vector3, max_level: u32) -> Patch {
+    let faces = cube_faces();
+    let face = (0..6).max_by(|&a, &b| p.dot(&faces[a].0).total_cmp(&p.dot(&faces[b].0))).unwrap_or(0);
+    let (normal, u_axis, v_axis) = faces[face];
+    let depth = p.dot(&normal);
+    let (u, v) = (p.dot(&u_axis) / depth, p.dot(&v_axis) / depth);
+    for level in 0..=max_level {
+        let cells = 1u32 << level;
+        let cell = |t: f32| (((t + 1.0) * 0.5 * cells as f32).floor().max(0.0) as u32).min(cells - 1);
+        let patch = Patch { face, level, x: cell(u), y: cell(v) };
+        if leaves.contains(&patch) {
+            return patch;
+        }
+    }
+    Patch { face, level: 0, x: 0, y: 0 }
+}
+
+// Hoja vecina del lado `side`: la que contiene un punto apenas afuera del
+// centro de ese lado
+fn neighbor_leaf(leaves: &HashSet<Patch>, patch: &Patch, side: Side, max_level: u32) -> Patch {
+    let (normal, u_axis, v_axis) = cube_faces()[patch.face];
+    let (u0, v0) = patch.origin();
+    let size = patch.size();
+    // Un cuarto del parche más chico
+    let offset = 0.5 / (1u32 << max_level) as f32;
+    let (u, v) = match side {
+        Side::Bottom => (u0 + size * 0.5, v0 - offset),
+        Side::Right => (u0 + size + offset, v0 + size * 0.5),
+        Side::Top => (u0 + size * 0.5, v0 + size + offset),
+        Side::Left => (u0 - offset, v0 + size * 0.5),
+    };
+    leaf_at(leaves, normal + u_axis * u + v_axis * v, max_level)
+}
+
+// Triangula la tira entre el contorno `outer` y la fila `inner`, ambos
+// (índice, posición a lo largo del lado) y en sentido antihorario del parche
+// (el interior queda a la izquierda). Avanza por la polilínea cuyo próximo
+// punto está menos adelante, así sirve para cualquier cantidad de puntos
+fn zip_strip(outer: &[(u32, u32)], inner: &[(u32, u32)], indices: &mut Vec<u32>) {
+    let (mut i, mut j) = (0, 0);
+    while i + 1 < outer.len() || j + 1 < inner.len() {
+        // Comparar 2k en el contorno contra 2k + 1 en la fila interior: sin
+        // empates, y el contorno avanza primero cuando van parejos
+        let advance_outer = j + 1 == inner.len()
+            || (i + 1 < outer.len() && outer[i + 1].1 * 2 <= inner[j + 1].1 * 2 + 1);
+        if advance_outer {
+            indices.extend_from_slice(&[outer[i].0, outer[i + 1].0, inner[j].0]);
+            i += 1;
+        } else {
+            indices.extend_from_slice(&[outer[i].0, inner[j + 1].0, inner[j].0]);
+            j += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const FOCAL: f32 = 600.0;
+
+    // Cámaras pegadas a la esfera de radio 1: sobre el centro de una cara,
+    // sobre una arista entre dos caras y sobre una esquina entre tres
+    fn cameras() -> [Vector3; 3] {
+        [
+            Vector3::new(0.0, 0.0, 1.3),
+            Vector3::new(1.0, 0.0, 1.0).normalize() * 1.15,
+            Vector3::new(1.0, 1.0, 1.0).normalize() * 1.1,
+        ]
+    }
+
+    fn sphere() -> AdaptiveSphere {
+        AdaptiveSphere { max_level: 5, patch_grid: 4, ..AdaptiveSphere::default() }
+    }
+
+    fn leaves(camera: Vector3) -> HashSet<Patch> {
+        let sphere = sphere();
+        sphere.leaves(camera, 1.0, FOCAL, sphere.patch_grid, sphere.max_level)
+    }
+
+    #[test]
+    fn leaves_are_balanced_and_cover_the_cube() {
+        for camera in cameras() {
+            let leaves = leaves(camera);
+            let area: f32 = leaves.iter().map(|p| p.size() * p.size()).sum();
+            assert!((area - 24.0).abs() < 1e-3, "las hojas cubren {} de 24", area);
+
+            let mut across_faces = false;
+            for patch in &leaves {
+                for side in SIDES {
+                    let neighbor = neighbor_leaf(&leaves, patch, side, sphere().max_level);
+                    assert!(neighbor.level + 1 >= patch.level, "{:?} junto a {:?}", patch, neighbor);
+                    across_faces |= neighbor.face != patch.face && neighbor.level != patch.level;
+                }
+            }
+            // La prueba solo vale si hay saltos de nivel, también entre caras
+            assert!(across_faces, "sin vecinos de distinto nivel entre caras para {:?}", camera);
+        }
+    }
+
+    #[test]
+    fn mesh_is_a_closed_manifold() {
+        for camera in cameras() {
+            let mesh = sphere().mesh(camera, 1.0, FOCAL);
+            // Soldar por bits de la posición: los bordes compartidos salen idénticos
+            let mut welded: HashMap<[u32; 3], u32> = HashMap::new();
+            let ids: Vec<u32> = mesh
+                .positions
+                .chunks_exact(3)
+                .map(|p| {
+                    let next = welded.len() as u32;
+                    *welded.entry([p[0].to_bits(), p[1].to_bits(), p[2].to_bits()]).or_insert(next)
+                })
+                .collect();
+
+            // Cada arista orientada una sola vez y siempre con su opuesta:
+            // cerrado, sin grietas ni T y con el giro consistente
+            let mut edges: HashMap<(u32, u32), usize> = HashMap::new();
+            for tri in mesh.indices.chunks_exact(3) {
+                let [a, b, c] = [ids[tri[0] as usize], ids[tri[1] as usize], ids[tri[2] as usize]];
+                assert!(a != b && b != c && c != a, "triángulo degenerado");
+                for edge in [(a, b), (b, c), (c, a)] {
+                    *edges.entry(edge).or_default() += 1;
+                }
+            }
+            for (&(a, b), &count) in &edges {
+                assert_eq!(count, 1, "arista {}-{} repetida", a, b);
+                assert_eq!(edges.get(&(b, a)), Some(&1), "arista {}-{} sin vecina (grieta) para {:?}", a, b, camera);
+            }
+            // Esfera cerrada: V - E + F = 2 (V sin los vértices impares que el
+            // lado cosido contra una vecina gruesa deja sin usar)
+            let used: HashSet<u32> = mesh.indices.iter().map(|&i| ids[i as usize]).collect();
+            let faces = mesh.indices.len() / 3;
+            assert_eq!(used.len() as i64 - (edges.len() / 2) as i64 + faces as i64, 2);
+        }
+    }
+}
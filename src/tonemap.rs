@@ -0,0 +1,69 @@
+use crate::post::PostEffect;
+use crate::rasterizer::Framebuffer;
+
+// Curva para llevar el color HDR a [0, 1] antes de cuantizar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToneMapper {
+    // Recorte directo (lo que hacía el rasterizador antes del HDR)
+    Clamp,
+    // x / (1 + x): nunca satura, comprime mucho las luces altas
+    Reinhard,
+    // Aproximación de Narkowicz a la curva fílmica ACES
+    Aces,
+}
+
+impl ToneMapper {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ToneMapper::Clamp => "CLAMP",
+            ToneMapper::Reinhard => "REINHARD",
+            ToneMapper::Aces => "ACES",
+        }
+    }
+
+    // Siguiente curva (para alternar con una tecla)
+    pub fn next(&self) -> Self {
+        match self {
+            ToneMapper::Clamp => ToneMapper::Reinhard,
+            ToneMapper::Reinhard => ToneMapper::Aces,
+            ToneMapper::Aces => ToneMapper::Clamp,
+        }
+    }
+
+    fn map(&self, x: f32) -> f32 {
+        match self {
+            ToneMapper::Clamp => x,
+            ToneMapper::Reinhard => x / (1.0 + x),
+            ToneMapper::Aces => (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ToneMapping {
+    pub mapper: ToneMapper,
+    // Multiplicador del color HDR antes de la curva
+    pub exposure: f32,
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        ToneMapping { mapper: ToneMapper::Aces, exposure: 1.0 }
+    }
+}
+
+// Lleva el buffer HDR al rango de pantalla [0, 1]; lo que venga después en la
+// cadena de post-proceso trabaja ya sobre color de pantalla
+impl PostEffect for ToneMapping {
+    fn name(&self) -> &'static str {
+        "tonemap"
+    }
+
+    fn apply(&self, fb: &mut Framebuffer, _time: f32) {
+        for c in fb.hdr_buffer.iter_mut() {
+            for v in &mut c[..3] {
+                *v = self.mapper.map(*v * self.exposure).clamp(0.0, 1.0);
+            }
+        }
+    }
+}
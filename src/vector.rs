@@ -12,7 +12,6 @@ impl Vector3 {
         Vector3 { x, y, z }
     }
 
-    #[allow(dead_code)]
     pub fn zero() -> Self {
         Vector3::new(0.0, 0.0, 0.0)
     }
@@ -0,0 +1,138 @@
+use crate::rasterizer::{unpack_color, Framebuffer};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+// Escritor de video YUV4MPEG2 (4:2:0, BT.601 rango limitado) para pasar los
+// frames directo a ffmpeg u otros encoders:
+//   reenderizar_nave --headless --y4m - | ffmpeg -i - sol.mp4
+pub struct Y4mWriter {
+    out: Box<dyn Write>,
+    width: usize,
+    height: usize,
+    // Planos reutilizados entre frames
+    y_plane: Vec<u8>,
+    u_plane: Vec<u8>,
+    v_plane: Vec<u8>,
+}
+
+impl Y4mWriter {
+    // `path` = "-" escribe a stdout
+    pub fn create(path: &str, width: usize, height: usize, dt: f32) -> io::Result<Self> {
+        let out: Box<dyn Write> = if path == "-" {
+            Box::new(BufWriter::new(io::stdout()))
+        } else {
+            Box::new(BufWriter::new(File::create(path)?))
+        };
+
+        let chroma_w = width.div_ceil(2);
+        let chroma_h = height.div_ceil(2);
+        let mut writer = Y4mWriter {
+            out,
+            width,
+            height,
+            y_plane: vec![0; width * height],
+            u_plane: vec![0; chroma_w * chroma_h],
+            v_plane: vec![0; chroma_w * chroma_h],
+        };
+
+        // Frame rate como fracción: 1/dt con tres decimales
+        let fps_num = (1000.0 / dt).round().max(1.0) as u32;
+        writeln!(
+            writer.out,
+            "YUV4MPEG2 W{} H{} F{}:1000 Ip A1:1 C420jpeg",
+            width, height, fps_num
+        )?;
+        Ok(writer)
+    }
+
+    pub fn write_frame(&mut self, fb: &Framebuffer) -> io::Result<()> {
+        let (w, h) = (self.width, self.height);
+        let chroma_w = w.div_ceil(2);
+
+        // Luma por píxel
+        for (y_out, &c) in self.y_plane.iter_mut().zip(&fb.color_buffer) {
+            let [r, g, b, _] = unpack_color(c);
+            *y_out = (16.0 + 65.481 * r + 128.553 * g + 24.966 * b).round() as u8;
+        }
+
+        // Croma promediando bloques 2x2 (sitio centrado, C420jpeg)
+        for cy in 0..h.div_ceil(2) {
+            for cx in 0..chroma_w {
+                let (mut r, mut g, mut b) = (0.0, 0.0, 0.0);
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let x = (cx * 2 + dx).min(w - 1);
+                    let y = (cy * 2 + dy).min(h - 1);
+                    let [pr, pg, pb, _] = unpack_color(fb.color_buffer[y * fb.width + x]);
+                    r += pr;
+                    g += pg;
+                    b += pb;
+                }
+                let (r, g, b) = (r * 0.25, g * 0.25, b * 0.25);
+                let i = cy * chroma_w + cx;
+                self.u_plane[i] = (128.0 - 37.797 * r - 74.203 * g + 112.0 * b).round() as u8;
+                self.v_plane[i] = (128.0 + 112.0 * r - 93.786 * g - 18.214 * b).round() as u8;
+            }
+        }
+
+        self.out.write_all(b"FRAME\n")?;
+        self.out.write_all(&self.y_plane)?;
+        self.out.write_all(&self.u_plane)?;
+        self.out.write_all(&self.v_plane)?;
+        Ok(())
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Escribir frames a un archivo temporal y devolver el stream completo
+    fn stream(name: &str, width: usize, height: usize, dt: f32, frames: &[&Framebuffer]) -> Vec<u8> {
+        let path = std::env::temp_dir().join(format!("reenderizar_nave_{}_{}.y4m", std::process::id(), name));
+        let path = path.to_str().unwrap();
+        let mut writer = Y4mWriter::create(path, width, height, dt).unwrap();
+        for fb in frames {
+            writer.write_frame(fb).unwrap();
+        }
+        writer.finish().unwrap();
+        drop(writer);
+        let bytes = std::fs::read(path).unwrap();
+        std::fs::remove_file(path).ok();
+        bytes
+    }
+
+    #[test]
+    fn header_carries_size_rate_and_chroma_siting() {
+        let bytes = stream("cabecera", 4, 2, 0.04, &[]);
+        assert_eq!(bytes, b"YUV4MPEG2 W4 H2 F25000:1000 Ip A1:1 C420jpeg\n");
+        let bytes = stream("cabecera30", 640, 480, 1.0 / 30.0, &[]);
+        assert!(bytes.starts_with(b"YUV4MPEG2 W640 H480 F30000:1000 "));
+    }
+
+    #[test]
+    fn frame_is_bt601_limited_range() {
+        const RED: u32 = 0xFF0000;
+        const WHITE: u32 = 0xFFFFFF;
+        const BLUE: u32 = 0x0000FF;
+        // 3x3: la croma de la última fila y columna repite el borde
+        let mut fb = Framebuffer::new(3, 3);
+        fb.color_buffer = vec![RED, RED, WHITE, 0, 0, 0, WHITE, 0, BLUE];
+
+        let bytes = stream("frame", 3, 3, 0.04, &[&fb]);
+        let header_len = bytes.iter().position(|&b| b == b'\n').unwrap() + 1;
+        let frame = &bytes[header_len..];
+        assert!(frame.starts_with(b"FRAME\n"));
+        let planes = &frame[6..];
+        assert_eq!(planes.len(), 9 + 4 + 4);
+
+        // Luma: blanco 235, negro 16, rojo 81, azul 41
+        assert_eq!(&planes[..9], &[81, 81, 235, 16, 16, 16, 235, 16, 41]);
+        // Croma 2x2: rojo/negro a medias, grises neutros y azul puro
+        assert_eq!(&planes[9..13], &[109, 128, 128, 240]);
+        assert_eq!(&planes[13..17], &[184, 128, 128, 110]);
+    }
+}
@@ -0,0 +1,168 @@
+#!/usr/bin/env python3
+# Genera los fixtures de los tests de image_io.rs con el zlib de Python (un
+# codificador independiente del nuestro). Cada PNG va con su .rgba: los
+# píxeles esperados en RGBA 8 bits, calculados acá sin pasar por el decoder.
+#   python3 testdata/generar_fixtures.py
+import os
+import struct
+import zlib
+
+DIR = os.path.dirname(os.path.abspath(__file__))
+
+
+def chunk(kind, data):
+    return struct.pack(">I", len(data)) + kind + data + struct.pack(">I", zlib.crc32(kind + data))
+
+
+def paeth(a, b, c):
+    p = a + b - c
+    pa, pb, pc = abs(p - a), abs(p - b), abs(p - c)
+    if pa <= pb and pa <= pc:
+        return a
+    return b if pb <= pc else c
+
+
+def filter_rows(rows, bpp, filters):
+    out = b""
+    previous = bytes(len(rows[0]))
+    for y, row in enumerate(rows):
+        kind = filters[y % len(filters)]
+        line = bytearray()
+        for i, x in enumerate(row):
+            a = row[i - bpp] if i >= bpp else 0
+            b = previous[i]
+            c = previous[i - bpp] if i >= bpp else 0
+            predicted = [0, a, b, (a + b) // 2, paeth(a, b, c)][kind]
+            line.append((x - predicted) & 0xFF)
+        out += bytes([kind]) + bytes(line)
+        previous = row
+    return out
+
+
+def compress(data, mode, pieces=1):
+    if mode == "stored":
+        c = zlib.compressobj(0)
+    elif mode == "fixed":
+        c = zlib.compressobj(9, zlib.DEFLATED, 15, 9, zlib.Z_FIXED)
+    else:
+        c = zlib.compressobj(9)
+    # Con varios trozos, cada Z_SYNC_FLUSH cierra el bloque actual y agrega un
+    # bloque vacío sin comprimir
+    out = b""
+    step = -(-len(data) // pieces)
+    for start in range(0, len(data), step):
+        out += c.compress(data[start:start + step])
+        if start + step < len(data):
+            out += c.flush(zlib.Z_SYNC_FLUSH)
+    return out + c.flush()
+
+
+def block_type(stream):
+    return (stream[2] >> 1) & 3
+
+
+def write_png(name, width, height, depth, color_type, rows, bpp, filters, expected,
+              mode="dynamic", extra=b"", split_idat=False):
+    ihdr = struct.pack(">IIBBBBB", width, height, depth, color_type, 0, 0, 0)
+    data = compress(filter_rows(rows, bpp, filters), mode)
+    png = b"\x89PNG\r\n\x1a\n" + chunk(b"IHDR", ihdr) + extra
+    if split_idat:
+        half = len(data) // 2
+        png += chunk(b"IDAT", data[:half]) + chunk(b"IDAT", data[half:])
+    else:
+        png += chunk(b"IDAT", data)
+    png += chunk(b"IEND", b"")
+    with open(os.path.join(DIR, name + ".png"), "wb") as f:
+        f.write(png)
+    with open(os.path.join(DIR, name + ".rgba"), "wb") as f:
+        f.write(bytes(expected))
+    print(name, len(png), "bytes, bloque", block_type(data))
+
+
+def pack_bits(samples, depth):
+    out = bytearray()
+    acc, bits = 0, 0
+    for s in samples:
+        acc = (acc << depth) | s
+        bits += depth
+        if bits == 8:
+            out.append(acc)
+            acc, bits = 0, 0
+    if bits:
+        out.append(acc << (8 - bits))
+    return bytes(out)
+
+
+def value(x, y, c):
+    return (x * 37 + y * 53 + c * 71 + x * y * 5) & 0xFF
+
+
+# RGB 8 bits, filtros 0-4 dos veces cada uno, IDAT partido en dos chunks
+w, h = 9, 10
+rows = [bytes(value(x, y, c) for x in range(w) for c in range(3)) for y in range(h)]
+expected = [v for y in range(h) for x in range(w) for v in (value(x, y, 0), value(x, y, 1), value(x, y, 2), 255)]
+write_png("rgb8_filtros", w, h, 8, 2, rows, 3, [0, 1, 2, 3, 4], expected, split_idat=True)
+
+# RGBA 8 bits, filtros en otro orden
+w, h = 6, 5
+rows = [bytes(value(x, y, c) for x in range(w) for c in range(4)) for y in range(h)]
+expected = [value(x, y, c) for y in range(h) for x in range(w) for c in range(4)]
+write_png("rgba8_filtros", w, h, 8, 6, rows, 4, [4, 3, 2, 1, 0], expected, mode="fixed")
+
+# Gris 16 bits con tRNS: el valor transparente aparece en la diagonal
+w, h = 7, 5
+gray16 = lambda x, y: 0x1234 if x == y else (x * 9001 + y * 4099) & 0xFFFF
+rows = [b"".join(struct.pack(">H", gray16(x, y)) for x in range(w)) for y in range(h)]
+expected = []
+for y in range(h):
+    for x in range(w):
+        g = gray16(x, y) >> 8
+        expected += [g, g, g, 0 if gray16(x, y) == 0x1234 else 255]
+write_png("gris16_trns", w, h, 16, 0, rows, 2, [1, 4, 2, 0, 3], expected, mode="fixed",
+          extra=chunk(b"tRNS", struct.pack(">H", 0x1234)))
+
+# RGBA 16 bits
+w, h = 5, 4
+rgba16 = lambda x, y, c: (value(x, y, c) << 8) | value(y, x, c + 1)
+rows = [b"".join(struct.pack(">H", rgba16(x, y, c)) for x in range(w) for c in range(4)) for y in range(h)]
+expected = [rgba16(x, y, c) >> 8 for y in range(h) for x in range(w) for c in range(4)]
+write_png("rgba16", w, h, 16, 6, rows, 8, [4, 2, 3, 1], expected)
+
+# Paleta de 4 bits (ancho impar: medio byte al final de cada fila) con tRNS
+# más corto que la paleta; bloques sin comprimir
+palette = [(255, 0, 0), (0, 255, 0), (0, 0, 255), (255, 255, 0), (0, 255, 255), (40, 40, 40)]
+alphas = [0, 128, 200]
+w, h = 5, 4
+index = lambda x, y: (x + 2 * y) % len(palette)
+rows = [pack_bits([index(x, y) for x in range(w)], 4) for y in range(h)]
+expected = []
+for y in range(h):
+    for x in range(w):
+        i = index(x, y)
+        expected += list(palette[i]) + [alphas[i] if i < len(alphas) else 255]
+plte = chunk(b"PLTE", bytes(v for c in palette for v in c))
+write_png("paleta4_trns", w, h, 4, 3, rows, 1, [0, 1, 2, 4], expected, mode="stored",
+          extra=plte + chunk(b"tRNS", bytes(alphas)))
+
+# Gris de 1 y 2 bits: las muestras se expanden a 0-255
+for depth, w in [(1, 11), (2, 7)]:
+    h = 3
+    levels = (1 << depth) - 1
+    gray = lambda x, y: (x * 3 + y) % (levels + 1)
+    rows = [pack_bits([gray(x, y) for x in range(w)], depth) for y in range(h)]
+    expected = []
+    for y in range(h):
+        for x in range(w):
+            g = gray(x, y) * 255 // levels
+            expected += [g, g, g, 255]
+    write_png("gris%d" % depth, w, h, depth, 0, rows, 1, [0, 2, 4], expected, mode="stored")
+
+# Streams zlib de cada tipo de bloque DEFLATE sobre el mismo texto (el test lo
+# arma igual): sin comprimir, Huffman fijo y dinámico, cada uno en tres tramos
+PHRASE = b"el sol sale por el este y se pone por el oeste; "
+text = bytes(PHRASE[(i * 7 // 5) % len(PHRASE)] ^ ((i // 997) & 3) for i in range(6000))
+for mode in ["stored", "fixed", "dynamic"]:
+    data = compress(text, mode, pieces=3)
+    with open(os.path.join(DIR, "texto_%s.zlib" % mode), "wb") as f:
+        f.write(data)
+    print(mode, len(data), "bytes, bloque", block_type(data))
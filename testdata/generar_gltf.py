@@ -0,0 +1,79 @@
+#!/usr/bin/env python3
+# Genera los fixtures glTF de los tests de gltf.rs: el mismo documento como
+# .gltf (buffer embebido en data URI) y como .glb (buffer en el chunk BIN).
+#   python3 testdata/generar_gltf.py
+import base64
+import json
+import math
+import os
+import struct
+
+DIR = os.path.dirname(os.path.abspath(__file__))
+
+
+def pad4(data, fill=b"\0"):
+    return data + fill * ((4 - len(data) % 4) % 4)
+
+
+# Triángulo en z = 0 (antihorario visto desde +z), tira de 4 vértices y
+# abanico de 5 con índices u8
+triangle = [(0, 0, 0), (1, 0, 0), (0, 1, 0)]
+strip = [(0, 0, 0), (1, 0, 0), (0, 1, 0), (1, 1, 0)]
+fan = [(0, 0, 0), (1, 0, 0), (1, 1, 0), (0, 1, 0), (-1, 1, 0)]
+fan_indices = [0, 1, 2, 3, 4]
+
+views, accessors = [], []
+buffer = b""
+
+
+def add(data, count, kind, component_type):
+    global buffer
+    views.append({"buffer": 0, "byteOffset": len(buffer), "byteLength": len(data)})
+    accessors.append({"bufferView": len(views) - 1, "componentType": component_type, "count": count, "type": kind})
+    buffer = pad4(buffer + data)
+    return len(accessors) - 1
+
+
+def positions(points):
+    return add(b"".join(struct.pack("<3f", *p) for p in points), len(points), "VEC3", 5126)
+
+
+triangle_pos = positions(triangle)
+strip_pos = positions(strip)
+fan_pos = positions(fan)
+fan_idx = add(bytes(fan_indices), len(fan_indices), "SCALAR", 5121)
+
+# Raíz con traslación, rotación de 90° en y y escala 2; su hijo con `matrix`
+# (traslación x + 1); un nodo espejado en x y los de tira y abanico
+s = math.sqrt(0.5)
+document = {
+    "asset": {"version": "2.0"},
+    "scene": 0,
+    "scenes": [{"nodes": [0, 2, 3, 4]}],
+    "nodes": [
+        {"name": "trs", "translation": [1, 2, 3], "rotation": [0, s, 0, s], "scale": [2, 2, 2], "mesh": 0, "children": [1]},
+        {"name": "matriz", "matrix": [1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 1, 0, 0, 1], "mesh": 0},
+        {"name": "espejo", "scale": [-1, 1, 1], "mesh": 0},
+        {"name": "tira", "mesh": 1},
+        {"name": "abanico", "mesh": 2},
+    ],
+    "meshes": [
+        {"primitives": [{"attributes": {"POSITION": triangle_pos}}]},
+        {"primitives": [{"attributes": {"POSITION": strip_pos}, "mode": 5}]},
+        {"primitives": [{"attributes": {"POSITION": fan_pos}, "indices": fan_idx, "mode": 6}]},
+    ],
+    "bufferViews": views,
+    "accessors": accessors,
+}
+
+embedded = dict(document, buffers=[{"byteLength": len(buffer), "uri": "data:application/octet-stream;base64," + base64.b64encode(buffer).decode()}])
+with open(os.path.join(DIR, "nodos.gltf"), "w") as f:
+    json.dump(embedded, f, indent=1)
+
+binary = dict(document, buffers=[{"byteLength": len(buffer)}])
+text = pad4(json.dumps(binary).encode(), b" ")
+glb = b"glTF" + struct.pack("<II", 2, 12 + 8 + len(text) + 8 + len(buffer))
+glb += struct.pack("<I", len(text)) + b"JSON" + text
+glb += struct.pack("<I", len(buffer)) + b"BIN\0" + buffer
+with open(os.path.join(DIR, "nodos.glb"), "wb") as f:
+    f.write(glb)